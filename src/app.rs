@@ -1,62 +1,196 @@
-use std::path::PathBuf;
 use std::sync::{Arc, RwLock, mpsc};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use eframe::egui;
 
-use crate::hotkey::start_hotkey_listener;
-use crate::keymap::map_egui_key_to_key;
-use crate::settings::{Action, Key, MouseButton, Settings, config_file_path, save_settings};
-use crate::worker::start_click_worker;
+use autoclicker::backend::select_backend;
+use autoclicker::doctor::{Check, Status, run_checks};
+use autoclicker::engine::{ClickerState, Engine, EngineEvent};
+use autoclicker::health::{Health, Subsystem};
+use autoclicker::hotkey::start_hotkey_listener;
+use autoclicker::interval::{Interval, IntervalUnit, round_significant};
+use autoclicker::keymap::map_egui_key_to_key;
+use autoclicker::persist::Persister;
+use autoclicker::settings::{
+    Action, ConfigLocation, Hotkey, HotkeyMode, Key, LoadError, MouseButton, Settings, TimingMode,
+    Trigger,
+};
+use autoclicker::validate::{Diagnostic, Severity, has_errors};
+use autoclicker::watch::{ConfigWatcher, start_config_watcher};
 
 #[derive(Debug)]
 pub struct AutoClickerApp {
     pub(crate) settings: Arc<RwLock<Settings>>,
-    pub(crate) config_path: PathBuf,
-    pub(crate) active_flag: Arc<RwLock<bool>>,
-    pub(crate) recording_hotkey: bool,
-    pub(crate) recording_action_key: bool,
-    pub(crate) last_save_error: Option<String>,
-    pub(crate) tx_wake: mpsc::Sender<()>,
+    pub(crate) config_location: Option<ConfigLocation>,
+    pub(crate) persister: Option<Persister>,
+    pub(crate) config_watcher: Option<ConfigWatcher>,
+    /// When the hotkey recording in progress gives up.
+    pub(crate) recording_hotkey: Option<Instant>,
+    pub(crate) recorded_hotkeys: mpsc::Receiver<Option<Hotkey>>,
+    /// Like `recording_hotkey`, for the key the action types.
+    pub(crate) recording_action_key: Option<Instant>,
+    /// Settings changes made by hotkeys, still to be saved.
+    pub(crate) adjusted_settings: mpsc::Receiver<String>,
+    /// The latest of those changes and when it was made, shown briefly in the status row.
+    pub(crate) last_adjustment: Option<(String, Instant)>,
+    /// Why the settings file could not be used at startup. While set, nothing is saved,
+    /// so the file stays as the user left it until they fix or overwrite it.
+    pub(crate) load_error: Option<LoadError>,
+    /// Signals a valid settings file picked up by the watcher.
+    pub(crate) reloaded: mpsc::Receiver<()>,
+    pub(crate) rejected_edit: Vec<Diagnostic>,
+    pub(crate) engine: Engine,
+    /// Name of the hotkey listener in use.
+    pub(crate) listener: &'static str,
+    /// Results of the last environment check, shown while the diagnostics window is open.
+    pub(crate) doctor_checks: Option<Vec<Check>>,
+    pub(crate) doctor_running: Option<mpsc::Receiver<Vec<Check>>>,
 }
 
 impl AutoClickerApp {
-    pub fn new() -> Self {
-        let config_path = config_file_path();
-        let settings = Arc::new(RwLock::new(crate::settings::load_settings(&config_path)));
-        let active_flag = Arc::new(RwLock::new(false));
+    /// Starts the app backed by `config_location`, or with in-memory settings only if `None`.
+    ///
+    /// The UI is only repainted when the engine, the persister or the watcher report a change.
+    pub fn new(config_location: Option<ConfigLocation>, ctx: &egui::Context) -> Self {
+        let (loaded, load_error) = match &config_location {
+            Some(loc) => autoclicker::settings::load_settings(&loc.path),
+            None => (Settings::default(), None),
+        };
+        let settings = Arc::new(RwLock::new(loaded));
 
-        let (tx_wake, rx_wake) = mpsc::channel::<()>();
+        let repaint = ctx.clone();
+        let (recorded_tx, recorded_hotkeys) = mpsc::channel();
+        let (adjusted_tx, adjusted_settings) = mpsc::channel();
+        let engine = Engine::builder(
+            Arc::clone(&settings),
+            select_backend(&settings.read().unwrap()),
+        )
+        .subscribe(move |event| {
+            match event {
+                EngineEvent::HotkeyRecorded(hotkey) => {
+                    let _ = recorded_tx.send(hotkey.clone());
+                }
+                EngineEvent::SettingsAdjusted(what) => {
+                    let _ = adjusted_tx.send(what.clone());
+                }
+                _ => {}
+            }
+            repaint.request_repaint();
+        })
+        .start();
+        let listener = start_hotkey_listener(engine.clone());
 
-        start_click_worker(Arc::clone(&settings), Arc::clone(&active_flag), rx_wake);
-        start_hotkey_listener(Arc::clone(&settings), Arc::clone(&active_flag));
+        let persister = config_location.as_ref().map(|loc| {
+            let repaint = ctx.clone();
+            Persister::spawn(loc.path.clone(), move || repaint.request_repaint())
+        });
+        let (reloaded_tx, reloaded) = mpsc::channel();
+        let config_watcher =
+            config_location
+                .as_ref()
+                .zip(persister.as_ref())
+                .and_then(|(loc, persister)| {
+                    let engine = engine.clone();
+                    start_config_watcher(
+                        loc.path.clone(),
+                        Arc::clone(&settings),
+                        persister.own_write_probe(),
+                        move |result| match result {
+                            Ok(()) => {
+                                let _ = reloaded_tx.send(());
+                                engine.settings_changed();
+                            }
+                            Err(e) => engine.settings_rejected(e.to_string()),
+                        },
+                    )
+                    .inspect_err(|e| tracing::warn!("Settings hot-reload unavailable: {}", e))
+                    .ok()
+                });
 
         Self {
             settings,
-            config_path,
-            active_flag,
-            recording_hotkey: false,
-            recording_action_key: false,
-            last_save_error: None,
-            tx_wake,
+            config_location,
+            persister,
+            config_watcher,
+            recording_hotkey: None,
+            recorded_hotkeys,
+            recording_action_key: None,
+            adjusted_settings,
+            last_adjustment: None,
+            load_error,
+            reloaded,
+            rejected_edit: Vec::new(),
+            engine,
+            listener,
+            doctor_checks: None,
+            doctor_running: None,
         }
     }
 
+    /// Applies an edit from the GUI. Edits that fail validation are rejected and
+    /// their diagnostics shown instead, so an unusable file is never written.
     fn with_settings_mut<F: FnOnce(&mut Settings)>(&mut self, f: F) {
-        {
-            let mut s = self.settings.write().unwrap();
-            f(&mut s);
+        // Under one write lock, like `Engine::adjust`, so hotkey adjustments and reloads
+        // in between are not lost.
+        let edited = {
+            let mut settings = self.settings.write().unwrap();
+            let mut edited = settings.clone();
+            f(&mut edited);
+            let diagnostics = edited.validate();
+            if has_errors(&diagnostics) {
+                Err(diagnostics)
+            } else {
+                *settings = edited.clone();
+                Ok(edited)
+            }
+        };
+        match edited {
+            Ok(edited) => {
+                self.rejected_edit.clear();
+                self.persist(edited);
+                self.engine.settings_changed();
+            }
+            Err(diagnostics) => {
+                self.rejected_edit = diagnostics
+                    .into_iter()
+                    .filter(Diagnostic::is_error)
+                    .collect();
+            }
         }
-        let s_clone = self.settings.read().unwrap().clone();
-        if let Err(e) = save_settings(&self.config_path, &s_clone) {
-            self.last_save_error = Some(e);
-        } else {
-            self.last_save_error = None;
-            let _ = self.tx_wake.send(());
+    }
+
+    fn persist(&self, settings: Settings) {
+        if self.load_error.is_none()
+            && let Some(persister) = &self.persister
+        {
+            persister.save(settings);
         }
     }
 }
 
+/// How long the Record button waits for a key before giving up.
+const RECORD_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long a change made by a hotkey stays in the status row.
+const ADJUSTMENT_SHOWN: Duration = Duration::from_secs(3);
+
+/// Values for `unit` that stay between 1 µs and 24 h.
+fn interval_range(unit: IntervalUnit) -> std::ops::RangeInclusive<f64> {
+    let a = Interval::new(1.0, IntervalUnit::Micros)
+        .converted(unit)
+        .value;
+    let b = Interval::new(24.0, IntervalUnit::Hours)
+        .converted(unit)
+        .value;
+    a.min(b)..=a.max(b)
+}
+
+impl Drop for AutoClickerApp {
+    fn drop(&mut self) {
+        self.engine.shutdown();
+    }
+}
+
 fn last_pressed_key(input: &egui::InputState) -> Option<egui::Key> {
     input.events.iter().rev().find_map(|e| match e {
         egui::Event::Key {
@@ -68,12 +202,46 @@ fn last_pressed_key(input: &egui::InputState) -> Option<egui::Key> {
 
 impl eframe::App for AutoClickerApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        while let Ok(recorded) = self.recorded_hotkeys.try_recv() {
+            let for_action = self.recording_action_key.take().is_some();
+            self.recording_hotkey = None;
+            match recorded {
+                Some(hotkey) if for_action => {
+                    let action = match hotkey.key {
+                        Some(Key::Mouse(button)) => Action::Mouse(button),
+                        Some(key) => Action::Keyboard(key),
+                        // Modifiers alone are not something to press.
+                        None => continue,
+                    };
+                    self.with_settings_mut(|s| s.action = action);
+                }
+                Some(hotkey) => self.with_settings_mut(|s| s.hotkey = hotkey),
+                None => {}
+            }
+        }
+
         let mut style = (*ctx.style()).clone();
         style.spacing.item_spacing = egui::vec2(6.0, 4.0);
         style.spacing.window_margin = egui::Margin::same(6);
         style.spacing.button_padding = egui::vec2(6.0, 4.0);
         ctx.set_style(style);
 
+        if self.reloaded.try_iter().count() > 0 {
+            self.load_error = None;
+        }
+        if let Some(what) = self.adjusted_settings.try_iter().last() {
+            self.persist(self.settings.read().unwrap().clone());
+            self.last_adjustment = Some((what, Instant::now()));
+            ctx.request_repaint_after(ADJUSTMENT_SHOWN);
+        }
+        if self
+            .last_adjustment
+            .as_ref()
+            .is_some_and(|(_, at)| at.elapsed() >= ADJUSTMENT_SHOWN)
+        {
+            self.last_adjustment = None;
+        }
+
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.spacing_mut().item_spacing = egui::vec2(6.0, 4.0);
 
@@ -86,9 +254,14 @@ impl eframe::App for AutoClickerApp {
                     self.ui_status_row(ui);
                     ui.end_row();
 
+                    ui.label("Input");
+                    self.ui_health_row(ui);
+                    ui.end_row();
+
                     ui.label("Hotkey");
                     self.ui_hotkey_row(ui);
                     ui.end_row();
+                    self.ui_diagnostic_rows(ui, true);
 
                     self.ui_action_rows(ui);
 
@@ -96,62 +269,245 @@ impl eframe::App for AutoClickerApp {
                     self.ui_interval_row(ui);
                     ui.end_row();
 
+                    ui.label("Timing");
+                    self.ui_timing_row(ui);
+                    ui.end_row();
+
+                    ui.label("Limit");
+                    self.ui_limit_row(ui);
+                    ui.end_row();
+
                     self.ui_error_row(ui);
                 });
         });
-
-        ctx.request_repaint_after(Duration::from_millis(80));
+        self.ui_doctor_window(ctx);
     }
 }
 
 impl AutoClickerApp {
+    fn ui_health_row(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            for (label, subsystem, backend) in [
+                ("Hotkeys", Subsystem::Listener, self.listener),
+                ("Clicking", Subsystem::Injector, self.engine.backend_name()),
+            ] {
+                let health = self.engine.health(subsystem);
+                let color = match health {
+                    Health::Starting => egui::Color32::GRAY,
+                    Health::Ok => egui::Color32::GREEN,
+                    Health::Degraded(_) => egui::Color32::YELLOW,
+                    Health::Failed(_) => egui::Color32::RED,
+                };
+                ui.label(egui::RichText::new("●").color(color))
+                    .on_hover_text(format!("{subsystem}: {health}"));
+                ui.label(format!("{label} ({backend})"))
+                    .on_hover_text(format!("{subsystem}: {health}"));
+            }
+            let button = egui::Button::new("Diagnose…").small();
+            if ui
+                .add_enabled(self.doctor_running.is_none(), button)
+                .on_hover_text("Check the session, device permissions and input injection")
+                .clicked()
+            {
+                self.start_doctor(ui.ctx());
+            }
+        });
+    }
+
+    /// Runs the checks off the UI thread; they may take a few seconds.
+    fn start_doctor(&mut self, ctx: &egui::Context) {
+        let settings = self.settings.read().unwrap().clone();
+        let repaint = ctx.clone();
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = tx.send(run_checks(&settings));
+            repaint.request_repaint();
+        });
+        self.doctor_running = Some(rx);
+        self.doctor_checks = Some(Vec::new());
+    }
+
+    fn ui_doctor_window(&mut self, ctx: &egui::Context) {
+        if let Some(rx) = &self.doctor_running
+            && let Ok(checks) = rx.try_recv()
+        {
+            self.doctor_checks = Some(checks);
+            self.doctor_running = None;
+        }
+        let Some(checks) = &self.doctor_checks else {
+            return;
+        };
+        let mut open = true;
+        egui::Window::new("Diagnostics")
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                if self.doctor_running.is_some() {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label("Checking…");
+                    });
+                    return;
+                }
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for check in checks {
+                        let color = match check.status {
+                            Status::Skip => egui::Color32::GRAY,
+                            Status::Ok => egui::Color32::GREEN,
+                            Status::Warn => egui::Color32::YELLOW,
+                            Status::Fail => egui::Color32::RED,
+                        };
+                        ui.horizontal_wrapped(|ui| {
+                            ui.label(egui::RichText::new("●").color(color));
+                            ui.strong(check.name);
+                            ui.label(&check.detail);
+                        });
+                        if let Some(fix) = &check.fix {
+                            ui.weak(format!("Fix: {fix}"));
+                        }
+                    }
+                });
+            });
+        if !open {
+            self.doctor_checks = None;
+            self.doctor_running = None;
+        }
+    }
+
     fn ui_status_row(&self, ui: &mut egui::Ui) {
-        let is_active = { *self.active_flag.read().unwrap() };
+        let state = self.engine.state();
         ui.horizontal(|ui| {
-            let (dot, color) = if is_active {
-                ("●", egui::Color32::GREEN)
-            } else {
-                ("●", egui::Color32::DARK_RED)
+            let color = match state {
+                ClickerState::Idle => egui::Color32::DARK_RED,
+                ClickerState::Arming | ClickerState::Paused => egui::Color32::YELLOW,
+                ClickerState::Running => egui::Color32::GREEN,
+                ClickerState::StoppedByLimit => egui::Color32::LIGHT_BLUE,
+                ClickerState::Error(_) => egui::Color32::RED,
             };
-            ui.label(egui::RichText::new(dot).color(color));
-            ui.label(if is_active { "ON" } else { "OFF" });
+            ui.label(egui::RichText::new("●").color(color));
+            ui.label(state.to_string());
+            if state != ClickerState::Idle {
+                ui.weak(format!("{} clicks", self.engine.clicks()));
+            }
+            if let Some(profile) = &self.settings.read().unwrap().profile {
+                ui.weak(format!("Profile {profile}"));
+            }
+            if let Some((what, _)) = &self.last_adjustment {
+                ui.colored_label(egui::Color32::LIGHT_BLUE, what)
+                    .on_hover_text("Changed by a hotkey");
+            }
+            if let Some(typed) = self.engine.pending_sequence() {
+                ui.colored_label(egui::Color32::YELLOW, format!("{typed}, …"))
+                    .on_hover_text("Waiting for the next key of a hotkey sequence");
+            }
+            if state.is_active() {
+                if ui.add(egui::Button::new("Pause").small()).clicked() {
+                    self.engine.pause();
+                }
+            } else if state == ClickerState::Paused
+                && ui.add(egui::Button::new("Resume").small()).clicked()
+            {
+                self.engine.resume();
+            }
+        });
+    }
+
+    /// Shows the countdown of a recording in progress. Returns whether the global listener
+    /// is down, in which case the caller records the keys egui sees instead.
+    fn ui_recording_prompt(&mut self, ui: &mut egui::Ui, what: &str, deadline: Instant) -> bool {
+        let left = deadline.saturating_duration_since(Instant::now());
+        // Again when the countdown shows one second less.
+        ui.ctx()
+            .request_repaint_after(left.saturating_sub(Duration::from_secs(left.as_secs())));
+        ui.horizontal(|ui| {
+            ui.colored_label(
+                egui::Color32::YELLOW,
+                format!(
+                    "Press the new {what} now… {}s (Esc cancels)",
+                    left.as_secs() + 1
+                ),
+            );
+            if ui.add(egui::Button::new("Cancel").small()).clicked() {
+                self.engine.cancel_recording();
+            }
         });
+        matches!(self.engine.health(Subsystem::Listener), Health::Failed(_))
     }
 
     fn ui_hotkey_row(&mut self, ui: &mut egui::Ui) {
         let s = self.settings.read().unwrap().clone();
         let mut hot = s.hotkey.clone();
-        if self.recording_hotkey {
-            ui.horizontal(|ui| {
-                ui.colored_label(egui::Color32::YELLOW, "Recording…");
-                if ui.add(egui::Button::new("Cancel").small()).clicked() {
-                    self.recording_hotkey = false;
-                }
-            });
-
+        if let Some(deadline) = self.recording_hotkey {
+            if !self.ui_recording_prompt(ui, "hotkey", deadline) {
+                return;
+            }
             let input = ui.input(|i| i.clone());
-            let eg_ctrl = input.modifiers.ctrl;
-            let eg_alt = input.modifiers.alt;
-            let eg_shift = input.modifiers.shift;
-            let eg_mac_cmd = input.modifiers.mac_cmd;
             if let Some(ev) = last_pressed_key(&input)
                 && let Some(k) = map_egui_key_to_key(ev)
             {
-                hot.ctrl = eg_ctrl;
-                hot.alt = eg_alt;
-                hot.shift = eg_shift;
-                hot.meta = eg_mac_cmd;
-                hot.key = k;
-                self.recording_hotkey = false;
+                hot = Hotkey {
+                    ctrl: input.modifiers.ctrl,
+                    alt: input.modifiers.alt,
+                    shift: input.modifiers.shift,
+                    meta: input.modifiers.mac_cmd,
+                    extra_modifiers: hot.extra_modifiers,
+                    key: Some(k),
+                    mode: hot.mode,
+                    ..Hotkey::default_toggle()
+                };
+                self.engine.cancel_recording();
+                self.recording_hotkey = None;
                 self.with_settings_mut(|s| s.hotkey = hot);
             }
         } else {
+            let mut mode = hot.mode;
+            let mut trigger = hot.trigger;
+            let mut extra = hot.extra_modifiers;
+            let mut suppress = s.suppress_hotkeys;
             ui.horizontal(|ui| {
                 ui.monospace(format!("{}", hot));
-                if ui.add(egui::Button::new("Record").small()).clicked() {
-                    self.recording_hotkey = true;
+                if ui
+                    .add(egui::Button::new("Record").small())
+                    .on_hover_text("Press any key or mouse button, with modifiers")
+                    .clicked()
+                {
+                    self.engine.record_hotkey(RECORD_TIMEOUT);
+                    self.recording_hotkey = Some(Instant::now() + RECORD_TIMEOUT);
                 }
+                ui.radio_value(&mut mode, HotkeyMode::Toggle, "Toggle")
+                    .on_hover_text("Each press switches clicking on or off");
+                ui.radio_value(&mut mode, HotkeyMode::Hold, "Hold")
+                    .on_hover_text("Clicks only while the hotkey is held down");
+                egui::ComboBox::from_id_salt("hotkey_trigger")
+                    .selected_text(trigger.to_string())
+                    .show_ui(ui, |ui| {
+                        for t in Trigger::ALL {
+                            ui.selectable_value(&mut trigger, t, t.to_string());
+                        }
+                    })
+                    .response
+                    .on_hover_text(format!(
+                        "Tap: let go within {} ms\nDouble-tap: second tap within {} ms\nLong press: hold for {} ms",
+                        hot.timing.tap_ms, hot.timing.double_tap_ms, hot.timing.long_press_ms
+                    ));
+                ui.checkbox(&mut extra, "Extra modifiers")
+                    .on_hover_text("Also react while other modifiers are held");
+                ui.checkbox(&mut suppress, "Suppress").on_hover_text(
+                    "Keep hotkeys from reaching the focused window; their modifiers still do (Linux, evdev and uinput; takes effect on the next start)",
+                );
             });
+            if mode != hot.mode {
+                self.with_settings_mut(|s| s.hotkey.mode = mode);
+            }
+            if trigger != hot.trigger {
+                self.with_settings_mut(|s| s.hotkey.trigger = trigger);
+            }
+            if extra != hot.extra_modifiers {
+                self.with_settings_mut(|s| s.hotkey.extra_modifiers = extra);
+            }
+            if suppress != s.suppress_hotkeys {
+                self.with_settings_mut(|s| s.suppress_hotkeys = suppress);
+            }
         }
     }
 
@@ -188,15 +544,11 @@ impl AutoClickerApp {
             _ => MouseButton::Left,
         };
         egui::ComboBox::from_id_salt("mouse_button")
-            .selected_text(match btn {
-                MouseButton::Left => "Left",
-                MouseButton::Right => "Right",
-                MouseButton::Middle => "Middle",
-            })
+            .selected_text(btn.to_str())
             .show_ui(ui, |ui| {
-                ui.selectable_value(&mut btn, MouseButton::Left, "Left");
-                ui.selectable_value(&mut btn, MouseButton::Right, "Right");
-                ui.selectable_value(&mut btn, MouseButton::Middle, "Middle");
+                for button in MouseButton::ALL {
+                    ui.selectable_value(&mut btn, button, button.to_str());
+                }
             });
         Action::Mouse(btn)
     }
@@ -206,8 +558,10 @@ impl AutoClickerApp {
             Action::Keyboard(k) => k,
             _ => Key::Space,
         };
-        if self.recording_action_key {
-            self.ui_keyboard_recording(ui, &mut k);
+        if let Some(deadline) = self.recording_action_key {
+            if self.ui_recording_prompt(ui, "key", deadline) {
+                self.ui_keyboard_recording(ui, &mut k);
+            }
         } else {
             self.ui_keyboard_picker(ui, &mut k);
         }
@@ -215,18 +569,13 @@ impl AutoClickerApp {
     }
 
     fn ui_keyboard_recording(&mut self, ui: &mut egui::Ui, k: &mut Key) {
-        ui.horizontal(|ui| {
-            ui.colored_label(egui::Color32::YELLOW, "Recording…");
-            if ui.add(egui::Button::new("Cancel").small()).clicked() {
-                self.recording_action_key = false;
-            }
-        });
         let input = ui.input(|i| i.clone());
         if let Some(ev) = last_pressed_key(&input)
             && let Some(newk) = map_egui_key_to_key(ev)
         {
             *k = newk;
-            self.recording_action_key = false;
+            self.engine.cancel_recording();
+            self.recording_action_key = None;
         }
     }
 
@@ -260,55 +609,144 @@ impl AutoClickerApp {
                     ui.selectable_value(k, Key::Enter, "Enter");
                     ui.selectable_value(k, Key::Escape, "Escape");
                 });
-            if ui.add(egui::Button::new("Record").small()).clicked() {
-                self.recording_action_key = true;
+            if ui
+                .add(egui::Button::new("Record").small())
+                .on_hover_text("Press any key, including media and F13–F24 keys")
+                .clicked()
+            {
+                self.engine.record_hotkey(RECORD_TIMEOUT);
+                self.recording_action_key = Some(Instant::now() + RECORD_TIMEOUT);
             }
         });
     }
 
     fn ui_interval_row(&mut self, ui: &mut egui::Ui) {
-        let s_clone = self.settings.read().unwrap().clone();
-        let mut ms = s_clone.interval_ms as f64;
-        let mut cps = if ms > 0.0 { 1000.0 / ms } else { 0.0 };
+        let current = self.settings.read().unwrap().interval;
+        let mut interval = current;
         ui.horizontal(|ui| {
-            ui.label("ms");
-            let ms_changed = ui
-                .add_sized(
-                    [90.0, 22.0],
-                    egui::DragValue::new(&mut ms)
-                        .speed(1.0)
-                        .range(1.0..=10_000.0),
-                )
-                .changed();
-            ui.add_space(8.0);
-            ui.label("cps");
-            let cps_changed = ui
-                .add_sized(
-                    [90.0, 22.0],
-                    egui::DragValue::new(&mut cps)
-                        .speed(0.1)
-                        .range(0.1..=1000.0),
-                )
-                .changed();
-
-            if ms_changed {
-                ms = ms.clamp(1.0, 10_000.0);
-                self.with_settings_mut(|s| s.interval_ms = ms.round() as u64);
-                cps = (1000.0 / ms).clamp(0.1, 1000.0);
-            }
-            if cps_changed {
-                cps = cps.clamp(0.1, 1000.0);
-                let new_ms = (1000.0 / cps).round() as u64;
-                self.with_settings_mut(|s| s.interval_ms = new_ms.max(1));
-            }
+            let speed = (interval.value.abs() * 0.01).max(0.01);
+            ui.add_sized(
+                [90.0, 22.0],
+                egui::DragValue::new(&mut interval.value)
+                    .speed(speed)
+                    .range(interval_range(interval.unit)),
+            );
+            egui::ComboBox::from_id_salt("interval_unit")
+                .width(56.0)
+                .selected_text(interval.unit.label())
+                .show_ui(ui, |ui| {
+                    for unit in IntervalUnit::ALL {
+                        if ui
+                            .selectable_label(interval.unit == unit, unit.label())
+                            .clicked()
+                        {
+                            let converted = interval.converted(unit);
+                            interval = Interval::new(round_significant(converted.value), unit);
+                        }
+                    }
+                });
+            let equivalent = match interval.unit {
+                IntervalUnit::Cps | IntervalUnit::Cpm => format!("= {:?}", interval.as_duration()),
+                _ => format!("= {} cps", round_significant(interval.per_second())),
+            };
+            ui.weak(equivalent);
+        });
+        if interval != current {
+            self.with_settings_mut(|s| s.interval = interval);
+        }
+    }
+
+    fn ui_timing_row(&mut self, ui: &mut egui::Ui) {
+        let current = self.settings.read().unwrap().timing;
+        let mut timing = current;
+        ui.horizontal(|ui| {
+            ui.radio_value(&mut timing, TimingMode::FixedRate, "Fixed rate")
+                .on_hover_text("Clicks land on a fixed grid; time spent clicking is not added");
+            ui.radio_value(&mut timing, TimingMode::FixedDelay, "Fixed delay")
+                .on_hover_text("Waits the full interval after each click finishes");
+        });
+        if timing != current {
+            self.with_settings_mut(|s| s.timing = timing);
+        }
+    }
+
+    fn ui_limit_row(&mut self, ui: &mut egui::Ui) {
+        let current = self.settings.read().unwrap().click_limit;
+        let mut limited = current.is_some();
+        let mut limit = current.unwrap_or(100);
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut limited, "Stop after");
+            ui.add_enabled(
+                limited,
+                egui::DragValue::new(&mut limit)
+                    .speed(1.0)
+                    .range(1..=u64::MAX),
+            );
+            ui.label("clicks");
         });
+        let limit = limited.then_some(limit);
+        if limit != current {
+            self.with_settings_mut(|s| s.click_limit = limit);
+        }
     }
 
-    fn ui_error_row(&self, ui: &mut egui::Ui) {
-        if let Some(err) = &self.last_save_error {
+    fn ui_error_row(&mut self, ui: &mut egui::Ui) {
+        if self.config_location.is_none() {
+            ui.label("");
+            ui.colored_label(
+                egui::Color32::YELLOW,
+                "No config location found; settings will not be saved",
+            );
+            ui.end_row();
+        }
+        if let Some(err) = self.persister.as_ref().and_then(|p| p.last_error()) {
             ui.label("");
             ui.colored_label(egui::Color32::RED, format!("Save error: {err}"));
             ui.end_row();
         }
+        if let Some(err) = self.config_watcher.as_ref().and_then(|w| w.last_error()) {
+            ui.label("");
+            ui.colored_label(egui::Color32::RED, format!("Reload error: {err}"));
+            ui.end_row();
+        }
+        if let Some(err) = &self.load_error {
+            ui.label("");
+            ui.colored_label(
+                egui::Color32::RED,
+                format!("Load error: {err} (using defaults; changes are not saved until the file is fixed)"),
+            );
+            ui.end_row();
+            ui.label("");
+            if ui
+                .button("Overwrite file")
+                .on_hover_text("Save the settings shown here; the old file is kept as a backup")
+                .clicked()
+            {
+                self.load_error = None;
+                self.persist(self.settings.read().unwrap().clone());
+            }
+            ui.end_row();
+        }
+        self.ui_diagnostic_rows(ui, false);
+    }
+
+    /// Problems with the settings, either those of the hotkeys (shown under the Hotkey
+    /// row, with conflicts between them) or all others.
+    fn ui_diagnostic_rows(&self, ui: &mut egui::Ui, hotkeys: bool) {
+        let current = self.settings.read().unwrap().validate();
+        for d in self
+            .rejected_edit
+            .iter()
+            .chain(&current)
+            .filter(|d| d.is_about_hotkeys() == hotkeys)
+        {
+            let color = match d.severity {
+                Severity::Error => egui::Color32::RED,
+                Severity::Warning => egui::Color32::YELLOW,
+            };
+            ui.label("");
+            ui.colored_label(color, format!("{}: {}", d.path, d.message));
+            ui.end_row();
+        }
     }
 }
@@ -0,0 +1,757 @@
+use std::fmt::{Display, Formatter};
+
+use tracing::{debug, info};
+
+use crate::session::Session;
+use crate::settings::{Action, InputInjector, Settings};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackendError(pub String);
+
+impl Display for BackendError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for BackendError {}
+
+/// Injects the configured action into the system.
+///
+/// A backend is moved onto the click worker thread and used only from there, so
+/// connections can be opened lazily in [`Backend::init`].
+pub trait Backend: Send {
+    /// Short name for logs and the UI, e.g. `"enigo"`.
+    fn name(&self) -> &'static str;
+
+    /// Prepares the backend; called on the worker thread before the first action.
+    fn init(&mut self) -> Result<(), BackendError> {
+        Ok(())
+    }
+
+    fn perform(&mut self, action: &Action) -> Result<(), BackendError>;
+
+    /// Sends anything [`Backend::perform`] buffered. At high rates the worker calls this
+    /// once per batch of actions rather than after each one.
+    fn flush(&mut self) -> Result<(), BackendError> {
+        Ok(())
+    }
+
+    /// Drops any connection so the next [`Backend::init`] reconnects from scratch.
+    fn reset(&mut self) {}
+}
+
+impl<B: Backend + ?Sized> Backend for Box<B> {
+    fn name(&self) -> &'static str {
+        (**self).name()
+    }
+
+    fn init(&mut self) -> Result<(), BackendError> {
+        (**self).init()
+    }
+
+    fn perform(&mut self, action: &Action) -> Result<(), BackendError> {
+        (**self).perform(action)
+    }
+
+    fn flush(&mut self) -> Result<(), BackendError> {
+        (**self).flush()
+    }
+
+    fn reset(&mut self) {
+        (**self).reset()
+    }
+}
+
+/// Performs nothing. Useful for dry runs and for embedding the engine without input access.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NullBackend;
+
+impl Backend for NullBackend {
+    fn name(&self) -> &'static str {
+        "none"
+    }
+
+    fn perform(&mut self, _action: &Action) -> Result<(), BackendError> {
+        Ok(())
+    }
+}
+
+/// The backend [`InputInjector::Auto`] picks for this session.
+pub fn default_backend() -> Box<dyn Backend> {
+    select_backend(&Settings::default())
+}
+
+/// Builds the backend for the `injector` setting. `Auto` probes the candidates for
+/// `Session::detect()` in order and takes the first that connects, or the first one if
+/// none does, so its error shows up in the injector health.
+pub fn select_backend(settings: &Settings) -> Box<dyn Backend> {
+    let candidates: Vec<_> = candidates(settings.injector, Session::detect())
+        .into_iter()
+        .filter_map(|kind| create(kind, settings).map(|backend| (kind, backend)))
+        .collect();
+    if candidates.len() <= 1 {
+        return match candidates.into_iter().next() {
+            Some((_, backend)) => backend,
+            None => Box::new(NullBackend),
+        };
+    }
+
+    let mut first = None;
+    for (kind, mut backend) in candidates {
+        match backend.init() {
+            Ok(()) => {
+                // Connect again lazily on the worker thread.
+                backend.reset();
+                info!("Injecting input with {}", backend.name());
+                return backend;
+            }
+            Err(e) => {
+                debug!("{} backend unavailable: {}", backend.name(), e);
+                // ydotoold is only used when it happens to run; its absence is no problem.
+                if kind != InputInjector::Ydotool {
+                    first.get_or_insert(backend);
+                }
+            }
+        }
+    }
+    first.unwrap_or_else(|| Box::new(NullBackend))
+}
+
+/// Backends to try for `choice` in `session`, best first. An explicit choice that this
+/// platform or build lacks falls back to the automatic order.
+pub fn candidates(choice: InputInjector, session: Session) -> Vec<InputInjector> {
+    let available = |kind: InputInjector| match kind {
+        InputInjector::Enigo => cfg!(feature = "clicking_enigo"),
+        InputInjector::Uinput | InputInjector::Xtest | InputInjector::Ydotool => {
+            cfg!(target_os = "linux")
+        }
+        InputInjector::Auto | InputInjector::None => true,
+    };
+    if choice != InputInjector::Auto && available(choice) {
+        return vec![choice];
+    }
+    let order: &[InputInjector] = match session {
+        // A running ydotoold is used in preference to a device of our own.
+        Session::Wayland => &[
+            InputInjector::Ydotool,
+            InputInjector::Uinput,
+            InputInjector::Xtest,
+            InputInjector::Enigo,
+        ],
+        Session::Headless => &[InputInjector::Ydotool, InputInjector::Uinput],
+        Session::X11 => &[
+            InputInjector::Xtest,
+            InputInjector::Enigo,
+            InputInjector::Uinput,
+        ],
+        Session::Native => &[InputInjector::Enigo],
+    };
+    let order: Vec<_> = order.iter().copied().filter(|&k| available(k)).collect();
+    if order.is_empty() {
+        vec![InputInjector::None]
+    } else {
+        order
+    }
+}
+
+fn create(kind: InputInjector, settings: &Settings) -> Option<Box<dyn Backend>> {
+    match kind {
+        #[cfg(feature = "clicking_enigo")]
+        InputInjector::Enigo => Some(Box::new(EnigoBackend::default())),
+        #[cfg(target_os = "linux")]
+        InputInjector::Uinput => Some(Box::new(UinputBackend::default())),
+        #[cfg(target_os = "linux")]
+        InputInjector::Xtest => Some(Box::new(XTestBackend::default())),
+        #[cfg(target_os = "linux")]
+        InputInjector::Ydotool => Some(Box::new(YdotoolBackend::new(
+            settings
+                .ydotool_socket
+                .clone()
+                .unwrap_or_else(ydotool_backend::default_socket_path),
+        ))),
+        InputInjector::None => Some(Box::new(NullBackend)),
+        _ => None,
+    }
+}
+
+#[cfg(feature = "clicking_enigo")]
+pub use self::enigo_backend::EnigoBackend;
+
+#[cfg(feature = "clicking_enigo")]
+mod enigo_backend {
+    use enigo::{Button as EButton, Direction as EDir, Enigo, Keyboard as _, Mouse as _};
+
+    use super::{Backend, BackendError};
+    use crate::settings::{Action, Key, MouseButton};
+
+    #[derive(Default)]
+    pub struct EnigoBackend {
+        enigo: Option<Enigo>,
+    }
+
+    impl std::fmt::Debug for EnigoBackend {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("EnigoBackend")
+                .field("connected", &self.enigo.is_some())
+                .finish()
+        }
+    }
+
+    impl Backend for EnigoBackend {
+        fn name(&self) -> &'static str {
+            "enigo"
+        }
+
+        fn init(&mut self) -> Result<(), BackendError> {
+            if self.enigo.is_none() {
+                let enigo = Enigo::new(&enigo::Settings::default())
+                    .map_err(|e| BackendError(format!("input injection unavailable: {e}")))?;
+                self.enigo = Some(enigo);
+            }
+            Ok(())
+        }
+
+        fn perform(&mut self, action: &Action) -> Result<(), BackendError> {
+            self.init()?;
+            let enigo = self.enigo.as_mut().expect("initialized above");
+            let result = match action {
+                Action::Mouse(button) => enigo.button(map_button_to_enigo(*button), EDir::Click),
+                Action::Keyboard(k) => match k {
+                    Key::Char(c) => enigo.text(&c.to_string()),
+                    Key::Space => enigo.text(" "),
+                    Key::Enter => enigo.text("\n"),
+                    // X11 keycodes are Linux key codes offset by 8.
+                    #[cfg(target_os = "linux")]
+                    Key::Raw { code, .. } => enigo.raw(code.saturating_add(8), EDir::Click),
+                    #[cfg(not(target_os = "linux"))]
+                    Key::Raw { name, .. } => {
+                        return Err(BackendError(format!(
+                            "{name} is a Linux key code and can only be typed on Linux"
+                        )));
+                    }
+                    _ => match map_key_to_enigo(k) {
+                        Some(ek) => enigo.key(ek, EDir::Click),
+                        None => Ok(()),
+                    },
+                },
+            };
+            result.map_err(|e| BackendError(e.to_string()))
+        }
+
+        fn reset(&mut self) {
+            self.enigo = None;
+        }
+    }
+
+    fn map_button_to_enigo(button: MouseButton) -> EButton {
+        match button {
+            MouseButton::Left => EButton::Left,
+            MouseButton::Right => EButton::Right,
+            MouseButton::Middle => EButton::Middle,
+            MouseButton::ScrollUp => EButton::ScrollUp,
+            MouseButton::ScrollDown => EButton::ScrollDown,
+            MouseButton::ScrollLeft => EButton::ScrollLeft,
+            MouseButton::ScrollRight => EButton::ScrollRight,
+            MouseButton::Back => EButton::Back,
+            MouseButton::Forward => EButton::Forward,
+        }
+    }
+
+    fn map_key_to_enigo(key: &Key) -> Option<enigo::Key> {
+        match key {
+            Key::Char(c) => Some(enigo::Key::Unicode(*c)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub use self::uinput_backend::{DEVICE_NAME as UINPUT_DEVICE_NAME, UinputBackend};
+
+#[cfg(target_os = "linux")]
+mod uinput_backend {
+    use std::thread;
+    use std::time::Duration;
+
+    use evdev::uinput::VirtualDevice;
+    use evdev::{AttributeSet, EventType, InputEvent, KeyCode, RelativeAxisCode};
+
+    use super::{Backend, BackendError};
+    use crate::keymap::map_key_to_evdev;
+    use crate::settings::{Action, Key, MouseButton};
+
+    pub const DEVICE_NAME: &str = "autoclicker";
+    /// Compositors need a moment to pick up a new device; earlier events are lost.
+    const DEVICE_SETTLE: Duration = Duration::from_millis(200);
+
+    /// Injects through a virtual mouse and keyboard created with `/dev/uinput`, which works
+    /// under Wayland and on the console. Keys are typed as on a US layout.
+    #[derive(Default)]
+    pub struct UinputBackend {
+        device: Option<VirtualDevice>,
+    }
+
+    impl std::fmt::Debug for UinputBackend {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("UinputBackend")
+                .field("connected", &self.device.is_some())
+                .finish()
+        }
+    }
+
+    impl Backend for UinputBackend {
+        fn name(&self) -> &'static str {
+            "uinput"
+        }
+
+        fn init(&mut self) -> Result<(), BackendError> {
+            if self.device.is_none() {
+                let device = create_device()
+                    .map_err(|e| BackendError(format!("cannot create a uinput device: {e}")))?;
+                thread::sleep(DEVICE_SETTLE);
+                self.device = Some(device);
+            }
+            Ok(())
+        }
+
+        fn perform(&mut self, action: &Action) -> Result<(), BackendError> {
+            self.init()?;
+            let device = self.device.as_mut().expect("initialized above");
+            let (code, shift) = match action {
+                Action::Mouse(MouseButton::Left) => (KeyCode::BTN_LEFT, false),
+                Action::Mouse(MouseButton::Right) => (KeyCode::BTN_RIGHT, false),
+                Action::Mouse(MouseButton::Middle) => (KeyCode::BTN_MIDDLE, false),
+                Action::Mouse(MouseButton::Back) => (KeyCode::BTN_SIDE, false),
+                Action::Mouse(MouseButton::Forward) => (KeyCode::BTN_EXTRA, false),
+                Action::Mouse(scroll) => {
+                    let (axis, value) = match scroll {
+                        MouseButton::ScrollUp => (RelativeAxisCode::REL_WHEEL, 1),
+                        MouseButton::ScrollDown => (RelativeAxisCode::REL_WHEEL, -1),
+                        MouseButton::ScrollLeft => (RelativeAxisCode::REL_HWHEEL, -1),
+                        _ => (RelativeAxisCode::REL_HWHEEL, 1),
+                    };
+                    return device
+                        .emit(&[InputEvent::new(EventType::RELATIVE.0, axis.0, value)])
+                        .map_err(|e| BackendError(e.to_string()));
+                }
+                Action::Keyboard(key) => {
+                    let code = map_key_to_evdev(key).ok_or_else(|| {
+                        BackendError(format!("uinput cannot type {}", key.to_str()))
+                    })?;
+                    let shift = matches!(key, Key::Char(c) if c.is_ascii_uppercase());
+                    (code, shift)
+                }
+            };
+            let result = if shift {
+                press(device, KeyCode::KEY_LEFTSHIFT, 1)
+                    .and_then(|()| click(device, code))
+                    .and_then(|()| press(device, KeyCode::KEY_LEFTSHIFT, 0))
+            } else {
+                click(device, code)
+            };
+            result.map_err(|e| BackendError(e.to_string()))
+        }
+
+        fn reset(&mut self) {
+            self.device = None;
+        }
+    }
+
+    fn create_device() -> std::io::Result<VirtualDevice> {
+        let mut keys = AttributeSet::<KeyCode>::new();
+        for code in [
+            KeyCode::BTN_LEFT,
+            KeyCode::BTN_RIGHT,
+            KeyCode::BTN_MIDDLE,
+            KeyCode::BTN_SIDE,
+            KeyCode::BTN_EXTRA,
+        ] {
+            keys.insert(code);
+        }
+        // Every keyboard key, so raw key codes can be typed too. Joystick and gamepad
+        // buttons are left out, or desktops would treat the device as a game controller.
+        for code in (1..KeyCode::BTN_0.0)
+            .chain(KeyCode::KEY_OK.0..KeyCode::BTN_DPAD_UP.0)
+            .chain(KeyCode::KEY_ALS_TOGGLE.0..KeyCode::BTN_TRIGGER_HAPPY1.0)
+        {
+            keys.insert(KeyCode::new(code));
+        }
+        // Without motion axes desktops do not treat the buttons as a mouse.
+        let mut axes = AttributeSet::<RelativeAxisCode>::new();
+        for axis in [
+            RelativeAxisCode::REL_X,
+            RelativeAxisCode::REL_Y,
+            RelativeAxisCode::REL_WHEEL,
+            RelativeAxisCode::REL_HWHEEL,
+        ] {
+            axes.insert(axis);
+        }
+
+        VirtualDevice::builder()?
+            .name(DEVICE_NAME)
+            .with_keys(&keys)?
+            .with_relative_axes(&axes)?
+            .build()
+    }
+
+    fn press(device: &mut VirtualDevice, code: KeyCode, value: i32) -> std::io::Result<()> {
+        device.emit(&[InputEvent::new(EventType::KEY.0, code.0, value)])
+    }
+
+    fn click(device: &mut VirtualDevice, code: KeyCode) -> std::io::Result<()> {
+        press(device, code, 1)?;
+        press(device, code, 0)
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub use self::xtest_backend::XTestBackend;
+
+#[cfg(target_os = "linux")]
+mod xtest_backend {
+    use std::collections::HashMap;
+
+    use x11rb::connection::{Connection, RequestConnection};
+    use x11rb::protocol::Event;
+    use x11rb::protocol::xproto::{
+        BUTTON_PRESS_EVENT, BUTTON_RELEASE_EVENT, ConnectionExt as _, KEY_PRESS_EVENT,
+        KEY_RELEASE_EVENT, Keycode, Keysym, Window,
+    };
+    use x11rb::protocol::xtest::{self, ConnectionExt as _};
+    use x11rb::rust_connection::RustConnection;
+
+    use super::{Backend, BackendError};
+    use crate::settings::{Action, Key};
+
+    const XK_SHIFT_L: Keysym = 0xffe1;
+
+    /// Injects with the XTest extension over a direct X11 connection. Keys are resolved
+    /// through the server's current keyboard mapping, so they match the active layout.
+    ///
+    /// Requests are only queued by [`Backend::perform`]; the worker flushes them.
+    #[derive(Default)]
+    pub struct XTestBackend {
+        connection: Option<Connected>,
+    }
+
+    struct Connected {
+        conn: RustConnection,
+        root: Window,
+        keymap: Keymap,
+    }
+
+    impl std::fmt::Debug for XTestBackend {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("XTestBackend")
+                .field("connected", &self.connection.is_some())
+                .finish()
+        }
+    }
+
+    impl Backend for XTestBackend {
+        fn name(&self) -> &'static str {
+            "xtest"
+        }
+
+        fn init(&mut self) -> Result<(), BackendError> {
+            if self.connection.is_none() {
+                self.connection = Some(Connected::open()?);
+            }
+            Ok(())
+        }
+
+        fn perform(&mut self, action: &Action) -> Result<(), BackendError> {
+            self.init()?;
+            let c = self.connection.as_mut().expect("initialized above");
+            c.reload_keymap_if_changed()?;
+            match action {
+                Action::Mouse(button) => {
+                    let button = button.x11_button();
+                    c.fake(BUTTON_PRESS_EVENT, button)?;
+                    c.fake(BUTTON_RELEASE_EVENT, button)
+                }
+                Action::Keyboard(Key::Raw { code, name }) => {
+                    // X servers on Linux number keys as the kernel does, offset by 8.
+                    let keycode = u8::try_from(code.saturating_add(8)).map_err(|_| {
+                        BackendError(format!("{name} is out of the X11 keycode range"))
+                    })?;
+                    c.fake(KEY_PRESS_EVENT, keycode)?;
+                    c.fake(KEY_RELEASE_EVENT, keycode)
+                }
+                Action::Keyboard(key) => {
+                    let keysym = keysym(key);
+                    let (keycode, shift) =
+                        c.keymap.keys.get(&keysym).copied().ok_or_else(|| {
+                            BackendError(format!(
+                                "{} is not on the current keyboard layout",
+                                key.to_str()
+                            ))
+                        })?;
+                    let shift = if shift { c.keymap.shift } else { None };
+                    if let Some(shift) = shift {
+                        c.fake(KEY_PRESS_EVENT, shift)?;
+                    }
+                    c.fake(KEY_PRESS_EVENT, keycode)?;
+                    c.fake(KEY_RELEASE_EVENT, keycode)?;
+                    if let Some(shift) = shift {
+                        c.fake(KEY_RELEASE_EVENT, shift)?;
+                    }
+                    Ok(())
+                }
+            }
+        }
+
+        fn flush(&mut self) -> Result<(), BackendError> {
+            match &self.connection {
+                Some(c) => c.conn.flush().map_err(x11_error),
+                None => Ok(()),
+            }
+        }
+
+        fn reset(&mut self) {
+            self.connection = None;
+        }
+    }
+
+    impl Connected {
+        fn open() -> Result<Self, BackendError> {
+            let (conn, screen) = x11rb::connect(None).map_err(x11_error)?;
+            if conn
+                .extension_information(xtest::X11_EXTENSION_NAME)
+                .map_err(x11_error)?
+                .is_none()
+            {
+                return Err(BackendError(
+                    "the X server has no XTEST extension".to_string(),
+                ));
+            }
+            let root = conn.setup().roots[screen].root;
+            let keymap = Keymap::load(&conn)?;
+            Ok(Self { conn, root, keymap })
+        }
+
+        /// The server announces layout switches with `MappingNotify`, sent to every client.
+        fn reload_keymap_if_changed(&mut self) -> Result<(), BackendError> {
+            let mut changed = false;
+            while let Some(event) = self.conn.poll_for_event().map_err(x11_error)? {
+                changed |= matches!(event, Event::MappingNotify(_));
+            }
+            if changed {
+                self.keymap = Keymap::load(&self.conn)?;
+            }
+            Ok(())
+        }
+
+        fn fake(&self, kind: u8, detail: u8) -> Result<(), BackendError> {
+            self.conn
+                .xtest_fake_input(kind, detail, x11rb::CURRENT_TIME, self.root, 0, 0, 0)
+                .map_err(x11_error)?;
+            Ok(())
+        }
+    }
+
+    /// Where each keysym sits on the current layout: its keycode and whether Shift is needed.
+    struct Keymap {
+        keys: HashMap<Keysym, (Keycode, bool)>,
+        shift: Option<Keycode>,
+    }
+
+    impl Keymap {
+        fn load(conn: &RustConnection) -> Result<Self, BackendError> {
+            let setup = conn.setup();
+            let (min, max) = (setup.min_keycode, setup.max_keycode);
+            let reply = conn
+                .get_keyboard_mapping(min, max - min + 1)
+                .map_err(x11_error)?
+                .reply()
+                .map_err(x11_error)?;
+            let per_keycode = usize::from(reply.keysyms_per_keycode).max(1);
+
+            let mut keys = HashMap::new();
+            for (keycode, syms) in (min..=max).zip(reply.keysyms.chunks(per_keycode)) {
+                let unshifted = syms.first().copied().unwrap_or(0);
+                let mut shifted = syms.get(1).copied().unwrap_or(0);
+                // A lone lowercase letter implies its uppercase form on the shift level.
+                if shifted == 0 && (0x61..=0x7a).contains(&unshifted) {
+                    shifted = unshifted - 0x20;
+                }
+                for (keysym, shift) in [(unshifted, false), (shifted, true)] {
+                    if keysym == 0 {
+                        continue;
+                    }
+                    // Prefer keys that need no Shift, then the lowest keycode.
+                    let entry = keys.entry(keysym).or_insert((keycode, shift));
+                    if entry.1 && !shift {
+                        *entry = (keycode, shift);
+                    }
+                }
+            }
+            let shift = keys.get(&XK_SHIFT_L).map(|&(keycode, _)| keycode);
+            Ok(Self { keys, shift })
+        }
+    }
+
+    fn keysym(key: &Key) -> Keysym {
+        match key {
+            Key::F6 => 0xffc3,
+            Key::F7 => 0xffc4,
+            Key::F8 => 0xffc5,
+            Key::F9 => 0xffc6,
+            Key::F10 => 0xffc7,
+            Key::F11 => 0xffc8,
+            Key::F12 => 0xffc9,
+            Key::Space => 0x20,
+            Key::Enter => 0xff0d,
+            Key::Escape => 0xff1b,
+            Key::Mouse(_) | Key::Raw { .. } => 0,
+            // Latin-1 keysyms equal their code points; the rest use the Unicode range.
+            Key::Char(c) => match u32::from(*c) {
+                cp @ (0x20..=0x7e | 0xa0..=0xff) => cp,
+                cp => 0x0100_0000 | cp,
+            },
+        }
+    }
+
+    fn x11_error(e: impl std::fmt::Display) -> BackendError {
+        BackendError(format!("X11: {e}"))
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub use self::ydotool_backend::YdotoolBackend;
+
+#[cfg(target_os = "linux")]
+pub mod ydotool_backend {
+    use std::ffi::c_long;
+    use std::os::unix::net::UnixDatagram;
+    use std::path::{Path, PathBuf};
+
+    use evdev::{EventType, KeyCode, RelativeAxisCode};
+
+    use super::{Backend, BackendError};
+    use crate::keymap::map_key_to_evdev;
+    use crate::settings::{Action, Key, MouseButton};
+
+    /// The name of the uinput device `ydotoold` injects through.
+    pub const DEVICE_NAME: &str = "ydotoold virtual device";
+    /// Where `ydotoold` listens unless `YDOTOOL_SOCKET` says otherwise.
+    pub const DEFAULT_SOCKET: &str = "/tmp/.ydotool_socket";
+
+    /// `struct input_event`: a `timeval` followed by type, code and value.
+    pub const EVENT_SIZE: usize = 2 * size_of::<c_long>() + 8;
+
+    /// `$YDOTOOL_SOCKET`, or [`DEFAULT_SOCKET`].
+    pub fn default_socket_path() -> PathBuf {
+        std::env::var_os("YDOTOOL_SOCKET")
+            .filter(|p| !p.is_empty())
+            .map_or_else(|| PathBuf::from(DEFAULT_SOCKET), PathBuf::from)
+    }
+
+    /// Injects through a running `ydotoold`, sharing its uinput device. Speaks the daemon's
+    /// socket protocol directly: one raw `input_event` per datagram, which the daemon
+    /// writes to its device as is.
+    #[derive(Debug)]
+    pub struct YdotoolBackend {
+        path: PathBuf,
+        socket: Option<UnixDatagram>,
+    }
+
+    impl YdotoolBackend {
+        pub fn new(path: impl Into<PathBuf>) -> Self {
+            Self {
+                path: path.into(),
+                socket: None,
+            }
+        }
+
+        pub fn socket_path(&self) -> &Path {
+            &self.path
+        }
+    }
+
+    impl Backend for YdotoolBackend {
+        fn name(&self) -> &'static str {
+            "ydotool"
+        }
+
+        fn init(&mut self) -> Result<(), BackendError> {
+            if self.socket.is_none() {
+                let socket = UnixDatagram::unbound()
+                    .and_then(|s| s.connect(&self.path).map(|()| s))
+                    .map_err(|e| {
+                        BackendError(format!(
+                            "cannot reach ydotoold at {}: {e}",
+                            self.path.display()
+                        ))
+                    })?;
+                self.socket = Some(socket);
+            }
+            Ok(())
+        }
+
+        fn perform(&mut self, action: &Action) -> Result<(), BackendError> {
+            self.init()?;
+            let socket = self.socket.as_ref().expect("initialized above");
+            let send = |kind: EventType, code: u16, value: i32| {
+                send_event(socket, kind, code, value)?;
+                send_event(socket, EventType::SYNCHRONIZATION, 0, 0)
+            };
+            let key = |code: KeyCode| {
+                send(EventType::KEY, code.0, 1)?;
+                send(EventType::KEY, code.0, 0)
+            };
+            let result = match action {
+                Action::Mouse(MouseButton::Left) => key(KeyCode::BTN_LEFT),
+                Action::Mouse(MouseButton::Right) => key(KeyCode::BTN_RIGHT),
+                Action::Mouse(MouseButton::Middle) => key(KeyCode::BTN_MIDDLE),
+                Action::Mouse(MouseButton::Back) => key(KeyCode::BTN_SIDE),
+                Action::Mouse(MouseButton::Forward) => key(KeyCode::BTN_EXTRA),
+                Action::Mouse(MouseButton::ScrollUp) => {
+                    send(EventType::RELATIVE, RelativeAxisCode::REL_WHEEL.0, 1)
+                }
+                Action::Mouse(MouseButton::ScrollDown) => {
+                    send(EventType::RELATIVE, RelativeAxisCode::REL_WHEEL.0, -1)
+                }
+                Action::Mouse(MouseButton::ScrollLeft) => {
+                    send(EventType::RELATIVE, RelativeAxisCode::REL_HWHEEL.0, -1)
+                }
+                Action::Mouse(MouseButton::ScrollRight) => {
+                    send(EventType::RELATIVE, RelativeAxisCode::REL_HWHEEL.0, 1)
+                }
+                Action::Keyboard(k) => {
+                    let code = map_key_to_evdev(k).ok_or_else(|| {
+                        BackendError(format!("ydotool cannot type {}", k.to_str()))
+                    })?;
+                    if matches!(k, Key::Char(c) if c.is_ascii_uppercase()) {
+                        send(EventType::KEY, KeyCode::KEY_LEFTSHIFT.0, 1)
+                            .and_then(|()| key(code))
+                            .and_then(|()| send(EventType::KEY, KeyCode::KEY_LEFTSHIFT.0, 0))
+                    } else {
+                        key(code)
+                    }
+                }
+            };
+            result.map_err(|e| BackendError(format!("ydotoold: {e}")))
+        }
+
+        fn reset(&mut self) {
+            self.socket = None;
+        }
+    }
+
+    fn send_event(
+        socket: &UnixDatagram,
+        kind: EventType,
+        code: u16,
+        value: i32,
+    ) -> std::io::Result<()> {
+        // The daemon stamps events itself, so the timeval stays zero.
+        let mut event = [0u8; EVENT_SIZE];
+        let fields = EVENT_SIZE - 8;
+        event[fields..fields + 2].copy_from_slice(&kind.0.to_ne_bytes());
+        event[fields + 2..fields + 4].copy_from_slice(&code.to_ne_bytes());
+        event[fields + 4..].copy_from_slice(&value.to_ne_bytes());
+        socket.send(&event).map(|_| ())
+    }
+}
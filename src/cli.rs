@@ -0,0 +1,435 @@
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::{Arc, RwLock, mpsc};
+use std::time::Duration;
+
+use clap::{Parser, Subcommand, ValueEnum};
+
+use autoclicker::backend::select_backend;
+use autoclicker::doctor::{has_failures, run_checks};
+use autoclicker::engine::{ClickerState, Engine, EngineEvent};
+use autoclicker::hotkey::start_hotkey_listener;
+use autoclicker::interval::{IntervalUnit, round_significant};
+use autoclicker::persist::{BACKUP_COUNT, save_settings_atomic};
+use autoclicker::scheduler::measure;
+use autoclicker::settings::{
+    Action, ConfigLocation, Hotkey, Key, LoadError, Settings, TimingMode, read_settings,
+};
+use autoclicker::validate::has_errors;
+use autoclicker::watch::start_config_watcher;
+
+type CliResult = Result<ExitCode, Box<dyn Error>>;
+
+/// Settings left out of the file while unset or empty, so `get` and `set` cannot find
+/// them in it.
+const OPTIONAL_KEYS: &[&str] = &[
+    "click_limit",
+    "ydotool_socket",
+    "profiles",
+    "profile",
+    "bindings",
+];
+
+/// The name `interval` had when it was a whole number of milliseconds.
+const LEGACY_INTERVAL: &str = "interval_ms";
+
+#[derive(Debug, Parser)]
+#[command(version, about = "Minimal cross-platform autoclicker")]
+pub struct Cli {
+    /// Settings file to use instead of the default location
+    #[arg(long, global = true, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Read or change the settings file without starting the GUI
+    #[command(subcommand)]
+    Config(ConfigCommand),
+    /// Click without a window: the hotkey toggles the clicker and settings edits are picked up live
+    Run {
+        /// Start clicking right away and exit once the click limit is reached
+        #[arg(long)]
+        start: bool,
+    },
+    /// Check the session, device permissions, hotkeys and input injection, and suggest fixes
+    Doctor,
+    /// Measure how accurately the click scheduler holds a rate (no input is injected)
+    Bench {
+        /// Target clicks per second
+        #[arg(long, default_value_t = 1000.0)]
+        cps: f64,
+        /// How long to run
+        #[arg(long, default_value_t = 3.0)]
+        seconds: f64,
+        #[arg(long, value_enum, default_value_t = TimingArg::FixedRate)]
+        timing: TimingArg,
+        /// Simulated time spent injecting each click, in microseconds
+        #[arg(long, default_value_t = 0)]
+        work_us: u64,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigCommand {
+    /// Print one setting, e.g. `interval`, `hotkey` or `hotkey.ctrl`
+    Get { key: String },
+    /// Change settings, e.g. `interval=25ms`, `interval=2500cps`, `action=mouse:middle` or `hotkey=ctrl+f9`
+    Set {
+        #[arg(required = true, value_name = "KEY=VALUE")]
+        assignments: Vec<String>,
+    },
+    /// Check a settings file and report every problem found
+    Validate {
+        /// File to check; defaults to the file the GUI uses
+        file: Option<PathBuf>,
+    },
+    /// Print the effective settings
+    Print {
+        #[arg(long, value_enum, default_value_t = Format::Toml)]
+        format: Format,
+    },
+    /// Restore the default settings; the previous file is kept as a backup
+    Reset,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TimingArg {
+    FixedRate,
+    FixedDelay,
+}
+
+impl From<TimingArg> for TimingMode {
+    fn from(t: TimingArg) -> Self {
+        match t {
+            TimingArg::FixedRate => TimingMode::FixedRate,
+            TimingArg::FixedDelay => TimingMode::FixedDelay,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    Toml,
+    Json,
+}
+
+pub fn run(command: Command, location: Option<ConfigLocation>) -> ExitCode {
+    let command = match command {
+        Command::Config(command) => command,
+        Command::Run { start } => return run_headless(location, start),
+        Command::Doctor => return doctor(location),
+        Command::Bench {
+            cps,
+            seconds,
+            timing,
+            work_us,
+        } => return bench(cps, seconds, timing.into(), work_us),
+    };
+    let path = match command {
+        ConfigCommand::Validate {
+            file: Some(ref file),
+        } => Some(file.clone()),
+        _ => location.map(|loc| loc.path),
+    };
+    let Some(path) = path else {
+        eprintln!("error: no config location could be determined; use --config <PATH>");
+        return ExitCode::FAILURE;
+    };
+
+    let result = match command {
+        ConfigCommand::Get { key } => get(&path, &key),
+        ConfigCommand::Set { assignments } => set(&path, &assignments),
+        ConfigCommand::Validate { .. } => validate(&path),
+        ConfigCommand::Print { format } => print(&path, format),
+        ConfigCommand::Reset => reset(&path),
+    };
+    result.unwrap_or_else(|e| {
+        eprintln!("error: {e}");
+        ExitCode::FAILURE
+    })
+}
+
+fn run_headless(location: Option<ConfigLocation>, start: bool) -> ExitCode {
+    let settings = match &location {
+        Some(loc) => match load(&loc.path) {
+            Ok(settings) => settings,
+            Err(e) => {
+                eprintln!("error: {}: {e}", loc.path.display());
+                return ExitCode::FAILURE;
+            }
+        },
+        None => Settings::default(),
+    };
+    let settings = Arc::new(RwLock::new(settings));
+
+    let (tx, rx) = mpsc::channel();
+    let engine = Engine::builder(
+        Arc::clone(&settings),
+        select_backend(&settings.read().unwrap()),
+    )
+    .subscribe(move |event| {
+        let _ = tx.send(event.clone());
+    })
+    .start();
+    let listener = start_hotkey_listener(engine.clone());
+    let path = location.as_ref().map(|loc| loc.path.clone());
+    let _watcher = location.and_then(|loc| {
+        let engine = engine.clone();
+        start_config_watcher(
+            loc.path,
+            settings,
+            |_| false,
+            move |result| match result {
+                Ok(()) => engine.settings_changed(),
+                Err(e) => engine.settings_rejected(e.to_string()),
+            },
+        )
+        .inspect_err(|e| eprintln!("warning: settings hot-reload unavailable: {e}"))
+        .ok()
+    });
+
+    let hotkey = engine.settings().read().unwrap().hotkey.clone();
+    eprintln!(
+        "Clicking with {}, listening with {}; {} {} to toggle",
+        engine.backend_name(),
+        listener,
+        hotkey.trigger.to_string().to_lowercase(),
+        hotkey
+    );
+    if start {
+        engine.start_clicking();
+    }
+    for event in rx {
+        match event {
+            EngineEvent::StateChanged { to, .. } => {
+                eprintln!("{to} ({} clicks)", engine.clicks());
+                match to {
+                    ClickerState::StoppedByLimit if start => return ExitCode::SUCCESS,
+                    ClickerState::Error(_) => return ExitCode::FAILURE,
+                    _ => {}
+                }
+            }
+            EngineEvent::SettingsChanged => eprintln!("Settings reloaded"),
+            EngineEvent::SettingsRejected(reason) => {
+                eprintln!("warning: ignoring settings edit: {reason}");
+            }
+            EngineEvent::HealthChanged { subsystem, health } => {
+                eprintln!("{subsystem}: {health}");
+            }
+            EngineEvent::HotkeyRecorded(_)
+            | EngineEvent::SequencePending(None)
+            | EngineEvent::ClicksChanged(_) => {}
+            EngineEvent::SequencePending(Some(typed)) => eprintln!("{typed}, …"),
+            EngineEvent::SettingsAdjusted(what) => {
+                eprintln!("{what}");
+                let settings = engine.settings().read().unwrap().clone();
+                if let Some(path) = &path
+                    && let Err(e) = save(path, &settings)
+                {
+                    eprintln!("warning: {}: {e}", path.display());
+                }
+            }
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+fn doctor(location: Option<ConfigLocation>) -> ExitCode {
+    // Broken settings should not keep the checks from running.
+    let settings = location
+        .and_then(|loc| load(&loc.path).ok())
+        .unwrap_or_default();
+    let checks = run_checks(&settings);
+    for check in &checks {
+        println!("{check}");
+    }
+    if has_failures(&checks) {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+fn bench(cps: f64, seconds: f64, timing: TimingMode, work_us: u64) -> ExitCode {
+    if !(cps.is_finite() && cps > 0.0 && seconds.is_finite() && seconds > 0.0) {
+        eprintln!("error: --cps and --seconds must be positive");
+        return ExitCode::FAILURE;
+    }
+    let result = measure(
+        timing,
+        Duration::from_secs_f64(1.0 / cps),
+        Duration::from_secs_f64(seconds),
+        Duration::from_micros(work_us),
+    );
+    println!("{result}");
+    ExitCode::SUCCESS
+}
+
+/// Reads the settings the GUI would start with; a missing file means defaults.
+fn load(path: &Path) -> Result<Settings, LoadError> {
+    match read_settings(path) {
+        Err(LoadError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+            Ok(Settings::default())
+        }
+        other => other,
+    }
+}
+
+/// Like [`load`], but without validating, so `set` can repair an invalid file.
+fn load_unchecked(path: &Path) -> Result<Settings, Box<dyn Error>> {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => Ok(toml::from_str(&contents)?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Settings::default()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn save(path: &Path, settings: &Settings) -> Result<(), Box<dyn Error>> {
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    save_settings_atomic(path, settings, BACKUP_COUNT)?;
+    Ok(())
+}
+
+fn get(path: &Path, key: &str) -> CliResult {
+    let settings = load(path)?;
+    match key {
+        "hotkey" => println!("{}", settings.hotkey),
+        "action" => println!("{}", settings.action),
+        LEGACY_INTERVAL => println!(
+            "{}",
+            round_significant(settings.interval.converted(IntervalUnit::Millis).value)
+        ),
+        _ => {
+            let table = toml::Table::try_from(&settings)?;
+            let Some(value) = lookup(&table, key) else {
+                if OPTIONAL_KEYS.contains(&key) {
+                    return Ok(ExitCode::SUCCESS);
+                }
+                return Err(format!("unknown setting {key:?}").into());
+            };
+            match value {
+                toml::Value::String(s) => println!("{s}"),
+                toml::Value::Table(t) => print!("{}", toml::to_string_pretty(t)?),
+                v => println!("{v}"),
+            }
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn set(path: &Path, assignments: &[String]) -> CliResult {
+    let mut table = toml::Table::try_from(&load_unchecked(path)?)?;
+    for assignment in assignments {
+        let (key, raw) = assignment
+            .split_once('=')
+            .ok_or_else(|| format!("expected KEY=VALUE, got {assignment:?}"))?;
+        let (key, raw) = (key.trim(), raw.trim());
+        // Before intervals had units, they were set in milliseconds as `interval_ms`.
+        let (key, raw) = match key {
+            LEGACY_INTERVAL if raw.parse::<f64>().is_ok() => ("interval", format!("{raw}ms")),
+            LEGACY_INTERVAL => ("interval", raw.to_string()),
+            _ => (key, raw.to_string()),
+        };
+        let raw = raw.as_str();
+        if OPTIONAL_KEYS.contains(&key) && !table.contains_key(key) {
+            // Any placeholder; it is replaced right below.
+            table.insert(key.to_string(), toml::Value::Boolean(false));
+        }
+        let slot = lookup_mut(&mut table, key).ok_or_else(|| format!("unknown setting {key:?}"))?;
+        let mut value = parse_value(key, raw)?;
+        // The short hotkey form has no mode or matching options; keep the current ones.
+        if key == "hotkey"
+            && let Some(new) = value.as_table_mut()
+        {
+            for option in ["mode", "extra_modifiers", "trigger", "timing"] {
+                if let Some(current) = slot.get(option) {
+                    new.insert(option.to_string(), current.clone());
+                }
+            }
+        }
+        *slot = value;
+    }
+
+    let settings: Settings = toml::Value::Table(table)
+        .try_into()
+        .map_err(|e| format!("invalid value: {e}"))?;
+    let diagnostics = settings.validate();
+    for d in &diagnostics {
+        eprintln!("{d}");
+    }
+    if has_errors(&diagnostics) {
+        eprintln!("error: settings not saved");
+        return Ok(ExitCode::FAILURE);
+    }
+    save(path, &settings)?;
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Accepts the short forms of `action`, `hotkey` and keys; everything else is read
+/// as a TOML literal, falling back to a bare string.
+fn parse_value(key: &str, raw: &str) -> Result<toml::Value, Box<dyn Error>> {
+    Ok(match key {
+        "action" => toml::Value::try_from(raw.parse::<Action>()?)?,
+        "hotkey" => toml::Value::try_from(raw.parse::<Hotkey>()?)?,
+        "hotkey.key" | "action.Keyboard" => toml::Value::try_from(raw.parse::<Key>()?)?,
+        _ => toml::from_str::<toml::Table>(&format!("v = {raw}"))
+            .ok()
+            .and_then(|mut t| t.remove("v"))
+            .unwrap_or_else(|| toml::Value::String(raw.to_string())),
+    })
+}
+
+fn lookup<'a>(table: &'a toml::Table, key: &str) -> Option<&'a toml::Value> {
+    let mut parts = key.split('.');
+    let mut value = table.get(parts.next()?)?;
+    for part in parts {
+        value = value.as_table()?.get(part)?;
+    }
+    Some(value)
+}
+
+fn lookup_mut<'a>(table: &'a mut toml::Table, key: &str) -> Option<&'a mut toml::Value> {
+    let mut parts = key.split('.');
+    let mut value = table.get_mut(parts.next()?)?;
+    for part in parts {
+        value = value.as_table_mut()?.get_mut(part)?;
+    }
+    Some(value)
+}
+
+fn validate(path: &Path) -> CliResult {
+    let contents = std::fs::read_to_string(path)?;
+    let settings = toml::from_str::<Settings>(&contents)?;
+
+    let diagnostics = settings.validate();
+    for d in &diagnostics {
+        println!("{d}");
+    }
+    if has_errors(&diagnostics) {
+        return Ok(ExitCode::FAILURE);
+    }
+    println!("{}: ok", path.display());
+    Ok(ExitCode::SUCCESS)
+}
+
+fn print(path: &Path, format: Format) -> CliResult {
+    let settings = load(path)?;
+    match format {
+        Format::Toml => print!("{}", toml::to_string_pretty(&settings)?),
+        Format::Json => println!("{}", serde_json::to_string_pretty(&settings)?),
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn reset(path: &Path) -> CliResult {
+    save(path, &Settings::default())?;
+    println!("{}: reset to defaults", path.display());
+    Ok(ExitCode::SUCCESS)
+}
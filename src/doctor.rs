@@ -0,0 +1,419 @@
+use std::fmt::{Display, Formatter};
+
+use crate::backend::{Backend, select_backend};
+use crate::session::Session;
+use crate::settings::Settings;
+use crate::validate::{Diagnostic, Severity};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Status {
+    /// Not applicable here, or could not be tested because an earlier check failed.
+    Skip,
+    Ok,
+    Warn,
+    Fail,
+}
+
+impl Display for Status {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Status::Skip => "skip",
+            Status::Ok => " ok ",
+            Status::Warn => "warn",
+            Status::Fail => "FAIL",
+        })
+    }
+}
+
+/// The outcome of one environment check, with what to do about it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: Status,
+    pub detail: String,
+    /// How to fix a warning or failure.
+    pub fix: Option<String>,
+}
+
+impl Check {
+    fn new(name: &'static str, status: Status, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    fn fix(mut self, fix: impl Into<String>) -> Self {
+        self.fix = Some(fix.into());
+        self
+    }
+}
+
+impl Display for Check {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}] {}: {}", self.status, self.name, self.detail)?;
+        if let Some(fix) = &self.fix {
+            write!(f, "\n       fix: {fix}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Takes a few seconds, and may create a virtual input device.
+pub fn run_checks(settings: &Settings) -> Vec<Check> {
+    let session = Session::detect();
+    let mut checks = vec![check_session(session)];
+
+    #[cfg(target_os = "linux")]
+    {
+        let group = linux::check_input_group();
+        let devices = linux::check_input_devices();
+        let uinput = linux::check_uinput(session);
+        let roundtrip = if uinput.status == Status::Ok {
+            linux::check_virtual_roundtrip()
+        } else {
+            Check::new(
+                "Virtual device",
+                Status::Skip,
+                "needs /dev/uinput to create a test keyboard",
+            )
+        };
+        // The round trip is the real test; without uinput, readable devices are a fair guess.
+        let evidence = if roundtrip.status == Status::Skip {
+            &devices
+        } else {
+            &roundtrip
+        };
+        let listener = check_listener(settings, session, evidence);
+        let ydotool = linux::check_ydotoold(settings);
+        checks.extend([group, devices, uinput, roundtrip, listener, ydotool]);
+    }
+    #[cfg(not(target_os = "linux"))]
+    checks.push(check_listener(settings, session));
+
+    checks.push(check_hotkeys(settings));
+    checks.push(check_injection(settings, session));
+    checks
+}
+
+pub fn check_hotkeys(settings: &Settings) -> Check {
+    const NAME: &str = "Hotkeys";
+    let problems: Vec<Diagnostic> = settings
+        .validate()
+        .into_iter()
+        .filter(Diagnostic::is_about_hotkeys)
+        .collect();
+    let Some(worst) = problems.iter().map(|d| d.severity).max() else {
+        let count = settings.hotkeys().count();
+        return Check::new(NAME, Status::Ok, format!("{count} bound, no conflicts"));
+    };
+    let status = if worst == Severity::Error {
+        Status::Fail
+    } else {
+        Status::Warn
+    };
+    let detail = problems
+        .iter()
+        .map(|d| format!("{}: {}", d.path, d.message))
+        .collect::<Vec<_>>()
+        .join("; ");
+    Check::new(NAME, status, detail)
+        .fix("pick other hotkeys in the window or with `autoclicker config set`")
+}
+
+pub fn has_failures(checks: &[Check]) -> bool {
+    checks.iter().any(|c| c.status == Status::Fail)
+}
+
+fn check_session(session: Session) -> Check {
+    const NAME: &str = "Session";
+    match session {
+        Session::X11 | Session::Native => Check::new(NAME, Status::Ok, session.to_string()),
+        Session::Wayland if session.has_x_display() => Check::new(
+            NAME,
+            Status::Ok,
+            "Wayland, with XWayland for X11 applications",
+        ),
+        Session::Wayland => Check::new(NAME, Status::Warn, "Wayland without XWayland")
+            .fix("X11 based input injection needs XWayland; enable it in your compositor"),
+        Session::Headless => Check::new(
+            NAME,
+            Status::Warn,
+            "no display server (neither DISPLAY nor WAYLAND_DISPLAY is set)",
+        )
+        .fix("run from inside your desktop session, or export DISPLAY / WAYLAND_DISPLAY"),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn check_listener(settings: &Settings, session: Session, evidence: &Check) -> Check {
+    const NAME: &str = "Hotkey listener";
+    if crate::hotkey::uses_evdev(settings, session) {
+        let check = Check::new(NAME, evidence.status, format!("evdev: {}", evidence.detail));
+        return match &evidence.fix {
+            Some(fix) => check.fix(fix.clone()),
+            None => check,
+        };
+    }
+    match session {
+        Session::X11 => Check::new(NAME, Status::Ok, "rdev (X11 RECORD)"),
+        Session::Wayland => Check::new(
+            NAME,
+            Status::Warn,
+            "rdev under Wayland only sees keys pressed in X11 windows",
+        )
+        .fix("set `listener = \"Evdev\"` (or \"Auto\") in settings.toml"),
+        _ => Check::new(NAME, Status::Fail, "rdev needs an X11 display")
+            .fix("run inside an X11 session, or set `listener = \"Evdev\"` in settings.toml"),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn check_listener(_settings: &Settings, _session: Session) -> Check {
+    let check = Check::new("Hotkey listener", Status::Ok, "rdev");
+    if cfg!(target_os = "macos") {
+        check
+            .fix("if hotkeys do nothing, allow the app under Privacy & Security > Input Monitoring")
+    } else {
+        check
+    }
+}
+
+fn check_injection(settings: &Settings, session: Session) -> Check {
+    const NAME: &str = "Input injection";
+    let mut backend = select_backend(settings);
+    let result = backend.init();
+    backend.reset();
+    match result {
+        Ok(()) if session == Session::Wayland && backend.name() != "uinput" => Check::new(
+            NAME,
+            Status::Warn,
+            format!(
+                "{} connected through XWayland; clicks only reach X11 windows",
+                backend.name()
+            ),
+        ),
+        Ok(()) => Check::new(NAME, Status::Ok, format!("{} connected", backend.name())),
+        Err(e) if backend.name() == "uinput" => {
+            Check::new(NAME, Status::Fail, format!("uinput: {e}"))
+                .fix("make /dev/uinput writable (see the /dev/uinput check), or use an X11 session")
+        }
+        Err(e) => {
+            let fix = match session {
+                Session::Native if cfg!(target_os = "macos") => {
+                    "allow the app under Privacy & Security > Accessibility"
+                }
+                Session::Native => "check that no security software blocks simulated input",
+                _ => "run inside an X11 or XWayland session with DISPLAY set",
+            };
+            Check::new(NAME, Status::Fail, format!("{}: {e}", backend.name())).fix(fix)
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::fs::{self, OpenOptions};
+    use std::io;
+    use std::os::unix::net::UnixDatagram;
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+
+    use evdev::uinput::VirtualDevice;
+    use evdev::{AttributeSet, EventSummary, EventType, InputEvent, KeyCode};
+
+    use super::{Check, Status};
+    use crate::backend::ydotool_backend::default_socket_path;
+    use crate::evdev_listener::{INPUT_DIR, open_device};
+    use crate::session::Session;
+    use crate::settings::Settings;
+
+    const UINPUT: &str = "/dev/uinput";
+    const ADD_TO_INPUT_GROUP: &str = "add yourself to the `input` group: `sudo usermod -aG input $USER`, then log out and back in";
+    /// A gamepad button, so desktops ignore the test presses.
+    const TEST_KEY: KeyCode = KeyCode::BTN_TRIGGER_HAPPY1;
+    const ROUNDTRIP_TIMEOUT: Duration = Duration::from_secs(2);
+
+    pub(super) fn check_input_group() -> Check {
+        const NAME: &str = "input group";
+        let Some(gid) = input_group_id() else {
+            return Check::new(NAME, Status::Skip, "this system has no `input` group");
+        };
+        if process_groups().contains(&gid) {
+            return Check::new(NAME, Status::Ok, "you are a member");
+        }
+        let user = std::env::var("USER").unwrap_or_default();
+        if input_group_members().contains(&user) {
+            Check::new(
+                NAME,
+                Status::Warn,
+                "you were added, but this session started before that",
+            )
+            .fix("log out and back in (or reboot) for the new group to apply")
+        } else {
+            Check::new(NAME, Status::Warn, "you are not a member").fix(ADD_TO_INPUT_GROUP)
+        }
+    }
+
+    pub(super) fn check_input_devices() -> Check {
+        const NAME: &str = "Input devices";
+        let entries = match fs::read_dir(INPUT_DIR) {
+            Ok(entries) => entries,
+            Err(e) => return Check::new(NAME, Status::Fail, format!("{INPUT_DIR}: {e}")),
+        };
+        let nodes: Vec<_> = entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| {
+                p.file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.starts_with("event"))
+            })
+            .collect();
+        let readable = nodes.iter().filter(|p| fs::File::open(p).is_ok()).count();
+        let detail = format!("{readable} of {} readable in {INPUT_DIR}", nodes.len());
+        if nodes.is_empty() {
+            Check::new(NAME, Status::Warn, format!("no devices in {INPUT_DIR}"))
+        } else if readable == 0 {
+            Check::new(NAME, Status::Fail, detail).fix(ADD_TO_INPUT_GROUP)
+        } else if readable < nodes.len() {
+            Check::new(NAME, Status::Warn, detail).fix(ADD_TO_INPUT_GROUP)
+        } else {
+            Check::new(NAME, Status::Ok, detail)
+        }
+    }
+
+    /// Only needed outside X11, where XTest can inject instead.
+    pub(super) fn check_uinput(session: Session) -> Check {
+        const NAME: &str = "/dev/uinput";
+        let failed = if session == Session::X11 {
+            Status::Warn
+        } else {
+            Status::Fail
+        };
+        match OpenOptions::new().write(true).open(UINPUT) {
+            Ok(_) => Check::new(NAME, Status::Ok, "writable"),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                Check::new(NAME, failed, "missing").fix("load the module: `sudo modprobe uinput`")
+            }
+            Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
+                Check::new(NAME, failed, "permission denied").fix(
+                    "add the udev rule `KERNEL==\"uinput\", GROUP=\"input\", MODE=\"0660\"` to \
+                     /etc/udev/rules.d/99-uinput.rules, run `sudo udevadm trigger`, and join the `input` group",
+                )
+            }
+            Err(e) => Check::new(NAME, failed, e.to_string()),
+        }
+    }
+
+    /// Creates a virtual device, presses a button on it and reads the press back the way
+    /// the evdev listener would.
+    pub(super) fn check_virtual_roundtrip() -> Check {
+        const NAME: &str = "Virtual device";
+        match roundtrip() {
+            Ok(true) => Check::new(NAME, Status::Ok, "injected events are seen by the listener"),
+            Ok(false) => Check::new(
+                NAME,
+                Status::Fail,
+                "injected events never arrived at the listener",
+            ),
+            Err(e) if e.kind() == io::ErrorKind::PermissionDenied => Check::new(
+                NAME,
+                Status::Fail,
+                "created a test device, but cannot read it back",
+            )
+            .fix(ADD_TO_INPUT_GROUP),
+            Err(e) => Check::new(NAME, Status::Fail, e.to_string()),
+        }
+    }
+
+    /// Optional: a running daemon is an alternative to our own uinput device.
+    pub(super) fn check_ydotoold(settings: &Settings) -> Check {
+        const NAME: &str = "ydotoold";
+        let path = settings
+            .ydotool_socket
+            .clone()
+            .unwrap_or_else(default_socket_path);
+        if !path.exists() {
+            return Check::new(
+                NAME,
+                Status::Skip,
+                format!("not running (no socket at {})", path.display()),
+            );
+        }
+        match UnixDatagram::unbound().and_then(|s| s.connect(&path)) {
+            Ok(()) => Check::new(NAME, Status::Ok, format!("listening at {}", path.display())),
+            Err(e) => Check::new(NAME, Status::Warn, format!("{}: {e}", path.display())).fix(
+                "restart ydotoold, and make its socket writable for you (`ydotoold --socket-perm 0660`)",
+            ),
+        }
+    }
+
+    fn roundtrip() -> io::Result<bool> {
+        let mut keys = AttributeSet::<KeyCode>::new();
+        keys.insert(TEST_KEY);
+        let mut virtual_device = VirtualDevice::builder()?
+            .name("autoclicker doctor")
+            .with_keys(&keys)?
+            .build()?;
+
+        let node = virtual_device
+            .enumerate_dev_nodes_blocking()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "test device has no node"))??;
+        let mut device = open_device(&node)?;
+
+        virtual_device.emit(&[InputEvent::new(EventType::KEY.0, TEST_KEY.0, 1)])?;
+        virtual_device.emit(&[InputEvent::new(EventType::KEY.0, TEST_KEY.0, 0)])?;
+
+        let (tx, rx) = mpsc::channel();
+        // Ends with an error once the virtual device is dropped below.
+        thread::spawn(move || {
+            while let Ok(events) = device.fetch_events() {
+                for event in events {
+                    if let EventSummary::Key(_, TEST_KEY, 1) = event.destructure() {
+                        let _ = tx.send(());
+                        return;
+                    }
+                }
+            }
+        });
+        Ok(rx.recv_timeout(ROUNDTRIP_TIMEOUT).is_ok())
+    }
+
+    fn input_group_line() -> Option<Vec<String>> {
+        let groups = fs::read_to_string("/etc/group").ok()?;
+        groups
+            .lines()
+            .map(|line| line.split(':').map(str::to_string).collect::<Vec<_>>())
+            .find(|fields| fields.first().is_some_and(|name| name == "input"))
+    }
+
+    fn input_group_id() -> Option<u32> {
+        input_group_line()?.get(2)?.parse().ok()
+    }
+
+    fn input_group_members() -> Vec<String> {
+        input_group_line()
+            .and_then(|fields| fields.get(3).cloned())
+            .map(|members| members.split(',').map(str::to_string).collect())
+            .unwrap_or_default()
+    }
+
+    /// Supplementary groups of this process, which only change at login.
+    fn process_groups() -> Vec<u32> {
+        let status = fs::read_to_string("/proc/self/status").unwrap_or_default();
+        status
+            .lines()
+            .find_map(|line| line.strip_prefix("Groups:"))
+            .map(|ids| {
+                ids.split_whitespace()
+                    .filter_map(|id| id.parse().ok())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,442 @@
+use std::fmt::{Display, Formatter};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock, mpsc};
+use std::time::{Duration, Instant};
+
+use tracing::{info, warn};
+
+use crate::backend::Backend;
+use crate::health::{Health, Subsystem};
+use crate::settings::{Command, Hotkey, Settings};
+use crate::validate::Diagnostic;
+use crate::worker::{WorkerCommand, WorkerHandle, WorkerReport, start_click_worker};
+
+/// Lifecycle of the clicker. Only the engine changes it; everyone else observes it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClickerState {
+    Idle,
+    /// Started, waiting for the first click to go out.
+    Arming,
+    Running,
+    /// Suspended without losing the click count towards the limit.
+    Paused,
+    /// Stopped on its own after `click_limit` clicks.
+    StoppedByLimit,
+    /// Could not click; carries the reason.
+    Error(String),
+}
+
+impl ClickerState {
+    /// Whether the clicker is currently meant to be clicking.
+    pub fn is_active(&self) -> bool {
+        matches!(self, ClickerState::Arming | ClickerState::Running)
+    }
+}
+
+impl Display for ClickerState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClickerState::Idle => f.write_str("Idle"),
+            ClickerState::Arming => f.write_str("Arming"),
+            ClickerState::Running => f.write_str("Running"),
+            ClickerState::Paused => f.write_str("Paused"),
+            ClickerState::StoppedByLimit => f.write_str("Stopped (limit reached)"),
+            ClickerState::Error(reason) => write!(f, "Error: {reason}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EngineEvent {
+    StateChanged {
+        from: ClickerState,
+        to: ClickerState,
+    },
+    SettingsChanged,
+    /// A settings change from outside the GUI (e.g. a hand edit) was rejected.
+    SettingsRejected(String),
+    HealthChanged {
+        subsystem: Subsystem,
+        health: Health,
+    },
+    /// A recording started with [`Engine::record_hotkey`] ended; `None` if it timed out
+    /// or was cancelled.
+    HotkeyRecorded(Option<Hotkey>),
+    /// The first steps of a hotkey sequence were typed, e.g. `Ctrl+K`; `None` once it
+    /// completed, failed or timed out.
+    SequencePending(Option<String>),
+    /// A hotkey changed the settings, e.g. switched profiles; carries what it did.
+    /// Frontends save the settings, as the engine does not know where they live.
+    SettingsAdjusted(String),
+    /// Sent at most every [`CLICKS_CHANGED_EVERY`] while clicking.
+    ClicksChanged(u64),
+}
+
+pub const CLICKS_CHANGED_EVERY: Duration = Duration::from_millis(250);
+
+type Subscriber = Arc<dyn Fn(&EngineEvent) + Send + Sync>;
+
+struct Inner {
+    settings: Arc<RwLock<Settings>>,
+    backend: &'static str,
+    state: Mutex<ClickerState>,
+    clicks: AtomicU64,
+    clicks_reported: Mutex<Option<Instant>>,
+    listener_health: Mutex<Health>,
+    injector_health: Mutex<Health>,
+    subscribers: Mutex<Vec<Subscriber>>,
+    /// Id of the hotkey recording in progress, so a stale timeout cannot end a newer one.
+    recording: Mutex<Option<u64>>,
+    recordings: AtomicU64,
+    pending_sequence: Mutex<Option<String>>,
+    worker: WorkerHandle,
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        self.worker.send(WorkerCommand::Shutdown);
+    }
+}
+
+/// Owns the clicker state and the click worker. Clones drive the same clicker.
+#[derive(Clone)]
+pub struct Engine {
+    inner: Arc<Inner>,
+}
+
+impl std::fmt::Debug for Engine {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Engine")
+            .field("state", &self.state())
+            .field("clicks", &self.clicks())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Configures an [`Engine`] before its worker thread starts.
+pub struct EngineBuilder<B> {
+    settings: Arc<RwLock<Settings>>,
+    backend: B,
+    subscribers: Vec<Subscriber>,
+}
+
+impl<B: Backend + 'static> EngineBuilder<B> {
+    /// Like [`Engine::subscribe`], but also sees events emitted while starting up.
+    #[must_use]
+    pub fn subscribe(mut self, f: impl Fn(&EngineEvent) + Send + Sync + 'static) -> Self {
+        self.subscribers.push(Arc::new(f));
+        self
+    }
+
+    /// Spawns the click worker. The engine starts out [`ClickerState::Idle`].
+    pub fn start(self) -> Engine {
+        let (report_tx, report_rx) = mpsc::channel::<WorkerReport>();
+        let backend = self.backend.name();
+        let worker = start_click_worker(Arc::clone(&self.settings), self.backend, report_tx);
+        let engine = Engine {
+            inner: Arc::new(Inner {
+                settings: self.settings,
+                backend,
+                state: Mutex::new(ClickerState::Idle),
+                clicks: AtomicU64::new(0),
+                clicks_reported: Mutex::new(None),
+                listener_health: Mutex::new(Health::Starting),
+                injector_health: Mutex::new(Health::Starting),
+                subscribers: Mutex::new(self.subscribers),
+                recording: Mutex::new(None),
+                recordings: AtomicU64::new(0),
+                pending_sequence: Mutex::new(None),
+                worker,
+            }),
+        };
+
+        let weak = Arc::downgrade(&engine.inner);
+        std::thread::spawn(move || {
+            for report in report_rx {
+                let Some(inner) = weak.upgrade() else {
+                    return;
+                };
+                Engine { inner }.on_worker_report(report);
+            }
+        });
+        engine
+    }
+}
+
+impl Engine {
+    /// Starts building an engine that clicks according to `settings` through `backend`.
+    ///
+    /// `settings` stays shared: edit it in place and call [`Engine::settings_changed`].
+    pub fn builder<B: Backend + 'static>(
+        settings: Arc<RwLock<Settings>>,
+        backend: B,
+    ) -> EngineBuilder<B> {
+        EngineBuilder {
+            settings,
+            backend,
+            subscribers: Vec::new(),
+        }
+    }
+
+    pub fn settings(&self) -> &Arc<RwLock<Settings>> {
+        &self.inner.settings
+    }
+
+    /// Name of the backend actions are injected with.
+    pub fn backend_name(&self) -> &'static str {
+        self.inner.backend
+    }
+
+    pub fn state(&self) -> ClickerState {
+        self.inner.state.lock().unwrap().clone()
+    }
+
+    /// Clicks since the clicker was last started (pausing keeps the count).
+    pub fn clicks(&self) -> u64 {
+        self.inner.clicks.load(Ordering::Relaxed)
+    }
+
+    pub fn health(&self, subsystem: Subsystem) -> Health {
+        self.health_slot(subsystem).lock().unwrap().clone()
+    }
+
+    /// Records the health of a subsystem; listeners call this for [`Subsystem::Listener`].
+    pub fn report_health(&self, subsystem: Subsystem, health: Health) {
+        {
+            let mut slot = self.health_slot(subsystem).lock().unwrap();
+            if *slot == health {
+                return;
+            }
+            *slot = health.clone();
+        }
+        match &health {
+            Health::Failed(reason) => warn!("The {} failed: {}", subsystem, reason),
+            Health::Degraded(reason) => warn!("The {} is degraded: {}", subsystem, reason),
+            Health::Starting | Health::Ok => info!("The {} is {}", subsystem, health),
+        }
+        self.emit(&EngineEvent::HealthChanged { subsystem, health });
+    }
+
+    fn health_slot(&self, subsystem: Subsystem) -> &Mutex<Health> {
+        match subsystem {
+            Subsystem::Listener => &self.inner.listener_health,
+            Subsystem::Injector => &self.inner.injector_health,
+        }
+    }
+
+    /// Calls `f` for every event, on whichever thread produced it. Keep it short; it may
+    /// drive the engine or subscribe again, which only sees later events.
+    pub fn subscribe(&self, f: impl Fn(&EngineEvent) + Send + Sync + 'static) {
+        self.inner.subscribers.lock().unwrap().push(Arc::new(f));
+    }
+
+    pub fn toggle(&self) {
+        if self.state().is_active() {
+            self.stop();
+        } else {
+            self.start_clicking();
+        }
+    }
+
+    /// Starts clicking from a fresh count; resumes instead if paused.
+    pub fn start_clicking(&self) {
+        let command = match self.state() {
+            ClickerState::Arming | ClickerState::Running => return,
+            ClickerState::Paused => WorkerCommand::Resume,
+            _ => {
+                self.inner.clicks.store(0, Ordering::Relaxed);
+                WorkerCommand::Start
+            }
+        };
+        self.transition(ClickerState::Arming);
+        self.inner.worker.send(command);
+    }
+
+    pub fn stop(&self) {
+        self.inner.worker.send(WorkerCommand::Stop);
+        if !matches!(self.state(), ClickerState::Error(_)) {
+            self.transition(ClickerState::Idle);
+        }
+    }
+
+    pub fn pause(&self) {
+        if self.state().is_active() {
+            self.inner.worker.send(WorkerCommand::Stop);
+            self.transition(ClickerState::Paused);
+        }
+    }
+
+    pub fn resume(&self) {
+        if self.state() == ClickerState::Paused {
+            self.start_clicking();
+        }
+    }
+
+    /// Tells the worker to pick up modified settings and notifies subscribers.
+    pub fn settings_changed(&self) {
+        self.inner.worker.send(WorkerCommand::SettingsChanged);
+        self.emit(&EngineEvent::SettingsChanged);
+    }
+
+    pub fn settings_rejected(&self, reason: String) {
+        self.emit(&EngineEvent::SettingsRejected(reason));
+    }
+
+    pub fn shutdown(&self) {
+        self.inner.worker.send(WorkerCommand::Shutdown);
+    }
+
+    /// Captures the next key or mouse button the hotkey listener sees, with the modifiers
+    /// held at that moment, instead of matching it. The result arrives as
+    /// [`EngineEvent::HotkeyRecorded`]; Escape without modifiers cancels.
+    pub fn record_hotkey(&self, timeout: Duration) {
+        let id = self.inner.recordings.fetch_add(1, Ordering::Relaxed);
+        *self.inner.recording.lock().unwrap() = Some(id);
+
+        let weak = Arc::downgrade(&self.inner);
+        std::thread::spawn(move || {
+            std::thread::sleep(timeout);
+            let Some(inner) = weak.upgrade() else {
+                return;
+            };
+            let engine = Engine { inner };
+            let expired = {
+                let mut recording = engine.inner.recording.lock().unwrap();
+                let expired = *recording == Some(id);
+                if expired {
+                    *recording = None;
+                }
+                expired
+            };
+            if expired {
+                info!("Hotkey recording timed out");
+                engine.emit(&EngineEvent::HotkeyRecorded(None));
+            }
+        });
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.inner.recording.lock().unwrap().is_some()
+    }
+
+    pub fn cancel_recording(&self) {
+        self.finish_recording(None);
+    }
+
+    /// Ends the recording in progress with `hotkey`; does nothing if none is.
+    pub(crate) fn finish_recording(&self, hotkey: Option<Hotkey>) {
+        if self.inner.recording.lock().unwrap().take().is_some() {
+            self.emit(&EngineEvent::HotkeyRecorded(hotkey));
+        }
+    }
+
+    /// The steps typed so far of a hotkey sequence waiting for its next step.
+    pub fn pending_sequence(&self) -> Option<String> {
+        self.inner.pending_sequence.lock().unwrap().clone()
+    }
+
+    pub(crate) fn set_pending_sequence(&self, pending: Option<String>) {
+        {
+            let mut slot = self.inner.pending_sequence.lock().unwrap();
+            if *slot == pending {
+                return;
+            }
+            slot.clone_from(&pending);
+        }
+        self.emit(&EngineEvent::SequencePending(pending));
+    }
+
+    /// Changes the settings as a binding's `command` asks, unless the outcome would not
+    /// pass validation.
+    pub fn adjust(&self, command: &Command) {
+        // One write lock throughout, so an edit landing meanwhile is neither lost nor
+        // overwritten.
+        let outcome = {
+            let mut settings = self.inner.settings.write().unwrap();
+            let mut adjusted = settings.clone();
+            adjusted.adjust(command).map(|what| {
+                match adjusted.validate().into_iter().find(Diagnostic::is_error) {
+                    Some(error) => Err(format!("{what}: {error}")),
+                    None => {
+                        *settings = adjusted;
+                        Ok(what)
+                    }
+                }
+            })
+        };
+        let what = match outcome {
+            None => {
+                warn!("Nothing to do for {}", command);
+                return;
+            }
+            Some(Err(reason)) => {
+                warn!("Not applying {}", reason);
+                self.settings_rejected(reason);
+                return;
+            }
+            Some(Ok(what)) => what,
+        };
+        info!("Adjusted settings: {}", what);
+        self.settings_changed();
+        self.emit(&EngineEvent::SettingsAdjusted(what));
+    }
+
+    fn on_worker_report(&self, report: WorkerReport) {
+        match report {
+            WorkerReport::Clicked(count) => {
+                self.inner.clicks.store(count, Ordering::Relaxed);
+                if self.state() == ClickerState::Arming {
+                    self.transition(ClickerState::Running);
+                }
+                let due = {
+                    let mut reported = self.inner.clicks_reported.lock().unwrap();
+                    let due = !self.state().is_active()
+                        || reported.is_none_or(|at| at.elapsed() >= CLICKS_CHANGED_EVERY);
+                    if due {
+                        *reported = Some(Instant::now());
+                    }
+                    due
+                };
+                if due {
+                    self.emit(&EngineEvent::ClicksChanged(count));
+                }
+            }
+            WorkerReport::LimitReached => {
+                if self.state().is_active() {
+                    self.transition(ClickerState::StoppedByLimit);
+                }
+            }
+            WorkerReport::Failed(reason) => {
+                warn!("Clicking failed: {}", reason);
+                self.transition(ClickerState::Error(reason));
+            }
+            WorkerReport::Injector(health) => {
+                // A recovered backend clears the error it caused.
+                if health.is_ok() && matches!(self.state(), ClickerState::Error(_)) {
+                    self.transition(ClickerState::Idle);
+                }
+                self.report_health(Subsystem::Injector, health);
+            }
+        }
+    }
+
+    fn transition(&self, to: ClickerState) {
+        let from = {
+            let mut state = self.inner.state.lock().unwrap();
+            if *state == to {
+                return;
+            }
+            std::mem::replace(&mut *state, to.clone())
+        };
+        info!("Clicker {} -> {}", from, to);
+        self.emit(&EngineEvent::StateChanged { from, to });
+    }
+
+    fn emit(&self, event: &EngineEvent) {
+        // Not called under the lock, so subscribers can call back into the engine.
+        let subscribers = self.inner.subscribers.lock().unwrap().clone();
+        for subscriber in subscribers {
+            subscriber(event);
+        }
+    }
+}
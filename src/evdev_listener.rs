@@ -0,0 +1,403 @@
+use std::collections::HashSet;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use evdev::uinput::VirtualDevice;
+use evdev::{Device, EventSummary, EventType, InputEvent, KeyCode};
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use tracing::{debug, info, warn};
+
+use crate::backend::{UINPUT_DEVICE_NAME, ydotool_backend};
+use crate::engine::Engine;
+use crate::health::{Backoff, Health, Subsystem};
+use crate::hotkey::{BindingsMatcher, KeyEvent, apply_decision, handle_event, handle_tick};
+use crate::keymap::map_evdev_to_input;
+
+pub const INPUT_DIR: &str = "/dev/input";
+
+/// udev fixes up permissions of new device nodes shortly after they appear.
+const OPEN_RETRIES: u32 = 10;
+const OPEN_RETRY_DELAY: Duration = Duration::from_millis(100);
+/// How often to check whether all keys are up, before grabbing a device.
+const GRAB_POLL: Duration = Duration::from_millis(50);
+/// Virtual devices that pass on the input of grabbed ones are named with this prefix,
+/// and not listened to themselves.
+const PASSTHROUGH_NAME: &str = "autoclicker passthrough";
+
+enum Message {
+    Plugged(PathBuf),
+    Unplugged(PathBuf),
+    /// The device is a keyboard or mouse and is being read.
+    Reading(PathBuf),
+    /// The device has no keys worth listening to.
+    Skipped(PathBuf),
+    /// An event, and where to answer whether it belongs to a hotkey if the device is
+    /// grabbed.
+    Input(KeyEvent, Option<mpsc::Sender<bool>>),
+    /// A device that was being read stopped delivering events.
+    Lost(PathBuf),
+    Denied(PathBuf),
+}
+
+/// Which devices the listener knows about, to derive its health.
+#[derive(Default)]
+struct Devices {
+    /// Opened, but not yet classified.
+    pending: HashSet<PathBuf>,
+    reading: HashSet<PathBuf>,
+    skipped: HashSet<PathBuf>,
+    denied: HashSet<PathBuf>,
+}
+
+impl Devices {
+    fn contains(&self, path: &Path) -> bool {
+        self.pending.contains(path)
+            || self.reading.contains(path)
+            || self.skipped.contains(path)
+            || self.denied.contains(path)
+    }
+
+    fn remove(&mut self, path: &Path) {
+        self.pending.remove(path);
+        self.reading.remove(path);
+        self.skipped.remove(path);
+        self.denied.remove(path);
+    }
+
+    /// `None` while devices are still being opened.
+    fn health(&self) -> Option<Health> {
+        if !self.pending.is_empty() {
+            return None;
+        }
+        Some(if !self.reading.is_empty() {
+            Health::Ok
+        } else if let Some(path) = self.denied.iter().next() {
+            Health::Failed(format!(
+                "no permission to read {}; add your user to the `input` group",
+                path.display()
+            ))
+        } else {
+            Health::Degraded("no keyboard or mouse found".to_string())
+        })
+    }
+}
+
+/// Reads hotkeys straight from the keyboards and mice under [`INPUT_DIR`], which works
+/// regardless of display server. Devices plugged in later are picked up automatically.
+///
+/// Needs read access to `/dev/input/event*`, usually through the `input` group. With
+/// [`Settings::suppress_hotkeys`], keyboards and mice are grabbed, and all their input but
+/// the hotkeys is passed on through a virtual device; that needs write access to
+/// `/dev/uinput`. Which keys are hotkeys is looked up on every key event, so later
+/// changes to the hotkeys apply right away.
+///
+/// [`Settings::suppress_hotkeys`]: crate::settings::Settings::suppress_hotkeys
+pub fn start_evdev_listener(engine: Engine) -> io::Result<()> {
+    let (tx, rx) = mpsc::channel::<Message>();
+    let suppress = engine.settings().read().unwrap().suppress_hotkeys;
+
+    let watcher_tx = tx.clone();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<Event>| {
+        let Ok(event) = res else {
+            return;
+        };
+        for path in event.paths.into_iter().filter(|p| is_event_node(p)) {
+            let message = match event.kind {
+                EventKind::Create(_) => Message::Plugged(path),
+                EventKind::Remove(_) => Message::Unplugged(path),
+                _ => continue,
+            };
+            let _ = watcher_tx.send(message);
+        }
+    })
+    .map_err(io::Error::other)?;
+    watcher
+        .watch(Path::new(INPUT_DIR), RecursiveMode::NonRecursive)
+        .map_err(io::Error::other)?;
+
+    for entry in std::fs::read_dir(INPUT_DIR)? {
+        let path = entry?.path();
+        if is_event_node(&path) {
+            let _ = tx.send(Message::Plugged(path));
+        }
+    }
+
+    thread::spawn(move || {
+        let _watcher = watcher;
+        let mut matcher = BindingsMatcher::new();
+        let mut devices = Devices::default();
+        let mut backoff = Backoff::new();
+        let mut retry_at = None;
+
+        loop {
+            if matcher
+                .next_deadline()
+                .is_some_and(|at| at <= Instant::now())
+            {
+                handle_tick(&engine, &mut matcher);
+            }
+            // Devices we may not read are retried, in case access is granted later.
+            if !devices.denied.is_empty() && retry_at.is_none() {
+                retry_at = Some(Instant::now() + backoff.next_delay());
+            }
+            let wake_at = match (retry_at, matcher.next_deadline()) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+            let message = match wake_at {
+                None => rx.recv().map_err(|_| mpsc::RecvTimeoutError::Disconnected),
+                Some(at) => rx.recv_timeout(at.saturating_duration_since(Instant::now())),
+            };
+            let message = match message {
+                Ok(message) => message,
+                Err(mpsc::RecvTimeoutError::Timeout) => {
+                    if retry_at.is_some_and(|at| at <= Instant::now()) {
+                        retry_at = None;
+                        for path in std::mem::take(&mut devices.denied) {
+                            devices.pending.insert(path.clone());
+                            spawn_reader(path, &tx, suppress);
+                        }
+                    }
+                    continue;
+                }
+                Err(mpsc::RecvTimeoutError::Disconnected) => return,
+            };
+
+            match message {
+                Message::Plugged(path) => {
+                    if !devices.contains(&path) {
+                        devices.pending.insert(path.clone());
+                        spawn_reader(path, &tx, suppress);
+                    }
+                }
+                Message::Unplugged(path) => devices.remove(&path),
+                Message::Reading(path) => {
+                    devices.remove(&path);
+                    devices.reading.insert(path);
+                }
+                Message::Skipped(path) => {
+                    devices.remove(&path);
+                    devices.skipped.insert(path);
+                }
+                Message::Input(event, reply) => {
+                    let consumed = handle_event(&engine, &mut matcher, &event);
+                    if let Some(reply) = reply {
+                        let _ = reply.send(consumed);
+                    }
+                    continue;
+                }
+                Message::Lost(path) => {
+                    info!("Input device {} went away", path.display());
+                    devices.remove(&path);
+                    // Its key releases will never arrive.
+                    if let Some(decision) = matcher.reset() {
+                        apply_decision(&engine, decision);
+                    }
+                }
+                Message::Denied(path) => {
+                    devices.remove(&path);
+                    devices.denied.insert(path);
+                }
+            }
+
+            if devices.denied.is_empty() {
+                backoff.reset();
+            }
+            if let Some(health) = devices.health() {
+                engine.report_health(Subsystem::Listener, health);
+            }
+        }
+    });
+    Ok(())
+}
+
+fn spawn_reader(path: PathBuf, tx: &mpsc::Sender<Message>, suppress: bool) {
+    let tx = tx.clone();
+    thread::spawn(move || read_device(&path, &tx, suppress));
+}
+
+fn is_event_node(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|n| n.starts_with("event"))
+}
+
+/// Keyboards and mice; skips things like power buttons, lid switches and sensors, and
+/// the devices we inject or pass input through, whose events would come back as input.
+fn is_keyboard_or_mouse(device: &Device) -> bool {
+    if device.name().is_some_and(|name| {
+        name == UINPUT_DEVICE_NAME
+            || name == ydotool_backend::DEVICE_NAME
+            || name.starts_with(PASSTHROUGH_NAME)
+    }) {
+        return false;
+    }
+    device.supported_keys().is_some_and(|keys| {
+        keys.contains(KeyCode::KEY_A)
+            || keys.contains(KeyCode::KEY_F8)
+            || keys.contains(KeyCode::BTN_LEFT)
+    })
+}
+
+/// Takes `device` away from other programs, and returns the virtual device that passes
+/// its input on.
+///
+/// Every keyboard and mouse is grabbed, not just those with a key of the current hotkeys,
+/// as the hotkeys can change while it is held.
+fn grab(device: &mut Device, path: &Path) -> Option<VirtualDevice> {
+    // Touchpads and tablets would need their absolute axes passed on too.
+    if device.supported_absolute_axes().is_some() {
+        return None;
+    }
+    let passthrough = match create_passthrough(device) {
+        Ok(passthrough) => passthrough,
+        Err(e) => {
+            warn!(
+                "Cannot suppress hotkeys on {}; no uinput device: {}",
+                path.display(),
+                e
+            );
+            return None;
+        }
+    };
+    // Keys held while grabbing would never be released for other programs.
+    while device
+        .get_key_state()
+        .is_ok_and(|held| held.iter().next().is_some())
+    {
+        thread::sleep(GRAB_POLL);
+    }
+    if let Err(e) = device.grab() {
+        warn!("Cannot suppress hotkeys on {}: {}", path.display(), e);
+        return None;
+    }
+    info!("Suppressing hotkeys on {}", path.display());
+    Some(passthrough)
+}
+
+fn create_passthrough(device: &Device) -> io::Result<VirtualDevice> {
+    let name = format!(
+        "{PASSTHROUGH_NAME} ({})",
+        device.name().unwrap_or("unnamed device")
+    );
+    let mut builder = VirtualDevice::builder()?.name(&name);
+    if let Some(keys) = device.supported_keys() {
+        builder = builder.with_keys(keys)?;
+    }
+    if let Some(axes) = device.supported_relative_axes() {
+        builder = builder.with_relative_axes(axes)?;
+    }
+    if let Some(misc) = device.misc_properties() {
+        builder = builder.with_msc(misc)?;
+    }
+    builder.build()
+}
+
+pub(crate) fn open_device(path: &Path) -> io::Result<Device> {
+    let mut attempt = 0;
+    loop {
+        match Device::open(path) {
+            Err(e) if e.kind() == io::ErrorKind::PermissionDenied && attempt < OPEN_RETRIES => {
+                attempt += 1;
+                thread::sleep(OPEN_RETRY_DELAY);
+            }
+            result => return result,
+        }
+    }
+}
+
+fn read_device(path: &Path, tx: &mpsc::Sender<Message>, suppress: bool) {
+    let mut device = match open_device(path) {
+        Ok(device) => device,
+        Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
+            let _ = tx.send(Message::Denied(path.to_path_buf()));
+            return;
+        }
+        Err(e) => {
+            debug!("Cannot open {}: {}", path.display(), e);
+            let _ = tx.send(Message::Unplugged(path.to_path_buf()));
+            return;
+        }
+    };
+    if !is_keyboard_or_mouse(&device) {
+        let _ = tx.send(Message::Skipped(path.to_path_buf()));
+        return;
+    }
+    let _ = tx.send(Message::Reading(path.to_path_buf()));
+    info!(
+        "Listening for hotkeys on {} ({})",
+        path.display(),
+        device.name().unwrap_or("unnamed device")
+    );
+    let mut passthrough = if suppress {
+        grab(&mut device, path)
+    } else {
+        None
+    };
+    let (reply_tx, reply_rx) = mpsc::channel();
+    // Input of the grabbed device since the last SYN_REPORT, minus the hotkeys.
+    let mut batch: Vec<InputEvent> = Vec::new();
+    // Whether a hotkey was taken out of `batch`, whose scan codes then go too.
+    let mut swallowed = false;
+
+    loop {
+        let events = match device.fetch_events() {
+            Ok(events) => events,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => {
+                debug!("Stopped reading {}: {}", path.display(), e);
+                let _ = tx.send(Message::Lost(path.to_path_buf()));
+                return;
+            }
+        };
+        let mut ungrab = false;
+        for event in events {
+            let mut consumed = false;
+            if let EventSummary::Key(_, code, value) = event.destructure()
+                && let Some(input) = map_evdev_to_input(code)
+            {
+                // 1 is a press, 2 an autorepeat and 0 a release.
+                let key_event = if value == 0 {
+                    KeyEvent::Release(input)
+                } else {
+                    KeyEvent::Press(input)
+                };
+                let reply = passthrough.is_some().then(|| reply_tx.clone());
+                if tx.send(Message::Input(key_event, reply)).is_err() {
+                    return;
+                }
+                consumed = passthrough.is_some() && reply_rx.recv().unwrap_or(false);
+            }
+
+            let Some(out) = &mut passthrough else {
+                continue;
+            };
+            if event.event_type() == EventType::SYNCHRONIZATION {
+                if std::mem::take(&mut swallowed) {
+                    batch.retain(|e| e.event_type() != EventType::MISC);
+                }
+                if !batch.is_empty() {
+                    if let Err(e) = out.emit(&batch) {
+                        warn!("Stopped suppressing hotkeys on {}: {}", path.display(), e);
+                        passthrough = None;
+                        ungrab = true;
+                    }
+                    batch.clear();
+                }
+            } else if consumed {
+                swallowed = true;
+            } else if matches!(
+                event.event_type(),
+                EventType::KEY | EventType::RELATIVE | EventType::MISC
+            ) {
+                batch.push(event);
+            }
+        }
+        if ungrab {
+            let _ = device.ungrab();
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
+/// The parts of the app that talk to the system and can break independently.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Subsystem {
+    /// Reads global hotkeys.
+    Listener,
+    /// Performs the clicks and key presses.
+    Injector,
+}
+
+impl Display for Subsystem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Subsystem::Listener => "hotkey listener",
+            Subsystem::Injector => "input injector",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Health {
+    /// Not checked yet.
+    Starting,
+    Ok,
+    /// Working, but not fully; carries what is wrong.
+    Degraded(String),
+    /// Not working; carries the reason. Retried in the background.
+    Failed(String),
+}
+
+impl Health {
+    pub fn is_ok(&self) -> bool {
+        *self == Health::Ok
+    }
+
+    pub fn reason(&self) -> Option<&str> {
+        match self {
+            Health::Degraded(reason) | Health::Failed(reason) => Some(reason),
+            Health::Starting | Health::Ok => None,
+        }
+    }
+}
+
+impl Display for Health {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Health::Starting => f.write_str("starting"),
+            Health::Ok => f.write_str("OK"),
+            Health::Degraded(reason) => write!(f, "degraded: {reason}"),
+            Health::Failed(reason) => write!(f, "failed: {reason}"),
+        }
+    }
+}
+
+/// Exponential retry delays: 1s, 2s, 4s, ... capped at 30s until [`Backoff::reset`].
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    next: Duration,
+}
+
+impl Backoff {
+    const INITIAL: Duration = Duration::from_secs(1);
+    const MAX: Duration = Duration::from_secs(30);
+
+    pub fn new() -> Self {
+        Self {
+            next: Self::INITIAL,
+        }
+    }
+
+    /// Returns the delay before the next attempt and doubles the one after it.
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = self.next;
+        self.next = (self.next * 2).min(Self::MAX);
+        delay
+    }
+
+    pub fn reset(&mut self) {
+        self.next = Self::INITIAL;
+    }
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self::new()
+    }
+}
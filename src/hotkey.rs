@@ -1,70 +1,721 @@
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, Mutex, mpsc};
+use std::time::{Duration, Instant};
 
-use rdev::{Event, EventType, Key as RdevKey, listen};
-use tracing::{error, info};
+use rdev::{Event, EventType, listen};
+use tracing::{info, warn};
 
-use crate::keymap::map_rdev_to_key;
-use crate::settings::Settings;
+use crate::engine::Engine;
+use crate::health::{Backoff, Health, Subsystem};
+use crate::keymap::{map_rdev_button_to_input, map_rdev_to_input};
+use crate::session::Session;
+use crate::settings::{
+    Command, Hotkey, HotkeyMode, InputListener, Key, MouseButton, Settings, Side, Trigger,
+};
 
-pub fn start_hotkey_listener(settings: Arc<RwLock<Settings>>, active: Arc<RwLock<bool>>) {
-    std::thread::spawn(move || {
-        let mut ctrl = false;
-        let mut alt = false;
-        let mut shift = false;
-        let mut meta = false;
-        let mut toggled_for_combo: bool = false;
-
-        let callback = move |event: Event| match event.event_type {
-            EventType::KeyPress(key) => {
-                update_mods_on_key(key, true, &mut ctrl, &mut alt, &mut shift, &mut meta);
-
-                if let Some(main_key) = map_rdev_to_key(key) {
-                    let hotkey = { settings.read().unwrap().hotkey.clone() };
-                    if hotkey.matches_combo(ctrl, alt, shift, meta, &main_key) && !toggled_for_combo
-                    {
-                        toggled_for_combo = true;
-                        let mut a = active.write().unwrap();
-                        *a = !*a;
-                        info!("Toggled autoclicker: {}", if *a { "ON" } else { "OFF" });
-                    }
+/// A physical modifier key; each side is tracked on its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Modifier {
+    ControlLeft,
+    ControlRight,
+    AltLeft,
+    /// AltGr on most layouts; a modifier of its own, not a second Alt.
+    AltRight,
+    ShiftLeft,
+    ShiftRight,
+    MetaLeft,
+    MetaRight,
+}
+
+/// A key as seen by the [`HotkeyMatcher`], independent of the listener it came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyInput {
+    Modifier(Modifier),
+    Key(Key),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyEvent {
+    /// Key went down. Autorepeat may deliver this again without a release in between.
+    Press(KeyInput),
+    Release(KeyInput),
+}
+
+/// What the listener should do in response to an event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    /// The hotkey was pressed in [`HotkeyMode::Toggle`].
+    Toggle,
+    /// The hotkey went down in [`HotkeyMode::Hold`].
+    Press,
+    /// The held hotkey was let go, either its key or one of its modifiers.
+    Release,
+}
+
+/// Turns key events into hotkey decisions. Long presses and sequence timeouts need
+/// [`HotkeyMatcher::tick`] at [`HotkeyMatcher::next_deadline`].
+#[derive(Debug, Clone, Default)]
+pub struct HotkeyMatcher {
+    modifiers: [bool; 8],
+    held_keys: Vec<Key>,
+    /// The hotkey went down and has not been released yet.
+    down: Option<Down>,
+    /// A [`Decision::Press`] was sent and its [`Decision::Release`] is still due.
+    holding: bool,
+    /// When the last tap ended, while a double tap may still follow.
+    last_tap: Option<Instant>,
+    /// Steps of a sequence typed so far.
+    sequence: Option<Sequence>,
+    /// Held keys whose press went to the hotkey, as the go-down key or a sequence step.
+    consumed: Vec<Key>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Sequence {
+    /// Index of the step that has to come next.
+    next: usize,
+    deadline: Instant,
+}
+
+#[derive(Debug, Clone)]
+struct Down {
+    /// `None` for a hotkey of modifiers alone.
+    key: Option<Key>,
+    since: Instant,
+    /// Something else was pressed meanwhile, so it is no tap or long press.
+    interrupted: bool,
+    fired: bool,
+    /// When a long press fires.
+    deadline: Option<Instant>,
+}
+
+impl HotkeyMatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feeds one event; `hotkey` is passed each time so edits apply immediately.
+    pub fn handle(&mut self, hotkey: &Hotkey, event: &KeyEvent) -> Option<Decision> {
+        self.handle_at(hotkey, event, Instant::now())
+    }
+
+    /// Like [`HotkeyMatcher::handle`], for an event that happened at `now`.
+    pub fn handle_at(
+        &mut self,
+        hotkey: &Hotkey,
+        event: &KeyEvent,
+        now: Instant,
+    ) -> Option<Decision> {
+        match event {
+            KeyEvent::Press(KeyInput::Modifier(m)) => {
+                if self.held(*m) {
+                    return None; // autorepeat
+                }
+                self.modifiers[*m as usize] = true;
+                if let Some(down) = &mut self.down {
+                    down.interrupted = true;
+                    return None;
                 }
+                if hotkey.key.is_none() && self.held_keys.is_empty() && self.modifiers_match(hotkey)
+                {
+                    return self.go_down(hotkey, None, now);
+                }
+                None
+            }
+            KeyEvent::Release(KeyInput::Modifier(m)) => {
+                self.modifiers[*m as usize] = false;
+                let down = self.down.as_ref()?;
+                if self.modifiers_match(hotkey) {
+                    return None;
+                }
+                if down.key.is_none() {
+                    return self.go_up(hotkey, now);
+                }
+                // A held hotkey ends as soon as one of its modifiers is let go.
+                self.holding.then(|| {
+                    self.holding = false;
+                    Decision::Release
+                })
+            }
+            KeyEvent::Press(KeyInput::Key(key)) => {
+                let key = key.normalized();
+                // Clicks, ours included, only count when the hotkey uses the button.
+                if matches!(key, Key::Mouse(_)) && !uses_key(hotkey, &key) {
+                    return None;
+                }
+                if self.held_keys.contains(&key) {
+                    return None; // autorepeat
+                }
+                self.held_keys.push(key.clone());
+                if let Some(down) = &mut self.down {
+                    down.interrupted = true;
+                    return None;
+                }
+                let step = self
+                    .sequence
+                    .take()
+                    .filter(|s| now < s.deadline)
+                    .map_or(0, |s| s.next);
+                // A wrong key ends the sequence, but may start it over.
+                let Some(step) = [step, 0]
+                    .into_iter()
+                    .find(|&i| hotkey.step(i).is_some_and(|s| self.combo_matches(s, &key)))
+                else {
+                    self.last_tap = None;
+                    return None;
+                };
+                self.consumed.push(key.clone());
+                if step < hotkey.then.len() {
+                    self.sequence = Some(Sequence {
+                        next: step + 1,
+                        deadline: now + hotkey.timing.sequence(),
+                    });
+                    return None;
+                }
+                self.go_down(hotkey, Some(key), now)
+            }
+            KeyEvent::Release(KeyInput::Key(key)) => {
+                let key = key.normalized();
+                self.held_keys.retain(|k| *k != key);
+                self.consumed.retain(|k| *k != key);
+                let down = self.down.as_ref()?;
+                if down.key.as_ref() != Some(&key) {
+                    return None;
+                }
+                self.go_up(hotkey, now)
+            }
+        }
+    }
+
+    /// Fires a long press whose time has come, and forgets a sequence that timed out.
+    pub fn tick(&mut self, hotkey: &Hotkey, now: Instant) -> Option<Decision> {
+        if self.sequence.is_some_and(|s| now >= s.deadline) {
+            self.sequence = None;
+        }
+        let down = self.down.as_mut()?;
+        if down.fired || down.interrupted || down.deadline.is_none_or(|d| now < d) {
+            return None;
+        }
+        down.fired = true;
+        Some(self.fire(hotkey))
+    }
+
+    /// When [`HotkeyMatcher::tick`] has something to do next.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        let long_press = self
+            .down
+            .as_ref()
+            .filter(|d| !d.fired && !d.interrupted)
+            .and_then(|d| d.deadline);
+        let sequence = self.sequence.map(|s| s.deadline);
+        match (long_press, sequence) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    /// Whether `event` belongs to a key press the hotkey took: the press itself once
+    /// handled, its autorepeat, and its release before it is handled. Suppressing
+    /// listeners keep these from other programs.
+    pub fn consumes(&self, event: &KeyEvent) -> bool {
+        match event {
+            KeyEvent::Press(KeyInput::Key(key)) | KeyEvent::Release(KeyInput::Key(key)) => {
+                self.consumed.contains(&key.normalized())
             }
-            EventType::KeyRelease(key) => {
-                update_mods_on_key(key, false, &mut ctrl, &mut alt, &mut shift, &mut meta);
+            _ => false,
+        }
+    }
+
+    /// How many steps of a sequence have been typed while it waits for the next one.
+    pub fn pending_steps(&self) -> usize {
+        self.sequence.map_or(0, |s| s.next)
+    }
+
+    /// Forgets every held key, e.g. after the input device went away. Releases a held hotkey.
+    pub fn reset(&mut self) -> Option<Decision> {
+        let was_holding = self.holding;
+        *self = Self::default();
+        was_holding.then_some(Decision::Release)
+    }
+
+    pub fn ctrl(&self) -> bool {
+        self.held(Modifier::ControlLeft) || self.held(Modifier::ControlRight)
+    }
+
+    pub fn alt(&self) -> bool {
+        self.held(Modifier::AltLeft)
+    }
+
+    pub fn altgr(&self) -> bool {
+        self.held(Modifier::AltRight)
+    }
+
+    pub fn shift(&self) -> bool {
+        self.held(Modifier::ShiftLeft) || self.held(Modifier::ShiftRight)
+    }
+
+    pub fn meta(&self) -> bool {
+        self.held(Modifier::MetaLeft) || self.held(Modifier::MetaRight)
+    }
+
+    /// A hotkey of `key` and the modifiers held now, with the options of `like`. Without a
+    /// key, modifiers held on one side only are recorded as that side.
+    pub fn recorded(&self, key: Option<Key>, like: &Hotkey) -> Hotkey {
+        let side = |left: Modifier, right: Modifier| match (
+            key.is_some(),
+            self.held(left),
+            self.held(right),
+        ) {
+            (false, true, false) => Side::Left,
+            (false, false, true) => Side::Right,
+            // Either side keeps working if the user switches hands.
+            _ => Side::Either,
+        };
+        // Modifiers alone would fire with every shortcut that uses them.
+        let trigger =
+            if key.is_none() && like.trigger == Trigger::Press && like.mode == HotkeyMode::Toggle {
+                Trigger::Tap
+            } else {
+                like.trigger
+            };
+        Hotkey {
+            ctrl: self.ctrl(),
+            alt: self.alt(),
+            shift: self.shift(),
+            meta: self.meta(),
+            altgr: self.altgr(),
+            ctrl_side: side(Modifier::ControlLeft, Modifier::ControlRight),
+            shift_side: side(Modifier::ShiftLeft, Modifier::ShiftRight),
+            meta_side: side(Modifier::MetaLeft, Modifier::MetaRight),
+            extra_modifiers: like.extra_modifiers,
+            key,
+            mode: like.mode,
+            trigger,
+            timing: like.timing,
+            then: Vec::new(),
+        }
+    }
+
+    fn held(&self, m: Modifier) -> bool {
+        self.modifiers[m as usize]
+    }
+
+    fn combo_matches(&self, hotkey: &Hotkey, key: &Key) -> bool {
+        hotkey.key.as_ref().is_some_and(|k| k.normalized() == *key) && self.modifiers_match(hotkey)
+    }
 
-                if let Some(main_key) = map_rdev_to_key(key) {
-                    let hot = { settings.read().unwrap().hotkey.clone() };
-                    if hot.key == main_key {
-                        toggled_for_combo = false;
-                    }
+    fn modifiers_match(&self, hotkey: &Hotkey) -> bool {
+        use Modifier as M;
+        let extra = hotkey.extra_modifiers;
+        let pairs = [
+            (
+                hotkey.ctrl,
+                hotkey.ctrl_side,
+                M::ControlLeft,
+                M::ControlRight,
+            ),
+            (hotkey.shift, hotkey.shift_side, M::ShiftLeft, M::ShiftRight),
+            (hotkey.meta, hotkey.meta_side, M::MetaLeft, M::MetaRight),
+        ];
+        let pairs_match = pairs.into_iter().all(|(wanted, side, left, right)| {
+            let (left, right) = (self.held(left), self.held(right));
+            match (wanted, side) {
+                (false, _) => extra || !(left || right),
+                (true, Side::Either) => left || right,
+                (true, Side::Left) => left && (extra || !right),
+                (true, Side::Right) => right && (extra || !left),
+            }
+        });
+        let singles_match = [(hotkey.alt, M::AltLeft), (hotkey.altgr, M::AltRight)]
+            .into_iter()
+            .all(|(wanted, m)| {
+                if wanted {
+                    self.held(m)
+                } else {
+                    extra || !self.held(m)
                 }
+            });
+        pairs_match && singles_match
+    }
+
+    fn go_down(&mut self, hotkey: &Hotkey, key: Option<Key>, now: Instant) -> Option<Decision> {
+        let fires = hotkey.trigger == Trigger::Press;
+        self.down = Some(Down {
+            key,
+            since: now,
+            interrupted: false,
+            fired: fires,
+            deadline: (hotkey.trigger == Trigger::LongPress)
+                .then(|| now + hotkey.timing.long_press()),
+        });
+        fires.then(|| self.fire(hotkey))
+    }
 
-                if !ctrl && !alt && !shift && !meta {
-                    toggled_for_combo = false;
+    fn go_up(&mut self, hotkey: &Hotkey, now: Instant) -> Option<Decision> {
+        let down = self.down.take()?;
+        if self.holding {
+            self.holding = false;
+            return Some(Decision::Release);
+        }
+        if down.fired || down.interrupted {
+            self.last_tap = None;
+            return None;
+        }
+        let held_for = now.saturating_duration_since(down.since);
+        match hotkey.trigger {
+            Trigger::Press => None,
+            Trigger::Tap => (held_for <= hotkey.timing.tap()).then_some(Decision::Toggle),
+            Trigger::DoubleTap if held_for > hotkey.timing.tap() => {
+                self.last_tap = None;
+                None
+            }
+            Trigger::DoubleTap => {
+                let first = self.last_tap.take().filter(|end| {
+                    down.since.saturating_duration_since(*end) <= hotkey.timing.double_tap()
+                });
+                if first.is_some() {
+                    return Some(Decision::Toggle);
                 }
+                self.last_tap = Some(now);
+                None
+            }
+            // Let go before a tick noticed it was long enough; a hold is over already.
+            Trigger::LongPress => (held_for >= hotkey.timing.long_press()
+                && hotkey.mode == HotkeyMode::Toggle)
+                .then_some(Decision::Toggle),
+        }
+    }
+
+    fn fire(&mut self, hotkey: &Hotkey) -> Decision {
+        match hotkey.mode {
+            HotkeyMode::Hold if !hotkey.trigger.is_tap() => {
+                self.holding = true;
+                Decision::Press
             }
-            _ => {}
+            _ => Decision::Toggle,
+        }
+    }
+}
+
+/// Whether any step of `hotkey` is on `key`, which must be normalized.
+fn uses_key(hotkey: &Hotkey, key: &Key) -> bool {
+    (0..=hotkey.then.len())
+        .filter_map(|i| hotkey.step(i))
+        .any(|step| step.key.as_ref().is_some_and(|k| k.normalized() == *key))
+}
+
+/// A [`HotkeyMatcher`] per entry of [`Settings::hotkeys`].
+#[derive(Debug, Clone, Default)]
+pub struct BindingsMatcher {
+    matchers: Vec<HotkeyMatcher>,
+}
+
+impl BindingsMatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The matcher of the main hotkey.
+    pub fn main(&self) -> &HotkeyMatcher {
+        const IDLE: &HotkeyMatcher = &HotkeyMatcher {
+            modifiers: [false; 8],
+            held_keys: Vec::new(),
+            down: None,
+            holding: false,
+            last_tap: None,
+            sequence: None,
+            consumed: Vec::new(),
+        };
+        self.matchers.first().unwrap_or(IDLE)
+    }
+
+    pub fn handle(&mut self, settings: &Settings, event: &KeyEvent) -> Vec<(Command, Decision)> {
+        self.handle_at(settings, event, Instant::now())
+    }
+
+    /// Feeds one event to every matcher and returns what each binding has to do.
+    pub fn handle_at(
+        &mut self,
+        settings: &Settings,
+        event: &KeyEvent,
+        now: Instant,
+    ) -> Vec<(Command, Decision)> {
+        self.each(settings, |matcher, hotkey| {
+            matcher.handle_at(hotkey, event, now)
+        })
+    }
+
+    pub fn tick(&mut self, settings: &Settings, now: Instant) -> Vec<(Command, Decision)> {
+        self.each(settings, |matcher, hotkey| matcher.tick(hotkey, now))
+    }
+
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.matchers
+            .iter()
+            .filter_map(HotkeyMatcher::next_deadline)
+            .min()
+    }
+
+    /// Whether any binding [consumes](HotkeyMatcher::consumes) `event`.
+    pub fn consumes(&self, event: &KeyEvent) -> bool {
+        self.matchers.iter().any(|m| m.consumes(event))
+    }
+
+    /// Like [`HotkeyMatcher::reset`]; only the main hotkey can be held.
+    pub fn reset(&mut self) -> Option<Decision> {
+        let decision = self.matchers.first_mut().and_then(HotkeyMatcher::reset);
+        self.matchers.clear();
+        decision
+    }
+
+    /// The steps typed so far of the longest sequence waiting for its next step, like
+    /// `Ctrl+K`.
+    pub fn pending(&self, settings: &Settings) -> Option<String> {
+        let (matcher, hotkey) = self
+            .matchers
+            .iter()
+            .zip(settings.hotkeys().map(|(hotkey, _)| hotkey))
+            .max_by_key(|(matcher, _)| matcher.pending_steps())?;
+        let typed: Vec<String> = (0..matcher.pending_steps())
+            .filter_map(|i| hotkey.step(i))
+            .map(|step| Hotkey {
+                then: Vec::new(),
+                ..step.clone()
+            })
+            .map(|step| step.to_string())
+            .collect();
+        (!typed.is_empty()).then(|| typed.join(", "))
+    }
+
+    fn each(
+        &mut self,
+        settings: &Settings,
+        mut f: impl FnMut(&mut HotkeyMatcher, &Hotkey) -> Option<Decision>,
+    ) -> Vec<(Command, Decision)> {
+        self.matchers
+            .resize_with(settings.bindings.len() + 1, HotkeyMatcher::new);
+        self.matchers
+            .iter_mut()
+            .zip(settings.hotkeys())
+            .filter_map(|(matcher, (hotkey, command))| {
+                f(matcher, hotkey).map(|decision| (command.clone(), decision))
+            })
+            .collect()
+    }
+}
+
+/// Applies a matcher decision to the engine.
+pub fn apply_decision(engine: &Engine, decision: Decision) {
+    match decision {
+        Decision::Toggle => engine.toggle(),
+        Decision::Press => engine.start_clicking(),
+        Decision::Release => engine.stop(),
+    }
+}
+
+/// Carries out what a binding's hotkey decided.
+pub fn run_command(engine: &Engine, command: &Command, decision: Decision) {
+    match command {
+        Command::Toggle => apply_decision(engine, decision),
+        // Everything else happens once, on the press.
+        _ if decision == Decision::Release => {}
+        _ => engine.adjust(command),
+    }
+}
+
+/// Feeds a listener event to the matchers, or to a recording in progress. Returns
+/// whether it belongs to a hotkey.
+pub fn handle_event(engine: &Engine, matcher: &mut BindingsMatcher, event: &KeyEvent) -> bool {
+    // A release stops being consumed once handled.
+    let consumed = matcher.consumes(event);
+    let settings = engine.settings().read().unwrap().clone();
+    // Letting go of modifiers without pressing a key records them alone.
+    let modifiers_only = matches!(event, KeyEvent::Release(KeyInput::Modifier(_)))
+        .then(|| matcher.main().recorded(None, &settings.hotkey));
+    // The matchers keep tracking modifiers while recording.
+    let mut decisions = matcher.handle(&settings, event);
+    if engine.is_recording() {
+        let recorded = match event {
+            KeyEvent::Press(KeyInput::Key(key)) => Some(
+                matcher
+                    .main()
+                    .recorded(Some(key.normalized()), &settings.hotkey),
+            ),
+            _ => modifiers_only,
         };
+        // A plain left click is how the recording is cancelled in the window, or how
+        // focus returns to it.
+        let clicked = |hotkey: &Hotkey| {
+            hotkey.key == Some(Key::Mouse(MouseButton::Left)) && !hotkey.has_modifiers()
+        };
+        if let Some(recorded) = recorded.filter(|r| !clicked(r)) {
+            let cancel = recorded.key == Some(Key::Escape) && !recorded.has_modifiers();
+            engine.finish_recording((!cancel).then_some(recorded));
+        }
+        // A hold that ends during the recording must still stop the clicker.
+        decisions.retain(|(_, decision)| *decision == Decision::Release);
+    }
+    for (command, decision) in decisions {
+        run_command(engine, &command, decision);
+    }
+    engine.set_pending_sequence(matcher.pending(&settings));
+    consumed || matcher.consumes(event)
+}
 
-        if let Err(e) = listen(callback) {
-            error!("Global input listener failed: {:?}", e);
+/// Listeners call this at [`BindingsMatcher::next_deadline`].
+pub(crate) fn handle_tick(engine: &Engine, matcher: &mut BindingsMatcher) {
+    let settings = engine.settings().read().unwrap().clone();
+    let decisions = matcher.tick(&settings, Instant::now());
+    if !engine.is_recording() {
+        for (command, decision) in decisions {
+            run_command(engine, &command, decision);
+        }
+    }
+    engine.set_pending_sequence(matcher.pending(&settings));
+}
+
+/// Whether to read `/dev/input`; rdev needs X11 on Linux and cannot suppress hotkeys.
+pub fn uses_evdev(settings: &Settings, session: Session) -> bool {
+    match settings.listener {
+        InputListener::Auto => {
+            matches!(session, Session::Wayland | Session::Headless)
+                || (settings.suppress_hotkeys && cfg!(target_os = "linux"))
+        }
+        InputListener::Rdev => false,
+        InputListener::Evdev => true,
+    }
+}
+
+/// Starts the global hotkey listener chosen by the `listener` setting and returns its name.
+pub fn start_hotkey_listener(engine: Engine) -> &'static str {
+    let (choice, suppress, use_evdev) = {
+        let settings = engine.settings().read().unwrap();
+        (
+            settings.listener,
+            settings.suppress_hotkeys,
+            uses_evdev(&settings, Session::detect()),
+        )
+    };
+
+    #[cfg(target_os = "linux")]
+    if use_evdev {
+        match crate::evdev_listener::start_evdev_listener(engine.clone()) {
+            Ok(()) => {
+                info!("Listening for hotkeys with evdev");
+                return "evdev";
+            }
+            Err(e) if choice == InputListener::Evdev => {
+                engine.report_health(
+                    Subsystem::Listener,
+                    Health::Failed(format!(
+                        "cannot watch {}: {e}",
+                        crate::evdev_listener::INPUT_DIR
+                    )),
+                );
+                retry_evdev_listener(engine);
+                return "evdev";
+            }
+            Err(e) => warn!("evdev hotkey listener unavailable, using rdev: {}", e),
+        }
+    }
+    #[cfg(not(target_os = "linux"))]
+    if use_evdev {
+        warn!("evdev hotkey listener is only available on Linux, using rdev");
+    }
+
+    if suppress {
+        warn!("rdev cannot suppress hotkeys; they reach the focused window too");
+    }
+    info!("Listening for hotkeys with rdev");
+    start_rdev_listener(engine);
+    "rdev"
+}
+
+/// Keeps trying to start the evdev listener when it was chosen explicitly.
+#[cfg(target_os = "linux")]
+fn retry_evdev_listener(engine: Engine) {
+    std::thread::spawn(move || {
+        let mut backoff = Backoff::new();
+        loop {
+            std::thread::sleep(backoff.next_delay());
+            if crate::evdev_listener::start_evdev_listener(engine.clone()).is_ok() {
+                info!("Listening for hotkeys with evdev");
+                return;
+            }
         }
     });
 }
 
-fn update_mods_on_key(
-    key: RdevKey,
-    is_down: bool,
-    ctrl: &mut bool,
-    alt: &mut bool,
-    shift: &mut bool,
-    meta: &mut bool,
-) {
-    match key {
-        RdevKey::ShiftLeft | RdevKey::ShiftRight => *shift = is_down,
-        RdevKey::ControlLeft | RdevKey::ControlRight => *ctrl = is_down,
-        RdevKey::Alt | RdevKey::AltGr => *alt = is_down,
-        RdevKey::MetaLeft | RdevKey::MetaRight => *meta = is_down,
-        _ => {}
+pub fn start_rdev_listener(engine: Engine) {
+    std::thread::spawn(move || {
+        let mut backoff = Backoff::new();
+        loop {
+            let started = Instant::now();
+            let reason = match listen(rdev_callback(engine.clone())) {
+                Err(e) => format!("rdev could not listen: {e:?}"),
+                Ok(()) => "rdev stopped listening".to_string(),
+            };
+            engine.report_health(Subsystem::Listener, Health::Failed(reason));
+            // A listener that ran for a while is not failing repeatedly.
+            if started.elapsed() > LISTENER_STABLE {
+                backoff.reset();
+            }
+            std::thread::sleep(backoff.next_delay());
+        }
+    });
+}
+
+const LISTENER_STABLE: Duration = Duration::from_secs(60);
+
+fn rdev_callback(engine: Engine) -> impl FnMut(Event) + 'static {
+    // rdev only calls back on input, so long presses are fired from another thread.
+    let matcher = Arc::new(Mutex::new(BindingsMatcher::new()));
+    let wake_ticker = spawn_ticker(engine.clone(), Arc::clone(&matcher));
+    let mut receiving = false;
+
+    move |event: Event| {
+        if !receiving {
+            receiving = true;
+            engine.report_health(Subsystem::Listener, Health::Ok);
+        }
+        let event = match event.event_type {
+            EventType::KeyPress(key) => map_rdev_to_input(key).map(KeyEvent::Press),
+            EventType::KeyRelease(key) => map_rdev_to_input(key).map(KeyEvent::Release),
+            EventType::ButtonPress(button) => map_rdev_button_to_input(button).map(KeyEvent::Press),
+            EventType::ButtonRelease(button) => {
+                map_rdev_button_to_input(button).map(KeyEvent::Release)
+            }
+            _ => None,
+        };
+        if let Some(event) = event {
+            let mut matcher = matcher.lock().unwrap();
+            handle_event(&engine, &mut matcher, &event);
+            if matcher.next_deadline().is_some() {
+                let _ = wake_ticker.send(());
+            }
+        }
     }
 }
+
+/// Ticks `matcher` at its deadline; send to the returned channel when it may have moved.
+fn spawn_ticker(engine: Engine, matcher: Arc<Mutex<BindingsMatcher>>) -> mpsc::Sender<()> {
+    let (tx, rx) = mpsc::channel::<()>();
+    std::thread::spawn(move || {
+        loop {
+            let next = matcher.lock().unwrap().next_deadline();
+            let woken = match next {
+                Some(deadline) => {
+                    rx.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                }
+                None => rx.recv().map_err(|_| mpsc::RecvTimeoutError::Disconnected),
+            };
+            match woken {
+                Ok(()) => {}
+                Err(mpsc::RecvTimeoutError::Timeout) => {
+                    handle_tick(&engine, &mut matcher.lock().unwrap());
+                }
+                Err(mpsc::RecvTimeoutError::Disconnected) => return,
+            }
+        }
+    });
+    tx
+}
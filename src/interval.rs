@@ -0,0 +1,226 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::time::Duration;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Unit an [`Interval`] was entered in. Rates (`Cps`, `Cpm`) are converted to their period.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntervalUnit {
+    Micros,
+    Millis,
+    Seconds,
+    Minutes,
+    Hours,
+    Cps,
+    Cpm,
+}
+
+impl IntervalUnit {
+    pub const ALL: [IntervalUnit; 7] = [
+        IntervalUnit::Micros,
+        IntervalUnit::Millis,
+        IntervalUnit::Seconds,
+        IntervalUnit::Minutes,
+        IntervalUnit::Hours,
+        IntervalUnit::Cps,
+        IntervalUnit::Cpm,
+    ];
+
+    pub fn suffix(self) -> &'static str {
+        match self {
+            IntervalUnit::Micros => "us",
+            IntervalUnit::Millis => "ms",
+            IntervalUnit::Seconds => "s",
+            IntervalUnit::Minutes => "min",
+            IntervalUnit::Hours => "h",
+            IntervalUnit::Cps => "cps",
+            IntervalUnit::Cpm => "cpm",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            IntervalUnit::Micros => "µs",
+            IntervalUnit::Millis => "ms",
+            IntervalUnit::Seconds => "s",
+            IntervalUnit::Minutes => "min",
+            IntervalUnit::Hours => "h",
+            IntervalUnit::Cps => "CPS",
+            IntervalUnit::Cpm => "CPM",
+        }
+    }
+
+    fn from_suffix(s: &str) -> Option<Self> {
+        Some(match s.to_ascii_lowercase().as_str() {
+            "us" | "µs" | "μs" => IntervalUnit::Micros,
+            "" | "ms" => IntervalUnit::Millis,
+            "s" | "sec" => IntervalUnit::Seconds,
+            "m" | "min" => IntervalUnit::Minutes,
+            "h" => IntervalUnit::Hours,
+            "cps" => IntervalUnit::Cps,
+            "cpm" => IntervalUnit::Cpm,
+            _ => return None,
+        })
+    }
+
+    /// Seconds per unit for durations; `None` for rates.
+    fn seconds(self) -> Option<f64> {
+        match self {
+            IntervalUnit::Micros => Some(1e-6),
+            IntervalUnit::Millis => Some(1e-3),
+            IntervalUnit::Seconds => Some(1.0),
+            IntervalUnit::Minutes => Some(60.0),
+            IntervalUnit::Hours => Some(3600.0),
+            IntervalUnit::Cps | IntervalUnit::Cpm => None,
+        }
+    }
+}
+
+/// Time between actions, kept in the unit the user chose so it round-trips through
+/// the GUI and the config file unchanged. Resolves to microsecond precision.
+///
+/// Serialized as a string such as `"60ms"`, `"250us"`, `"15min"` or `"2500cps"`.
+/// A bare number is read as milliseconds, which is how the old `interval_ms` was stored.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Interval {
+    pub value: f64,
+    pub unit: IntervalUnit,
+}
+
+impl Interval {
+    pub const fn new(value: f64, unit: IntervalUnit) -> Self {
+        Self { value, unit }
+    }
+
+    pub const fn millis(value: f64) -> Self {
+        Self::new(value, IntervalUnit::Millis)
+    }
+
+    /// Length of one period in seconds; not finite or non-positive for unusable values.
+    pub fn as_secs_f64(&self) -> f64 {
+        match self.unit {
+            IntervalUnit::Cps => 1.0 / self.value,
+            IntervalUnit::Cpm => 60.0 / self.value,
+            unit => self.value * unit.seconds().unwrap_or(1.0),
+        }
+    }
+
+    /// The period rounded to whole microseconds; zero for unusable values.
+    pub fn as_duration(&self) -> Duration {
+        let micros = (self.as_secs_f64() * 1e6).round();
+        if micros.is_finite() && micros > 0.0 {
+            Duration::from_micros(micros.min(u64::MAX as f64) as u64)
+        } else {
+            Duration::ZERO
+        }
+    }
+
+    pub fn per_second(&self) -> f64 {
+        1.0 / self.as_secs_f64()
+    }
+
+    /// The same period expressed in `unit`.
+    #[must_use]
+    pub fn converted(&self, unit: IntervalUnit) -> Self {
+        let secs = self.as_secs_f64();
+        let value = match unit {
+            IntervalUnit::Cps => 1.0 / secs,
+            IntervalUnit::Cpm => 60.0 / secs,
+            u => secs / u.seconds().unwrap_or(1.0),
+        };
+        Self::new(value, unit)
+    }
+
+    /// The period times `factor`, in the same unit.
+    #[must_use]
+    pub fn scaled(&self, factor: f64) -> Self {
+        let value = match self.unit {
+            IntervalUnit::Cps | IntervalUnit::Cpm => self.value / factor,
+            _ => self.value * factor,
+        };
+        Self::new(round_significant(value), self.unit)
+    }
+
+    /// The rate changed by `cps` actions per second, in the same unit.
+    #[must_use]
+    pub fn stepped_cps(&self, cps: f64) -> Self {
+        let stepped = Self::new(self.per_second() + cps, IntervalUnit::Cps).converted(self.unit);
+        Self::new(round_significant(stepped.value), self.unit)
+    }
+}
+
+/// Trims unit conversions like 16.666666667 to something readable.
+pub fn round_significant(v: f64) -> f64 {
+    if v == 0.0 || !v.is_finite() {
+        return v;
+    }
+    let scale = 10f64.powi(5 - v.abs().log10().floor() as i32);
+    (v * scale).round() / scale
+}
+
+impl Default for Interval {
+    fn default() -> Self {
+        Self::millis(60.0)
+    }
+}
+
+impl Display for Interval {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.value, self.unit.suffix())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseIntervalError(String);
+
+impl Display for ParseIntervalError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "invalid interval {:?}; expected a number followed by us, ms, s, min, h, cps or cpm",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for ParseIntervalError {}
+
+impl FromStr for Interval {
+    type Err = ParseIntervalError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed = s.trim();
+        let split = trimmed
+            .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-' || c == '+'))
+            .unwrap_or(trimmed.len());
+        let (number, suffix) = trimmed.split_at(split);
+        let value = number
+            .parse::<f64>()
+            .map_err(|_| ParseIntervalError(s.to_string()))?;
+        let unit = IntervalUnit::from_suffix(suffix.trim())
+            .ok_or_else(|| ParseIntervalError(s.to_string()))?;
+        Ok(Self::new(value, unit))
+    }
+}
+
+impl Serialize for Interval {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Interval {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Millis(f64),
+            Text(String),
+        }
+        match Repr::deserialize(deserializer)? {
+            Repr::Millis(ms) => Ok(Interval::millis(ms)),
+            Repr::Text(s) => s.parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
@@ -1,8 +1,172 @@
-use crate::settings::Key;
+use crate::hotkey::{KeyInput, Modifier};
+use crate::settings::{Key, MouseButton};
 
+#[cfg(feature = "gui")]
 use eframe::egui;
 use rdev::Key as RdevKey;
 
+/// Names for Linux key codes that have no [`Key`] variant of their own.
+const RAW_KEY_NAMES: &[(u16, &str)] = &[
+    (12, "Minus"),
+    (13, "Equal"),
+    (14, "Backspace"),
+    (15, "Tab"),
+    (26, "LeftBracket"),
+    (27, "RightBracket"),
+    (39, "Semicolon"),
+    (40, "Apostrophe"),
+    (41, "Grave"),
+    (43, "Backslash"),
+    (51, "Comma"),
+    (52, "Dot"),
+    (53, "Slash"),
+    (55, "KpAsterisk"),
+    (58, "CapsLock"),
+    (59, "F1"),
+    (60, "F2"),
+    (61, "F3"),
+    (62, "F4"),
+    (63, "F5"),
+    (69, "NumLock"),
+    (70, "ScrollLock"),
+    (71, "Kp7"),
+    (72, "Kp8"),
+    (73, "Kp9"),
+    (74, "KpMinus"),
+    (75, "Kp4"),
+    (76, "Kp5"),
+    (77, "Kp6"),
+    (78, "KpPlus"),
+    (79, "Kp1"),
+    (80, "Kp2"),
+    (81, "Kp3"),
+    (82, "Kp0"),
+    (83, "KpDot"),
+    (86, "IntlBackslash"),
+    (96, "KpEnter"),
+    (98, "KpSlash"),
+    (99, "PrintScreen"),
+    (102, "Home"),
+    (103, "Up"),
+    (104, "PageUp"),
+    (105, "Left"),
+    (106, "Right"),
+    (107, "End"),
+    (108, "Down"),
+    (109, "PageDown"),
+    (110, "Insert"),
+    (111, "Delete"),
+    (113, "Mute"),
+    (114, "VolumeDown"),
+    (115, "VolumeUp"),
+    (119, "Pause"),
+    (127, "Menu"),
+    (163, "NextTrack"),
+    (164, "PlayPause"),
+    (165, "PreviousTrack"),
+    (166, "StopMedia"),
+    (183, "F13"),
+    (184, "F14"),
+    (185, "F15"),
+    (186, "F16"),
+    (187, "F17"),
+    (188, "F18"),
+    (189, "F19"),
+    (190, "F20"),
+    (191, "F21"),
+    (192, "F22"),
+    (193, "F23"),
+    (194, "F24"),
+];
+
+/// The highest Linux key code (`KEY_MAX`).
+pub const MAX_RAW_KEY: u16 = 0x2ff;
+
+/// A readable name for a Linux key code: `VolumeUp`, the kernel's `KEY_PROG1`, or `Raw420`.
+pub fn raw_key_name(code: u16) -> String {
+    if let Some((_, name)) = RAW_KEY_NAMES.iter().find(|(c, _)| *c == code) {
+        return (*name).to_string();
+    }
+    #[cfg(target_os = "linux")]
+    {
+        let name = format!("{:?}", evdev::KeyCode::new(code));
+        if !name.starts_with("unknown") {
+            return name;
+        }
+    }
+    format!("Raw{code}")
+}
+
+/// The key for a Linux key code: its named variant, like `Key::Char('A')` for 30, if it
+/// has one, so it compares equal to what the listeners report; [`Key::Raw`] otherwise.
+pub fn key_for_code(code: u16) -> Key {
+    #[cfg(target_os = "linux")]
+    if let Some(key) = map_evdev_to_key(evdev::KeyCode::new(code)) {
+        return key;
+    }
+    Key::raw(code)
+}
+
+/// Whether the Linux key code is a modifier, which listeners report as such, not as a key.
+pub fn is_modifier_code(code: u16) -> bool {
+    // KEY_LEFTCTRL, KEY_LEFTSHIFT, KEY_RIGHTSHIFT, KEY_LEFTALT, KEY_RIGHTCTRL, KEY_RIGHTALT,
+    // KEY_LEFTMETA and KEY_RIGHTMETA.
+    matches!(code, 29 | 42 | 54 | 56 | 97 | 100 | 125 | 126)
+}
+
+/// Parses what [`raw_key_name`] produces, plus `raw:<code>` (decimal or `0x` hex).
+pub fn parse_raw_key(name: &str) -> Option<Key> {
+    if let Some((code, _)) = RAW_KEY_NAMES
+        .iter()
+        .find(|(_, n)| n.eq_ignore_ascii_case(name))
+    {
+        return Some(key_for_code(*code));
+    }
+    let lower = name.to_ascii_lowercase();
+    if let Some(number) = lower.strip_prefix("raw") {
+        let number = number.strip_prefix(':').unwrap_or(number);
+        let code = match number.strip_prefix("0x") {
+            Some(hex) => u16::from_str_radix(hex, 16),
+            None => number.parse(),
+        };
+        return code.ok().map(key_for_code);
+    }
+    #[cfg(target_os = "linux")]
+    if let Ok(code) = name.to_ascii_uppercase().parse::<evdev::KeyCode>() {
+        return map_evdev_to_key(code);
+    }
+    None
+}
+
+pub fn map_rdev_to_input(key: RdevKey) -> Option<KeyInput> {
+    use RdevKey as R;
+    let modifier = match key {
+        R::ControlLeft => Modifier::ControlLeft,
+        R::ControlRight => Modifier::ControlRight,
+        R::Alt => Modifier::AltLeft,
+        R::AltGr => Modifier::AltRight,
+        R::ShiftLeft => Modifier::ShiftLeft,
+        R::ShiftRight => Modifier::ShiftRight,
+        R::MetaLeft => Modifier::MetaLeft,
+        R::MetaRight => Modifier::MetaRight,
+        _ => return map_rdev_to_key(key).map(KeyInput::Key),
+    };
+    Some(KeyInput::Modifier(modifier))
+}
+
+/// Mouse buttons rdev reports; X11 numbers the thumb buttons 8 and 9.
+pub fn map_rdev_button_to_input(button: rdev::Button) -> Option<KeyInput> {
+    let button = match button {
+        rdev::Button::Left => MouseButton::Left,
+        rdev::Button::Right => MouseButton::Right,
+        rdev::Button::Middle => MouseButton::Middle,
+        rdev::Button::Unknown(8) => MouseButton::Back,
+        rdev::Button::Unknown(9) => MouseButton::Forward,
+        rdev::Button::Unknown(_) => return None,
+    };
+    Some(KeyInput::Key(Key::Mouse(button)))
+}
+
 pub fn map_rdev_to_key(key: RdevKey) -> Option<Key> {
     use RdevKey as R;
     Some(match key {
@@ -52,10 +216,210 @@ pub fn map_rdev_to_key(key: RdevKey) -> Option<Key> {
         R::Num7 => Key::Char('7'),
         R::Num8 => Key::Char('8'),
         R::Num9 => Key::Char('9'),
+        _ => return map_rdev_to_raw(key).map(Key::raw),
+    })
+}
+
+/// The Linux key code of a key rdev knows but [`Key`] has no variant for.
+fn map_rdev_to_raw(key: RdevKey) -> Option<u16> {
+    use RdevKey as R;
+    Some(match key {
+        R::Minus => 12,
+        R::Equal => 13,
+        R::Backspace => 14,
+        R::Tab => 15,
+        R::LeftBracket => 26,
+        R::RightBracket => 27,
+        R::SemiColon => 39,
+        R::Quote => 40,
+        R::BackQuote => 41,
+        R::BackSlash => 43,
+        R::Comma => 51,
+        R::Dot => 52,
+        R::Slash => 53,
+        R::KpMultiply => 55,
+        R::CapsLock => 58,
+        R::F1 => 59,
+        R::F2 => 60,
+        R::F3 => 61,
+        R::F4 => 62,
+        R::F5 => 63,
+        R::NumLock => 69,
+        R::ScrollLock => 70,
+        R::Kp7 => 71,
+        R::Kp8 => 72,
+        R::Kp9 => 73,
+        R::KpMinus => 74,
+        R::Kp4 => 75,
+        R::Kp5 => 76,
+        R::Kp6 => 77,
+        R::KpPlus => 78,
+        R::Kp1 => 79,
+        R::Kp2 => 80,
+        R::Kp3 => 81,
+        R::Kp0 => 82,
+        R::KpDelete => 83,
+        R::IntlBackslash => 86,
+        R::KpReturn => 96,
+        R::KpDivide => 98,
+        R::PrintScreen => 99,
+        R::Home => 102,
+        R::UpArrow => 103,
+        R::PageUp => 104,
+        R::LeftArrow => 105,
+        R::RightArrow => 106,
+        R::End => 107,
+        R::DownArrow => 108,
+        R::PageDown => 109,
+        R::Insert => 110,
+        R::Delete => 111,
+        R::Pause => 119,
+        // On X11 rdev reports the X keycode, which is the Linux key code plus 8.
+        #[cfg(target_os = "linux")]
+        R::Unknown(code) => u16::try_from(code).ok()?.checked_sub(8)?,
         _ => return None,
     })
 }
 
+/// Maps a Linux key code (US layout positions, as the kernel reports them).
+#[cfg(target_os = "linux")]
+pub fn map_evdev_to_input(code: evdev::KeyCode) -> Option<KeyInput> {
+    use evdev::KeyCode as K;
+    let modifier = match code {
+        K::KEY_LEFTCTRL => Modifier::ControlLeft,
+        K::KEY_RIGHTCTRL => Modifier::ControlRight,
+        K::KEY_LEFTALT => Modifier::AltLeft,
+        K::KEY_RIGHTALT => Modifier::AltRight,
+        K::KEY_LEFTSHIFT => Modifier::ShiftLeft,
+        K::KEY_RIGHTSHIFT => Modifier::ShiftRight,
+        K::KEY_LEFTMETA => Modifier::MetaLeft,
+        K::KEY_RIGHTMETA => Modifier::MetaRight,
+        _ => return map_evdev_to_key(code).map(KeyInput::Key),
+    };
+    Some(KeyInput::Modifier(modifier))
+}
+
+#[cfg(target_os = "linux")]
+pub fn map_evdev_to_key(code: evdev::KeyCode) -> Option<Key> {
+    use evdev::KeyCode as K;
+    Some(match code {
+        K::KEY_F6 => Key::F6,
+        K::KEY_F7 => Key::F7,
+        K::KEY_F8 => Key::F8,
+        K::KEY_F9 => Key::F9,
+        K::KEY_F10 => Key::F10,
+        K::KEY_F11 => Key::F11,
+        K::KEY_F12 => Key::F12,
+        K::KEY_SPACE => Key::Space,
+        K::KEY_ENTER => Key::Enter,
+        K::KEY_ESC => Key::Escape,
+        K::KEY_A => Key::Char('A'),
+        K::KEY_B => Key::Char('B'),
+        K::KEY_C => Key::Char('C'),
+        K::KEY_D => Key::Char('D'),
+        K::KEY_E => Key::Char('E'),
+        K::KEY_F => Key::Char('F'),
+        K::KEY_G => Key::Char('G'),
+        K::KEY_H => Key::Char('H'),
+        K::KEY_I => Key::Char('I'),
+        K::KEY_J => Key::Char('J'),
+        K::KEY_K => Key::Char('K'),
+        K::KEY_L => Key::Char('L'),
+        K::KEY_M => Key::Char('M'),
+        K::KEY_N => Key::Char('N'),
+        K::KEY_O => Key::Char('O'),
+        K::KEY_P => Key::Char('P'),
+        K::KEY_Q => Key::Char('Q'),
+        K::KEY_R => Key::Char('R'),
+        K::KEY_S => Key::Char('S'),
+        K::KEY_T => Key::Char('T'),
+        K::KEY_U => Key::Char('U'),
+        K::KEY_V => Key::Char('V'),
+        K::KEY_W => Key::Char('W'),
+        K::KEY_X => Key::Char('X'),
+        K::KEY_Y => Key::Char('Y'),
+        K::KEY_Z => Key::Char('Z'),
+        K::KEY_0 => Key::Char('0'),
+        K::KEY_1 => Key::Char('1'),
+        K::KEY_2 => Key::Char('2'),
+        K::KEY_3 => Key::Char('3'),
+        K::KEY_4 => Key::Char('4'),
+        K::KEY_5 => Key::Char('5'),
+        K::KEY_6 => Key::Char('6'),
+        K::KEY_7 => Key::Char('7'),
+        K::KEY_8 => Key::Char('8'),
+        K::KEY_9 => Key::Char('9'),
+        K::BTN_LEFT => Key::Mouse(MouseButton::Left),
+        K::BTN_RIGHT => Key::Mouse(MouseButton::Right),
+        K::BTN_MIDDLE => Key::Mouse(MouseButton::Middle),
+        K::BTN_SIDE => Key::Mouse(MouseButton::Back),
+        K::BTN_EXTRA => Key::Mouse(MouseButton::Forward),
+        _ => Key::raw(code.code()),
+    })
+}
+
+/// The key code that types `key` on a US layout; letters ignore case.
+#[cfg(target_os = "linux")]
+pub fn map_key_to_evdev(key: &Key) -> Option<evdev::KeyCode> {
+    use evdev::KeyCode as K;
+    Some(match key {
+        Key::F6 => K::KEY_F6,
+        Key::F7 => K::KEY_F7,
+        Key::F8 => K::KEY_F8,
+        Key::F9 => K::KEY_F9,
+        Key::F10 => K::KEY_F10,
+        Key::F11 => K::KEY_F11,
+        Key::F12 => K::KEY_F12,
+        Key::Space => K::KEY_SPACE,
+        Key::Enter => K::KEY_ENTER,
+        Key::Escape => K::KEY_ESC,
+        Key::Char('a' | 'A') => K::KEY_A,
+        Key::Char('b' | 'B') => K::KEY_B,
+        Key::Char('c' | 'C') => K::KEY_C,
+        Key::Char('d' | 'D') => K::KEY_D,
+        Key::Char('e' | 'E') => K::KEY_E,
+        Key::Char('f' | 'F') => K::KEY_F,
+        Key::Char('g' | 'G') => K::KEY_G,
+        Key::Char('h' | 'H') => K::KEY_H,
+        Key::Char('i' | 'I') => K::KEY_I,
+        Key::Char('j' | 'J') => K::KEY_J,
+        Key::Char('k' | 'K') => K::KEY_K,
+        Key::Char('l' | 'L') => K::KEY_L,
+        Key::Char('m' | 'M') => K::KEY_M,
+        Key::Char('n' | 'N') => K::KEY_N,
+        Key::Char('o' | 'O') => K::KEY_O,
+        Key::Char('p' | 'P') => K::KEY_P,
+        Key::Char('q' | 'Q') => K::KEY_Q,
+        Key::Char('r' | 'R') => K::KEY_R,
+        Key::Char('s' | 'S') => K::KEY_S,
+        Key::Char('t' | 'T') => K::KEY_T,
+        Key::Char('u' | 'U') => K::KEY_U,
+        Key::Char('v' | 'V') => K::KEY_V,
+        Key::Char('w' | 'W') => K::KEY_W,
+        Key::Char('x' | 'X') => K::KEY_X,
+        Key::Char('y' | 'Y') => K::KEY_Y,
+        Key::Char('z' | 'Z') => K::KEY_Z,
+        Key::Char('0') => K::KEY_0,
+        Key::Char('1') => K::KEY_1,
+        Key::Char('2') => K::KEY_2,
+        Key::Char('3') => K::KEY_3,
+        Key::Char('4') => K::KEY_4,
+        Key::Char('5') => K::KEY_5,
+        Key::Char('6') => K::KEY_6,
+        Key::Char('7') => K::KEY_7,
+        Key::Char('8') => K::KEY_8,
+        Key::Char('9') => K::KEY_9,
+        Key::Mouse(MouseButton::Left) => K::BTN_LEFT,
+        Key::Mouse(MouseButton::Right) => K::BTN_RIGHT,
+        Key::Mouse(MouseButton::Middle) => K::BTN_MIDDLE,
+        Key::Mouse(MouseButton::Back) => K::BTN_SIDE,
+        Key::Mouse(MouseButton::Forward) => K::BTN_EXTRA,
+        Key::Raw { code, .. } => K::new(*code),
+        Key::Char(_) | Key::Mouse(_) => return None,
+    })
+}
+
+#[cfg(feature = "gui")]
 pub fn map_egui_key_to_key(k: egui::Key) -> Option<Key> {
     use egui::Key as E;
     Some(match k {
@@ -95,6 +459,65 @@ pub fn map_egui_key_to_key(k: egui::Key) -> Option<Key> {
         E::X => Key::Char('X'),
         E::Y => Key::Char('Y'),
         E::Z => Key::Char('Z'),
+        E::Num0 => Key::Char('0'),
+        E::Num1 => Key::Char('1'),
+        E::Num2 => Key::Char('2'),
+        E::Num3 => Key::Char('3'),
+        E::Num4 => Key::Char('4'),
+        E::Num5 => Key::Char('5'),
+        E::Num6 => Key::Char('6'),
+        E::Num7 => Key::Char('7'),
+        E::Num8 => Key::Char('8'),
+        E::Num9 => Key::Char('9'),
+        _ => return map_egui_key_to_raw(k).map(Key::raw),
+    })
+}
+
+/// The Linux key code of an egui key [`Key`] has no variant for, on a US layout.
+#[cfg(feature = "gui")]
+fn map_egui_key_to_raw(k: egui::Key) -> Option<u16> {
+    use egui::Key as E;
+    Some(match k {
+        E::Minus => 12,
+        E::Equals => 13,
+        E::Backspace => 14,
+        E::Tab => 15,
+        E::OpenBracket => 26,
+        E::CloseBracket => 27,
+        E::Semicolon => 39,
+        E::Quote => 40,
+        E::Backtick => 41,
+        E::Backslash => 43,
+        E::Comma => 51,
+        E::Period => 52,
+        E::Slash => 53,
+        E::F1 => 59,
+        E::F2 => 60,
+        E::F3 => 61,
+        E::F4 => 62,
+        E::F5 => 63,
+        E::Home => 102,
+        E::ArrowUp => 103,
+        E::PageUp => 104,
+        E::ArrowLeft => 105,
+        E::ArrowRight => 106,
+        E::End => 107,
+        E::ArrowDown => 108,
+        E::PageDown => 109,
+        E::Insert => 110,
+        E::Delete => 111,
+        E::F13 => 183,
+        E::F14 => 184,
+        E::F15 => 185,
+        E::F16 => 186,
+        E::F17 => 187,
+        E::F18 => 188,
+        E::F19 => 189,
+        E::F20 => 190,
+        E::F21 => 191,
+        E::F22 => 192,
+        E::F23 => 193,
+        E::F24 => 194,
         _ => return None,
     })
 }
@@ -0,0 +1,58 @@
+//! The clicking engine behind the `autoclicker` app, usable without its GUI.
+//!
+//! An [`Engine`] owns a click worker thread and the clicker's [`ClickerState`]. It reads
+//! the shared [`Settings`] and injects actions through a [`Backend`]; subscribers get an
+//! [`EngineEvent`] for every change. The GUI and the headless `autoclicker run` command are
+//! both thin layers over this API.
+//!
+//! ```
+//! use std::sync::{Arc, RwLock, mpsc};
+//!
+//! use autoclicker::{ClickerState, Engine, EngineEvent, NullBackend, Settings};
+//!
+//! let settings = Settings {
+//!     interval: "1ms".parse().unwrap(),
+//!     click_limit: Some(3),
+//!     ..Settings::default()
+//! };
+//! let (tx, rx) = mpsc::channel();
+//! let engine = Engine::builder(Arc::new(RwLock::new(settings)), NullBackend)
+//!     .subscribe(move |event| {
+//!         let _ = tx.send(event.clone());
+//!     })
+//!     .start();
+//!
+//! engine.start_clicking();
+//! while let Ok(event) = rx.recv() {
+//!     if let EngineEvent::StateChanged { to: ClickerState::StoppedByLimit, .. } = event {
+//!         break;
+//!     }
+//! }
+//! assert_eq!(engine.clicks(), 3);
+//! ```
+//!
+//! Global hotkeys are wired up with [`hotkey::start_hotkey_listener`], and settings files
+//! are handled by [`settings`], [`persist`] and [`watch`].
+
+pub mod backend;
+pub mod doctor;
+pub mod engine;
+#[cfg(target_os = "linux")]
+pub mod evdev_listener;
+pub mod health;
+pub mod hotkey;
+pub mod interval;
+pub mod keymap;
+pub mod persist;
+pub mod scheduler;
+pub mod session;
+pub mod settings;
+pub mod validate;
+pub mod watch;
+pub mod worker;
+
+pub use backend::{Backend, BackendError, NullBackend, default_backend, select_backend};
+pub use engine::{ClickerState, Engine, EngineBuilder, EngineEvent};
+pub use health::{Health, Subsystem};
+pub use interval::{Interval, IntervalUnit};
+pub use settings::{Action, Hotkey, Key, MouseButton, Settings, TimingMode};
@@ -1,14 +1,34 @@
+#[cfg(feature = "gui")]
 mod app;
-mod hotkey;
-mod keymap;
-mod settings;
-mod worker;
+mod cli;
 
+use std::process::ExitCode;
+
+use autoclicker::settings;
+use clap::Parser;
+#[cfg(feature = "gui")]
 use eframe::egui;
+use tracing::{error, info};
 
-fn main() -> eframe::Result<()> {
+fn main() -> ExitCode {
     setup_tracing();
-    let app = app::AutoClickerApp::new();
+    let cli = cli::Cli::parse();
+    let location = settings::resolve_config_location(cli.config);
+    if let Some(loc) = &location {
+        info!(
+            "Using settings file {} ({})",
+            loc.path.display(),
+            loc.source
+        );
+    }
+    match cli.command {
+        Some(command) => cli::run(command, location),
+        None => run_gui(location),
+    }
+}
+
+#[cfg(feature = "gui")]
+fn run_gui(location: Option<settings::ConfigLocation>) -> ExitCode {
     let native_options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_resizable(true)
@@ -16,11 +36,23 @@ fn main() -> eframe::Result<()> {
             .with_min_inner_size(egui::vec2(280.0, 120.0)),
         ..Default::default()
     };
-    eframe::run_native(
+    match eframe::run_native(
         "Autoclicker",
         native_options,
-        Box::new(|_| Ok(Box::new(app))),
-    )
+        Box::new(|cc| Ok(Box::new(app::AutoClickerApp::new(location, &cc.egui_ctx)))),
+    ) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            error!("GUI failed: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(not(feature = "gui"))]
+fn run_gui(_location: Option<settings::ConfigLocation>) -> ExitCode {
+    error!("This build has no GUI; use `autoclicker run` to click headless");
+    ExitCode::FAILURE
 }
 
 fn setup_tracing() {
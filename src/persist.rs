@@ -0,0 +1,262 @@
+use std::fmt::{Display, Formatter};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock, mpsc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use tracing::{debug, error};
+
+use crate::settings::Settings;
+
+/// How many rotated copies of the previous settings file are kept next to it.
+pub const BACKUP_COUNT: usize = 3;
+
+/// Quiet period after the last change before the file is written.
+const DEBOUNCE: Duration = Duration::from_millis(400);
+/// Upper bound on how long a continuous stream of changes may postpone a write.
+const MAX_DELAY: Duration = Duration::from_secs(2);
+
+#[derive(Debug)]
+pub enum PersistError {
+    Serialize(toml::ser::Error),
+    Io {
+        op: &'static str,
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+impl PersistError {
+    fn io(op: &'static str, path: &Path, source: std::io::Error) -> Self {
+        Self::Io {
+            op,
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+}
+
+impl Display for PersistError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PersistError::Serialize(e) => write!(f, "failed to serialize settings: {e}"),
+            PersistError::Io { op, path, source } => {
+                write!(f, "failed to {op} {}: {source}", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for PersistError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PersistError::Serialize(e) => Some(e),
+            PersistError::Io { source, .. } => Some(source),
+        }
+    }
+}
+
+pub fn save_settings_atomic(
+    path: &Path,
+    settings: &Settings,
+    backups: usize,
+) -> Result<(), PersistError> {
+    let contents = toml::to_string_pretty(settings).map_err(PersistError::Serialize)?;
+    write_atomic(path, &contents, backups)
+}
+
+/// Replaces `path`, or the file it links to, atomically; keeps backups as `<name>.bak.N`.
+pub fn write_atomic(path: &Path, contents: &str, backups: usize) -> Result<(), PersistError> {
+    let target;
+    let path = if fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_symlink()) {
+        target = fs::canonicalize(path).map_err(|e| PersistError::io("resolve", path, e))?;
+        &target
+    } else {
+        path
+    };
+    let tmp = sibling_path(path, "tmp");
+    {
+        let mut file = File::create(&tmp).map_err(|e| PersistError::io("create", &tmp, e))?;
+        file.write_all(contents.as_bytes())
+            .map_err(|e| PersistError::io("write", &tmp, e))?;
+        file.sync_all()
+            .map_err(|e| PersistError::io("fsync", &tmp, e))?;
+    }
+
+    if path.exists() {
+        rotate_backups(path, backups)?;
+    }
+
+    fs::rename(&tmp, path).map_err(|e| PersistError::io("rename", &tmp, e))?;
+    sync_parent_dir(path)
+}
+
+pub fn backup_path(path: &Path, index: usize) -> PathBuf {
+    sibling_path(path, &format!("bak.{index}"))
+}
+
+fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+fn rotate_backups(path: &Path, backups: usize) -> Result<(), PersistError> {
+    if backups == 0 {
+        return Ok(());
+    }
+    for index in (1..backups).rev() {
+        let from = backup_path(path, index);
+        if from.exists() {
+            let to = backup_path(path, index + 1);
+            fs::rename(&from, &to).map_err(|e| PersistError::io("rotate", &from, e))?;
+        }
+    }
+    let first = backup_path(path, 1);
+    fs::copy(path, &first).map_err(|e| PersistError::io("back up", path, e))?;
+    Ok(())
+}
+
+#[cfg(unix)]
+fn sync_parent_dir(path: &Path) -> Result<(), PersistError> {
+    let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) else {
+        return Ok(());
+    };
+    File::open(dir)
+        .and_then(|d| d.sync_all())
+        .map_err(|e| PersistError::io("fsync", dir, e))
+}
+
+#[cfg(not(unix))]
+fn sync_parent_dir(_path: &Path) -> Result<(), PersistError> {
+    Ok(())
+}
+
+enum Command {
+    Save(Box<Settings>),
+    Flush(mpsc::Sender<()>),
+}
+
+/// Background writer that coalesces bursts of settings changes into a single atomic save.
+#[derive(Debug)]
+pub struct Persister {
+    tx: mpsc::Sender<Command>,
+    shared: Arc<Shared>,
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    last_error: RwLock<Option<Arc<PersistError>>>,
+    last_written: RwLock<Option<String>>,
+}
+
+impl Persister {
+    /// Starts the writer thread; `on_written` runs after every write attempt, successful or not.
+    pub fn spawn(path: PathBuf, on_written: impl Fn() + Send + 'static) -> Self {
+        let (tx, rx) = mpsc::channel::<Command>();
+        let shared = Arc::new(Shared::default());
+        let thread_shared = Arc::clone(&shared);
+        thread::spawn(move || run(&path, &rx, &thread_shared, &on_written));
+        Self { tx, shared }
+    }
+
+    /// Schedules `settings` to be written once changes settle.
+    pub fn save(&self, settings: Settings) {
+        let _ = self.tx.send(Command::Save(Box::new(settings)));
+    }
+
+    /// Writes any pending change immediately and waits for it to hit the disk.
+    pub fn flush(&self) {
+        let (ack_tx, ack_rx) = mpsc::channel();
+        if self.tx.send(Command::Flush(ack_tx)).is_ok() {
+            let _ = ack_rx.recv();
+        }
+    }
+
+    pub fn last_error(&self) -> Option<Arc<PersistError>> {
+        self.shared.last_error.read().unwrap().clone()
+    }
+
+    /// Returns a probe telling whether some file contents are exactly what this
+    /// persister wrote last, so watchers can ignore our own saves.
+    pub fn own_write_probe(&self) -> impl Fn(&str) -> bool + Send + Sync + 'static {
+        let shared = Arc::clone(&self.shared);
+        move |contents| shared.last_written.read().unwrap().as_deref() == Some(contents)
+    }
+}
+
+impl Drop for Persister {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+fn run(path: &Path, rx: &mpsc::Receiver<Command>, shared: &Shared, on_written: &impl Fn()) {
+    let mut pending: Option<(Settings, Instant)> = None;
+    loop {
+        let cmd = match &pending {
+            None => match rx.recv() {
+                Ok(cmd) => Some(cmd),
+                Err(_) => return,
+            },
+            Some((_, since)) => {
+                let wait = DEBOUNCE.min(MAX_DELAY.saturating_sub(since.elapsed()));
+                match rx.recv_timeout(wait) {
+                    Ok(cmd) => Some(cmd),
+                    Err(mpsc::RecvTimeoutError::Timeout) => None,
+                    Err(mpsc::RecvTimeoutError::Disconnected) => {
+                        write_pending(path, &mut pending, shared, on_written);
+                        return;
+                    }
+                }
+            }
+        };
+
+        match cmd {
+            Some(Command::Save(settings)) => {
+                let since = pending.take().map_or_else(Instant::now, |(_, since)| since);
+                pending = Some((*settings, since));
+                if since.elapsed() >= MAX_DELAY {
+                    write_pending(path, &mut pending, shared, on_written);
+                }
+            }
+            Some(Command::Flush(ack)) => {
+                write_pending(path, &mut pending, shared, on_written);
+                let _ = ack.send(());
+            }
+            None => write_pending(path, &mut pending, shared, on_written),
+        }
+    }
+}
+
+fn write_pending(
+    path: &Path,
+    pending: &mut Option<(Settings, Instant)>,
+    shared: &Shared,
+    on_written: &impl Fn(),
+) {
+    let Some((settings, _)) = pending.take() else {
+        return;
+    };
+    let result = toml::to_string_pretty(&settings)
+        .map_err(PersistError::Serialize)
+        .and_then(|contents| {
+            write_atomic(path, &contents, BACKUP_COUNT)?;
+            *shared.last_written.write().unwrap() = Some(contents);
+            Ok(())
+        });
+    match result {
+        Ok(()) => {
+            debug!("Saved settings to {}", path.display());
+            *shared.last_error.write().unwrap() = None;
+        }
+        Err(e) => {
+            error!("Failed to save settings: {}", e);
+            *shared.last_error.write().unwrap() = Some(Arc::new(e));
+        }
+    }
+    on_written();
+}
@@ -0,0 +1,224 @@
+use std::fmt::{Display, Formatter};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::settings::TimingMode;
+
+/// How long before a deadline we stop trusting the OS sleep and busy-wait instead.
+/// OS sleeps routinely overshoot by tens of microseconds (and by much more on Windows).
+#[cfg(windows)]
+pub const SPIN_THRESHOLD: Duration = Duration::from_millis(2);
+#[cfg(not(windows))]
+pub const SPIN_THRESHOLD: Duration = Duration::from_millis(1);
+
+/// How many missed fixed-rate ticks are caught up back-to-back before the schedule resyncs.
+const MAX_CATCH_UP_TICKS: u32 = 4;
+
+/// Computes absolute deadlines for the click loop.
+///
+/// With [`TimingMode::FixedRate`] each deadline is the previous deadline plus the interval,
+/// so time spent injecting does not accumulate. Short hiccups are caught up immediately; if
+/// the loop falls further behind (e.g. injection is slower than the interval) the missed
+/// ticks are dropped rather than replayed in a burst. [`TimingMode::FixedDelay`] waits a full
+/// interval after each action.
+#[derive(Debug, Clone)]
+pub struct Scheduler {
+    mode: TimingMode,
+    interval: Duration,
+    anchor: Instant,
+    next: Instant,
+}
+
+impl Scheduler {
+    pub fn new(mode: TimingMode, interval: Duration, now: Instant) -> Self {
+        Self {
+            mode,
+            interval,
+            anchor: now,
+            next: now + interval,
+        }
+    }
+
+    /// Restarts the schedule so the first action happens one interval after `now`.
+    pub fn start(&mut self, now: Instant) {
+        self.anchor = now;
+        self.next = now + self.interval;
+    }
+
+    /// Applies new settings; a changed interval takes effect for the pending deadline.
+    pub fn configure(&mut self, mode: TimingMode, interval: Duration) {
+        self.mode = mode;
+        if interval != self.interval {
+            self.interval = interval;
+            self.next = self.anchor + interval;
+        }
+    }
+
+    pub fn deadline(&self) -> Instant {
+        self.next
+    }
+
+    /// Records that the action for the current deadline finished at `now`.
+    pub fn advance(&mut self, now: Instant) {
+        match self.mode {
+            TimingMode::FixedRate => {
+                self.anchor = self.next;
+                self.next += self.interval;
+                if self.next + self.interval * MAX_CATCH_UP_TICKS < now {
+                    self.anchor = now;
+                    self.next = now;
+                }
+            }
+            TimingMode::FixedDelay => {
+                self.anchor = now;
+                self.next = now + self.interval;
+            }
+        }
+    }
+}
+
+/// Sleeps until `deadline`, spinning for the last [`SPIN_THRESHOLD`] to hit it precisely.
+pub fn sleep_until(deadline: Instant) {
+    loop {
+        let now = Instant::now();
+        if now >= deadline {
+            return;
+        }
+        let left = deadline - now;
+        if left > SPIN_THRESHOLD {
+            thread::sleep(left - SPIN_THRESHOLD);
+        } else {
+            std::hint::spin_loop();
+        }
+    }
+}
+
+/// Why [`wait_until`] returned.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Wake<T> {
+    Deadline,
+    Message(T),
+    Disconnected,
+}
+
+/// Like [`sleep_until`], but returns early as soon as a message arrives on `rx`.
+pub fn wait_until<T>(deadline: Instant, rx: &mpsc::Receiver<T>) -> Wake<T> {
+    loop {
+        let now = Instant::now();
+        if now >= deadline {
+            return Wake::Deadline;
+        }
+        let left = deadline - now;
+        let received = if left > SPIN_THRESHOLD {
+            rx.recv_timeout(left - SPIN_THRESHOLD)
+        } else {
+            std::hint::spin_loop();
+            rx.try_recv().map_err(|e| match e {
+                mpsc::TryRecvError::Empty => mpsc::RecvTimeoutError::Timeout,
+                mpsc::TryRecvError::Disconnected => mpsc::RecvTimeoutError::Disconnected,
+            })
+        };
+        match received {
+            Ok(msg) => return Wake::Message(msg),
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => return Wake::Disconnected,
+        }
+    }
+}
+
+/// Result of [`measure`]: how closely the scheduler tracked its target.
+#[derive(Debug, Clone)]
+pub struct Measurement {
+    pub mode: TimingMode,
+    pub target_rate: f64,
+    pub achieved_rate: f64,
+    pub ticks: usize,
+    pub mean_lateness: Duration,
+    pub p99_lateness: Duration,
+    pub max_lateness: Duration,
+    pub period_stddev: Duration,
+}
+
+impl Display for Measurement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "mode:           {:?}", self.mode)?;
+        writeln!(f, "ticks:          {}", self.ticks)?;
+        writeln!(f, "target rate:    {:.2}/s", self.target_rate)?;
+        writeln!(
+            f,
+            "achieved rate:  {:.2}/s ({:+.3}%)",
+            self.achieved_rate,
+            (self.achieved_rate / self.target_rate - 1.0) * 100.0
+        )?;
+        writeln!(
+            f,
+            "lateness:       mean {:?}, p99 {:?}, max {:?}",
+            self.mean_lateness, self.p99_lateness, self.max_lateness
+        )?;
+        write!(f, "period jitter:  {:?} (stddev)", self.period_stddev)
+    }
+}
+
+/// Runs the scheduler for `duration`, simulating an action that takes `work` each tick,
+/// and reports achieved rate and timing jitter.
+pub fn measure(
+    mode: TimingMode,
+    interval: Duration,
+    duration: Duration,
+    work: Duration,
+) -> Measurement {
+    let start = Instant::now();
+    let mut scheduler = Scheduler::new(mode, interval, start);
+    let mut ticks: Vec<Instant> = Vec::new();
+    let mut lateness: Vec<Duration> = Vec::new();
+
+    while start.elapsed() < duration {
+        let deadline = scheduler.deadline();
+        sleep_until(deadline);
+        let fired = Instant::now();
+        lateness.push(fired.saturating_duration_since(deadline));
+        ticks.push(fired);
+
+        let busy_until = fired + work;
+        while Instant::now() < busy_until {
+            std::hint::spin_loop();
+        }
+        scheduler.advance(Instant::now());
+    }
+
+    let periods: Vec<f64> = ticks
+        .windows(2)
+        .map(|w| (w[1] - w[0]).as_secs_f64())
+        .collect();
+    let achieved_rate = match (ticks.first(), ticks.last()) {
+        (Some(first), Some(last)) if !periods.is_empty() => {
+            periods.len() as f64 / (*last - *first).as_secs_f64()
+        }
+        _ => 0.0,
+    };
+    let mean_period = periods.iter().sum::<f64>() / periods.len().max(1) as f64;
+    let variance = periods
+        .iter()
+        .map(|p| (p - mean_period).powi(2))
+        .sum::<f64>()
+        / periods.len().max(1) as f64;
+
+    let target_rate = match mode {
+        TimingMode::FixedRate => 1.0 / interval.as_secs_f64(),
+        TimingMode::FixedDelay => 1.0 / (interval + work).as_secs_f64(),
+    };
+
+    lateness.sort();
+    let n = lateness.len();
+    Measurement {
+        mode,
+        target_rate,
+        achieved_rate,
+        ticks: n,
+        mean_lateness: lateness.iter().sum::<Duration>() / (n.max(1) as u32),
+        p99_lateness: lateness.get(n * 99 / 100).copied().unwrap_or_default(),
+        max_lateness: lateness.last().copied().unwrap_or_default(),
+        period_stddev: Duration::from_secs_f64(variance.sqrt()),
+    }
+}
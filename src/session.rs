@@ -0,0 +1,53 @@
+use std::fmt::{Display, Formatter};
+
+/// The kind of desktop session the app runs in, which decides what input access works.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Session {
+    X11,
+    Wayland,
+    /// A Linux console or SSH login without a display server.
+    Headless,
+    /// Windows or macOS.
+    Native,
+}
+
+impl Session {
+    /// Reads `XDG_SESSION_TYPE`, falling back to `WAYLAND_DISPLAY` and `DISPLAY`.
+    pub fn detect() -> Self {
+        if !cfg!(target_os = "linux") {
+            return Session::Native;
+        }
+        match std::env::var("XDG_SESSION_TYPE").as_deref() {
+            Ok("wayland") => return Session::Wayland,
+            Ok("x11") => return Session::X11,
+            _ => {}
+        }
+        if has_env("WAYLAND_DISPLAY") {
+            Session::Wayland
+        } else if has_env("DISPLAY") {
+            Session::X11
+        } else {
+            Session::Headless
+        }
+    }
+
+    /// Whether X11 clients can connect, natively or through XWayland.
+    pub fn has_x_display(&self) -> bool {
+        matches!(self, Session::X11 | Session::Wayland) && has_env("DISPLAY")
+    }
+}
+
+impl Display for Session {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Session::X11 => "X11",
+            Session::Wayland => "Wayland",
+            Session::Headless => "no display server",
+            Session::Native => std::env::consts::OS,
+        })
+    }
+}
+
+fn has_env(name: &str) -> bool {
+    std::env::var_os(name).is_some_and(|v| !v.is_empty())
+}
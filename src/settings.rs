@@ -1,15 +1,50 @@
 use std::fmt::{Display, Formatter};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
 
 use directories::ProjectDirs;
-use serde::{Deserialize, Serialize};
-use tracing::error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use tracing::{error, warn};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use crate::interval::Interval;
+use crate::keymap::{key_for_code, parse_raw_key, raw_key_name};
+use crate::validate::{Diagnostic, has_errors};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Settings {
     pub hotkey: Hotkey,
     pub action: Action,
-    pub interval_ms: u64,
+    #[serde(alias = "interval_ms")]
+    pub interval: Interval,
+    #[serde(default)]
+    pub timing: TimingMode,
+    /// Stop on its own after this many actions; unlimited when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub click_limit: Option<u64>,
+    #[serde(default)]
+    pub listener: InputListener,
+    /// Keep matched hotkeys from reaching the focused window. Linux only: the evdev
+    /// listener grabs the devices and passes everything else on through `/dev/uinput`.
+    /// Only keys are swallowed: the modifiers of a combo like Ctrl+F8 still reach the
+    /// window, as it is not known yet whether a hotkey follows when they are pressed.
+    /// Takes effect on the next start.
+    #[serde(default)]
+    pub suppress_hotkeys: bool,
+    #[serde(default)]
+    pub injector: InputInjector,
+    /// Socket of a running `ydotoold`; defaults to `$YDOTOOL_SOCKET` or `/tmp/.ydotool_socket`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ydotool_socket: Option<PathBuf>,
+    /// Named sets of action and interval to switch between with [`Command::Profile`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub profiles: Vec<Profile>,
+    /// The profile switched to last.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    /// Hotkeys besides `hotkey`, each running a [`Command`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bindings: Vec<Binding>,
 }
 
 impl Default for Settings {
@@ -17,18 +52,325 @@ impl Default for Settings {
         Self {
             hotkey: Hotkey::default_toggle(),
             action: Action::Mouse(MouseButton::Left),
-            interval_ms: 60,
+            interval: Interval::default(),
+            timing: TimingMode::default(),
+            click_limit: None,
+            listener: InputListener::default(),
+            suppress_hotkeys: false,
+            injector: InputInjector::default(),
+            ydotool_socket: None,
+            profiles: Vec::new(),
+            profile: None,
+            bindings: Vec::new(),
+        }
+    }
+}
+
+impl Settings {
+    /// The main hotkey, which toggles the clicker, followed by the bindings.
+    pub fn hotkeys(&self) -> impl Iterator<Item = (&Hotkey, &Command)> {
+        std::iter::once((&self.hotkey, &Command::Toggle))
+            .chain(self.bindings.iter().map(|b| (&b.hotkey, &b.command)))
+    }
+
+    /// Takes over the action, interval, timing and limit of the profile called `name`.
+    /// Returns `false` if there is none.
+    pub fn apply_profile(&mut self, name: &str) -> bool {
+        let Some(profile) = self.profiles.iter().find(|p| p.name == name) else {
+            return false;
+        };
+        self.action = profile.action.clone();
+        self.interval = profile.interval;
+        self.timing = profile.timing;
+        self.click_limit = profile.click_limit;
+        self.profile = Some(profile.name.clone());
+        true
+    }
+
+    /// Carries out a [`Command`] that changes the settings, and describes the outcome.
+    /// `None` for [`Command::Toggle`] and profiles that do not exist.
+    pub fn adjust(&mut self, command: &Command) -> Option<String> {
+        match command {
+            Command::Toggle => return None,
+            Command::Profile(name) => {
+                if !self.apply_profile(name) {
+                    return None;
+                }
+            }
+            Command::NextProfile => {
+                let current = self
+                    .profiles
+                    .iter()
+                    .position(|p| Some(&p.name) == self.profile.as_ref());
+                let next = current.map_or(0, |i| i + 1) % self.profiles.len().max(1);
+                let name = self.profiles.get(next)?.name.clone();
+                self.apply_profile(&name);
+            }
+            Command::MultiplyInterval(factor) => self.interval = self.interval.scaled(*factor),
+            Command::DivideInterval(factor) => self.interval = self.interval.scaled(1.0 / factor),
+            Command::StepCps(step) => self.interval = self.interval.stepped_cps(*step),
+            Command::CycleMouseButton => {
+                const CYCLE: [MouseButton; 5] = [
+                    MouseButton::Left,
+                    MouseButton::Right,
+                    MouseButton::Middle,
+                    MouseButton::Back,
+                    MouseButton::Forward,
+                ];
+                let next = match self.action {
+                    Action::Mouse(button) => CYCLE
+                        .iter()
+                        .position(|b| *b == button)
+                        .map_or(0, |i| (i + 1) % CYCLE.len()),
+                    Action::Keyboard(_) => 0,
+                };
+                self.action = Action::Mouse(CYCLE[next]);
+            }
+        }
+        Some(match command {
+            Command::Profile(_) | Command::NextProfile => {
+                format!("Profile {}", self.profile.as_deref().unwrap_or_default())
+            }
+            Command::CycleMouseButton => format!("Action {}", self.action),
+            _ => format!("Interval {}", self.interval),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Profile {
+    pub name: String,
+    pub action: Action,
+    pub interval: Interval,
+    #[serde(default)]
+    pub timing: TimingMode,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub click_limit: Option<u64>,
+}
+
+/// A hotkey and what it does.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Binding {
+    /// Written like `"Ctrl+K, 1"`, or as a table like the main hotkey to set its options.
+    #[serde(with = "hotkey_text")]
+    pub hotkey: Hotkey,
+    pub command: Command,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub enum Command {
+    /// Starts or stops clicking, like the main hotkey.
+    #[default]
+    Toggle,
+    /// Switches to the profile with this name.
+    Profile(String),
+    /// Switches to the profile after the current one, or the first.
+    NextProfile,
+    /// Makes the interval this many times longer, i.e. clicks slower.
+    MultiplyInterval(f64),
+    /// Makes the interval this many times shorter, i.e. clicks faster.
+    DivideInterval(f64),
+    /// Adds this many clicks per second; negative to slow down.
+    StepCps(f64),
+    /// Clicks the next of the left, right, middle, back and forward buttons.
+    CycleMouseButton,
+}
+
+impl Display for Command {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Command::Toggle => f.write_str("Toggle"),
+            Command::Profile(name) => write!(f, "Profile {name}"),
+            Command::NextProfile => f.write_str("Next profile"),
+            Command::MultiplyInterval(factor) => write!(f, "Interval ×{factor}"),
+            Command::DivideInterval(factor) => write!(f, "Interval ÷{factor}"),
+            Command::StepCps(step) => write!(f, "CPS {step:+}"),
+            Command::CycleMouseButton => f.write_str("Cycle mouse button"),
+        }
+    }
+}
+
+/// How the interval is measured between actions.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum TimingMode {
+    /// Actions start on a fixed grid; the time an action takes does not slow the rate down.
+    #[default]
+    FixedRate,
+    /// Each action starts one interval after the previous one finished.
+    FixedDelay,
+}
+
+/// Where global hotkeys are read from. Takes effect on the next start.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum InputListener {
+    /// evdev on Linux outside X11 (Wayland or the console), rdev everywhere else.
+    #[default]
+    Auto,
+    /// The platform's input hooks (X11 record, Windows and macOS hooks).
+    Rdev,
+    /// `/dev/input/event*` devices; Linux only, works under Wayland but needs read access.
+    Evdev,
+}
+
+/// How actions are injected. Takes effect on the next start.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum InputInjector {
+    /// The first one that works: XTest on X11, ydotoold or uinput under Wayland, enigo on
+    /// Windows and macOS.
+    #[default]
+    Auto,
+    /// XTest over a direct X11 connection (XWayland under Wayland); Linux only.
+    Xtest,
+    /// XTest on X11, SendInput on Windows, CGEvent on macOS.
+    Enigo,
+    /// A running `ydotoold`, through its socket; Linux only.
+    Ydotool,
+    /// A virtual device through `/dev/uinput`; Linux only, works under Wayland but needs write access.
+    Uinput,
+    /// Performs nothing; for dry runs.
+    None,
+}
+
+/// What pressing the hotkey does.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum HotkeyMode {
+    /// Each press switches the clicker on or off.
+    #[default]
+    Toggle,
+    /// The clicker runs only while the hotkey is held down.
+    Hold,
+}
+
+/// What the hotkey has to do to fire.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Trigger {
+    /// As soon as it goes down.
+    #[default]
+    Press,
+    /// Pressed and let go within `tap_ms`, with nothing else pressed in between.
+    Tap,
+    /// Two taps, the second starting within `double_tap_ms` of the first one ending.
+    DoubleTap,
+    /// Held for `long_press_ms`, with nothing else pressed in between.
+    LongPress,
+}
+
+impl Trigger {
+    pub const ALL: [Trigger; 4] = [
+        Trigger::Press,
+        Trigger::Tap,
+        Trigger::DoubleTap,
+        Trigger::LongPress,
+    ];
+
+    /// Taps fire once the hotkey is let go, so they cannot be held.
+    pub fn is_tap(self) -> bool {
+        matches!(self, Trigger::Tap | Trigger::DoubleTap)
+    }
+}
+
+impl Display for Trigger {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Trigger::Press => "Press",
+            Trigger::Tap => "Tap",
+            Trigger::DoubleTap => "Double-tap",
+            Trigger::LongPress => "Long press",
+        })
+    }
+}
+
+/// Timing of the [`Trigger`]s, in milliseconds.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct TriggerTiming {
+    pub tap_ms: u64,
+    pub double_tap_ms: u64,
+    pub long_press_ms: u64,
+    /// Time allowed between the steps of a sequence like `Ctrl+K, C`.
+    pub sequence_ms: u64,
+}
+
+impl Default for TriggerTiming {
+    fn default() -> Self {
+        Self {
+            tap_ms: 250,
+            double_tap_ms: 300,
+            long_press_ms: 500,
+            sequence_ms: 1000,
         }
     }
 }
 
+impl TriggerTiming {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn tap(&self) -> Duration {
+        Duration::from_millis(self.tap_ms)
+    }
+
+    pub fn double_tap(&self) -> Duration {
+        Duration::from_millis(self.double_tap_ms)
+    }
+
+    pub fn long_press(&self) -> Duration {
+        Duration::from_millis(self.long_press_ms)
+    }
+
+    pub fn sequence(&self) -> Duration {
+        Duration::from_millis(self.sequence_ms)
+    }
+}
+
+/// Which of a pair of modifier keys a hotkey wants.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Side {
+    #[default]
+    Either,
+    Left,
+    Right,
+}
+
+impl Side {
+    pub fn is_either(&self) -> bool {
+        *self == Side::Either
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Hotkey {
     pub ctrl: bool,
+    /// The left Alt key; the right one is `altgr`.
     pub alt: bool,
     pub shift: bool,
     pub meta: bool,
-    pub key: Key,
+    /// The right Alt key, AltGr on most layouts.
+    #[serde(default)]
+    pub altgr: bool,
+    #[serde(default, skip_serializing_if = "Side::is_either")]
+    pub ctrl_side: Side,
+    #[serde(default, skip_serializing_if = "Side::is_either")]
+    pub shift_side: Side,
+    #[serde(default, skip_serializing_if = "Side::is_either")]
+    pub meta_side: Side,
+    /// Also fire while modifiers the hotkey does not ask for are held.
+    #[serde(default)]
+    pub extra_modifiers: bool,
+    /// `None` for a hotkey made of modifiers alone, like tapping Right Ctrl.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<Key>,
+    #[serde(default)]
+    pub mode: HotkeyMode,
+    #[serde(default)]
+    pub trigger: Trigger,
+    #[serde(default, skip_serializing_if = "TriggerTiming::is_default")]
+    pub timing: TriggerTiming,
+    /// Further steps that have to follow within `timing.sequence_ms`, e.g. `C` after
+    /// `Ctrl+K`. Only their modifiers and key count.
+    #[serde(default, skip_serializing_if = "Vec::is_empty", with = "steps_text")]
+    pub then: Vec<Hotkey>,
 }
 
 impl Hotkey {
@@ -38,40 +380,238 @@ impl Hotkey {
             alt: false,
             shift: false,
             meta: false,
-            key: Key::F8,
+            altgr: false,
+            ctrl_side: Side::Either,
+            shift_side: Side::Either,
+            meta_side: Side::Either,
+            extra_modifiers: false,
+            key: Some(Key::F8),
+            mode: HotkeyMode::Toggle,
+            trigger: Trigger::Press,
+            timing: TriggerTiming::default(),
+            then: Vec::new(),
         }
     }
 
-    #[must_use]
-    pub fn matches_combo(&self, ctrl: bool, alt: bool, shift: bool, meta: bool, key: &Key) -> bool {
-        self.ctrl == ctrl
-            && self.alt == alt
-            && self.shift == shift
-            && self.meta == meta
-            && &self.key == key
+    pub fn has_modifiers(&self) -> bool {
+        self.ctrl || self.alt || self.shift || self.meta || self.altgr
     }
-}
 
-impl Display for Hotkey {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let mut parts: Vec<&str> = Vec::new();
+    /// Step `i` of the sequence; the hotkey itself is step 0.
+    pub fn step(&self, i: usize) -> Option<&Hotkey> {
+        match i {
+            0 => Some(self),
+            i => self.then.get(i - 1),
+        }
+    }
+
+    /// Whether the `Display` form says all there is to it.
+    fn is_plain(&self) -> bool {
+        self.mode == HotkeyMode::Toggle
+            && self.trigger == Trigger::Press
+            && !self.extra_modifiers
+            && self.timing.is_default()
+    }
+
+    /// Just the modifiers and key, without the further steps.
+    fn fmt_combo(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let sided = |side: Side, name: &str| match side {
+            Side::Either => name.to_string(),
+            Side::Left => format!("Left{name}"),
+            Side::Right => format!("Right{name}"),
+        };
+        let mut parts: Vec<String> = Vec::new();
         if self.ctrl {
-            parts.push("Ctrl");
+            parts.push(sided(self.ctrl_side, "Ctrl"));
         }
         if self.alt {
-            parts.push("Alt");
+            parts.push("Alt".to_string());
+        }
+        if self.altgr {
+            parts.push("AltGr".to_string());
         }
         if self.shift {
-            parts.push("Shift");
+            parts.push(sided(self.shift_side, "Shift"));
         }
         if self.meta {
-            parts.push("Meta");
+            parts.push(sided(self.meta_side, "Meta"));
+        }
+        match &self.key {
+            Some(Key::Char(c)) => parts.push(c.to_string()),
+            Some(key) => parts.push(key.to_str().to_string()),
+            None => {}
         }
-        parts.push(self.key.to_str());
         write!(f, "{}", parts.join("+"))
     }
 }
 
+/// Parses the `Display` form, e.g. `Ctrl+Shift+F8` or the sequence `Ctrl+K, C`. Modifier
+/// names are case-insensitive.
+impl FromStr for Hotkey {
+    type Err = ParseSettingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let steps = split_steps(s);
+        let mut hotkey = parse_combo(steps[0])?;
+        hotkey.then = steps[1..]
+            .iter()
+            .map(|step| parse_combo(step))
+            .collect::<Result<_, _>>()?;
+        Ok(hotkey)
+    }
+}
+
+/// Splits a sequence at its commas; a comma where a key belongs, as in `Ctrl+,`, is the
+/// key itself.
+fn split_steps(s: &str) -> Vec<&str> {
+    let mut steps = Vec::new();
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        let step = s[start..i].trim();
+        let wants_key = step.is_empty()
+            || step
+                .strip_suffix('+')
+                .is_some_and(|mods| !mods.is_empty() && !mods.ends_with('+'));
+        if c == ',' && !wants_key {
+            steps.push(&s[start..i]);
+            start = i + 1;
+        }
+    }
+    steps.push(&s[start..]);
+    steps
+}
+
+fn parse_combo(s: &str) -> Result<Hotkey, ParseSettingError> {
+    let err = || ParseSettingError::new("hotkey", s);
+    let combo = s.trim();
+    // The key may be `+` itself, as in `Ctrl++`.
+    let (mods, last) = match combo.strip_suffix("++") {
+        Some(mods) => (mods, "+"),
+        None if combo == "+" => ("", "+"),
+        None => combo.rsplit_once('+').unwrap_or(("", combo)),
+    };
+    let mut parts: Vec<&str> = if mods.is_empty() {
+        Vec::new()
+    } else {
+        mods.split('+').map(str::trim).collect()
+    };
+    let last = last.trim();
+    let key = if is_modifier_name(&last.to_ascii_lowercase()) {
+        parts.push(last);
+        None
+    } else {
+        Some(last.parse::<Key>().map_err(|_| err())?)
+    };
+    let mut hotkey = Hotkey {
+        ctrl: false,
+        key,
+        ..Hotkey::default_toggle()
+    };
+    for part in parts {
+        let part = part.to_ascii_lowercase();
+        let (side, name) = split_side(&part);
+        match (side, name) {
+            (side, "ctrl" | "control") => {
+                hotkey.ctrl = true;
+                hotkey.ctrl_side = side;
+            }
+            (Side::Either | Side::Left, "alt") => hotkey.alt = true,
+            (Side::Right, "alt") | (Side::Either, "altgr") => hotkey.altgr = true,
+            (side, "shift") => {
+                hotkey.shift = true;
+                hotkey.shift_side = side;
+            }
+            (side, "meta" | "super" | "cmd" | "win") => {
+                hotkey.meta = true;
+                hotkey.meta_side = side;
+            }
+            _ => return Err(err()),
+        }
+    }
+    Ok(hotkey)
+}
+
+const MODIFIER_NAMES: [&str; 8] = [
+    "ctrl", "control", "alt", "shift", "meta", "super", "cmd", "win",
+];
+
+/// Splits `rightctrl` or `lshift` into its side and modifier name.
+fn split_side(part: &str) -> (Side, &str) {
+    for (prefix, side) in [
+        ("left", Side::Left),
+        ("right", Side::Right),
+        ("l", Side::Left),
+        ("r", Side::Right),
+    ] {
+        if let Some(name) = part.strip_prefix(prefix)
+            && MODIFIER_NAMES.contains(&name)
+        {
+            return (side, name);
+        }
+    }
+    (Side::Either, part)
+}
+
+fn is_modifier_name(part: &str) -> bool {
+    part == "altgr" || MODIFIER_NAMES.contains(&split_side(part).1)
+}
+
+impl Display for Hotkey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.fmt_combo(f)?;
+        for step in &self.then {
+            f.write_str(", ")?;
+            step.fmt_combo(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes a [`Hotkey`] in its `Display` form when that loses nothing, and reads either form.
+mod hotkey_text {
+    use super::*;
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Repr {
+        Text(String),
+        Table(Hotkey),
+    }
+
+    pub fn serialize<S: Serializer>(hotkey: &Hotkey, serializer: S) -> Result<S::Ok, S::Error> {
+        if hotkey.is_plain() {
+            serializer.collect_str(hotkey)
+        } else {
+            hotkey.serialize(serializer)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Hotkey, D::Error> {
+        match Repr::deserialize(deserializer)? {
+            Repr::Text(s) => s.parse().map_err(serde::de::Error::custom),
+            Repr::Table(hotkey) => Ok(hotkey),
+        }
+    }
+}
+
+/// Writes the steps of a sequence in their `Display` form, e.g. `then = ["C"]`.
+mod steps_text {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(steps: &[Hotkey], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(steps.iter().map(ToString::to_string))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Hotkey>, D::Error> {
+        Vec::<String>::deserialize(deserializer)?
+            .iter()
+            .map(|s| parse_combo(s).map_err(serde::de::Error::custom))
+            .collect()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum Key {
     F6,
@@ -85,9 +625,36 @@ pub enum Key {
     Enter,
     Escape,
     Char(char),
+    /// A mouse button; only usable as a hotkey.
+    Mouse(MouseButton),
+    /// Any other key, by its Linux key code (`KEY_*` in `linux/input-event-codes.h`).
+    /// `name` is only for display.
+    Raw {
+        code: u16,
+        name: String,
+    },
 }
 
 impl Key {
+    /// The key with Linux key code `code`, named as [`raw_key_name`] does.
+    pub fn raw(code: u16) -> Key {
+        Key::Raw {
+            code,
+            name: raw_key_name(code),
+        }
+    }
+
+    /// Letters compare case-insensitively and raw keys by code, as their named variant
+    /// if they have one; everything else is returned unchanged.
+    #[must_use]
+    pub fn normalized(&self) -> Key {
+        match self {
+            Key::Char(c) => Key::Char(c.to_ascii_uppercase()),
+            Key::Raw { code, .. } => key_for_code(*code),
+            k => k.clone(),
+        }
+    }
+
     pub fn to_str(&self) -> &str {
         match self {
             Key::F6 => "F6",
@@ -139,15 +706,109 @@ impl Key {
                 '9' => "9",
                 _ => "?",
             },
+            Key::Mouse(button) => match button {
+                MouseButton::Left => "MouseLeft",
+                MouseButton::Right => "MouseRight",
+                MouseButton::Middle => "MouseMiddle",
+                MouseButton::ScrollUp => "MouseScrollUp",
+                MouseButton::ScrollDown => "MouseScrollDown",
+                MouseButton::ScrollLeft => "MouseScrollLeft",
+                MouseButton::ScrollRight => "MouseScrollRight",
+                MouseButton::Back => "MouseBack",
+                MouseButton::Forward => "MouseForward",
+            },
+            Key::Raw { name, .. } => name,
         }
     }
 }
 
+impl FromStr for Key {
+    type Err = ParseSettingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            return Ok(Key::Char(c.to_ascii_uppercase()));
+        }
+        let lower = s.to_ascii_lowercase();
+        if let Some(name) = lower.strip_prefix("mouse") {
+            return MouseButton::ALL
+                .into_iter()
+                .find(|b| b.to_str().eq_ignore_ascii_case(name))
+                .map(Key::Mouse)
+                .ok_or_else(|| ParseSettingError::new("key", s));
+        }
+        Ok(match lower.as_str() {
+            "f6" => Key::F6,
+            "f7" => Key::F7,
+            "f8" => Key::F8,
+            "f9" => Key::F9,
+            "f10" => Key::F10,
+            "f11" => Key::F11,
+            "f12" => Key::F12,
+            "space" => Key::Space,
+            "enter" | "return" => Key::Enter,
+            "escape" | "esc" => Key::Escape,
+            _ => return parse_raw_key(s).ok_or_else(|| ParseSettingError::new("key", s)),
+        })
+    }
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 pub enum MouseButton {
     Left,
     Right,
     Middle,
+    ScrollUp,
+    ScrollDown,
+    ScrollLeft,
+    ScrollRight,
+    /// The thumb button that usually means "back".
+    Back,
+    Forward,
+}
+
+impl MouseButton {
+    pub const ALL: [MouseButton; 9] = [
+        MouseButton::Left,
+        MouseButton::Right,
+        MouseButton::Middle,
+        MouseButton::ScrollUp,
+        MouseButton::ScrollDown,
+        MouseButton::ScrollLeft,
+        MouseButton::ScrollRight,
+        MouseButton::Back,
+        MouseButton::Forward,
+    ];
+
+    pub fn to_str(self) -> &'static str {
+        match self {
+            MouseButton::Left => "Left",
+            MouseButton::Right => "Right",
+            MouseButton::Middle => "Middle",
+            MouseButton::ScrollUp => "ScrollUp",
+            MouseButton::ScrollDown => "ScrollDown",
+            MouseButton::ScrollLeft => "ScrollLeft",
+            MouseButton::ScrollRight => "ScrollRight",
+            MouseButton::Back => "Back",
+            MouseButton::Forward => "Forward",
+        }
+    }
+
+    /// The X11 core button number, 1 to 9.
+    pub fn x11_button(self) -> u8 {
+        match self {
+            MouseButton::Left => 1,
+            MouseButton::Middle => 2,
+            MouseButton::Right => 3,
+            MouseButton::ScrollUp => 4,
+            MouseButton::ScrollDown => 5,
+            MouseButton::ScrollLeft => 6,
+            MouseButton::ScrollRight => 7,
+            MouseButton::Back => 8,
+            MouseButton::Forward => 9,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -156,26 +817,228 @@ pub enum Action {
     Keyboard(Key),
 }
 
-pub fn config_file_path() -> PathBuf {
-    let dirs = ProjectDirs::from("dev", "nbdy", "autoclicker")
-        .expect("Failed to resolve project directories");
-    let cfg_dir = dirs.config_dir();
-    std::fs::create_dir_all(cfg_dir).ok();
-    cfg_dir.join("settings.toml")
+/// Short form used on the command line: `mouse:left`, `mouse:scrolldown`, `key:F8`, `key:A`.
+impl Display for Action {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Action::Mouse(b) => write!(f, "mouse:{}", b.to_str().to_ascii_lowercase()),
+            Action::Keyboard(k) => write!(f, "key:{}", k.to_str()),
+        }
+    }
+}
+
+impl FromStr for Action {
+    type Err = ParseSettingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseSettingError::new("action", s);
+        let (kind, value) = s.split_once(':').ok_or_else(err)?;
+        match kind.trim().to_ascii_lowercase().as_str() {
+            "mouse" => MouseButton::ALL
+                .into_iter()
+                .find(|b| b.to_str().eq_ignore_ascii_case(value.trim()))
+                .map(Action::Mouse)
+                .ok_or_else(err),
+            "key" => value
+                .trim()
+                .parse()
+                .map(Action::Keyboard)
+                .map_err(|_| err()),
+            _ => Err(err()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseSettingError {
+    what: &'static str,
+    input: String,
+}
+
+impl ParseSettingError {
+    fn new(what: &'static str, input: &str) -> Self {
+        Self {
+            what,
+            input: input.to_string(),
+        }
+    }
+}
+
+impl Display for ParseSettingError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid {} {:?}", self.what, self.input)
+    }
+}
+
+impl std::error::Error for ParseSettingError {}
+
+pub const CONFIG_ENV_VAR: &str = "AUTOCLICKER_CONFIG";
+const CONFIG_FILE_NAME: &str = "settings.toml";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigSource {
+    /// `--config <path>` on the command line.
+    Flag,
+    /// The `AUTOCLICKER_CONFIG` environment variable.
+    Env,
+    /// A `settings.toml` next to the executable.
+    Portable,
+    /// The platform config directory.
+    ProjectDirs,
 }
 
-pub fn load_settings(path: &PathBuf) -> Settings {
-    match std::fs::read_to_string(path) {
-        Ok(s) => toml::from_str::<Settings>(&s).unwrap_or_else(|e| {
-            error!("Failed to parse settings TOML: {}", e);
-            Settings::default()
-        }),
-        Err(_) => Settings::default(),
+impl Display for ConfigSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ConfigSource::Flag => "--config",
+            ConfigSource::Env => CONFIG_ENV_VAR,
+            ConfigSource::Portable => "portable",
+            ConfigSource::ProjectDirs => "user config dir",
+        })
     }
 }
 
-pub fn save_settings(path: &PathBuf, settings: &Settings) -> Result<(), String> {
-    toml::to_string_pretty(settings)
-        .map_err(|e| e.to_string())
-        .and_then(|s| std::fs::write(path, s).map_err(|e| e.to_string()))
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigLocation {
+    pub path: PathBuf,
+    pub source: ConfigSource,
+}
+
+/// Picks the settings file in order of precedence: explicit flag, environment variable,
+/// portable file next to the executable, then the platform config directory.
+///
+/// Returns `None` if no location can be determined; callers should then keep settings in memory.
+pub fn resolve_config_location(flag: Option<PathBuf>) -> Option<ConfigLocation> {
+    let location = ConfigCandidates::from_environment(flag).resolve();
+    match &location {
+        Some(ConfigLocation {
+            path,
+            source: ConfigSource::ProjectDirs,
+        }) => {
+            if let Some(cfg_dir) = path.parent()
+                && let Err(e) = std::fs::create_dir_all(cfg_dir)
+            {
+                warn!("Failed to create {}: {}", cfg_dir.display(), e);
+            }
+        }
+        Some(_) => {}
+        None => warn!("Failed to resolve project directories"),
+    }
+    location
+}
+
+/// Where a settings file could come from, one field per [`ConfigSource`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConfigCandidates {
+    pub flag: Option<PathBuf>,
+    pub env: Option<PathBuf>,
+    /// `settings.toml` next to the executable; only used if it exists.
+    pub portable: Option<PathBuf>,
+    /// The platform config directory.
+    pub config_dir: Option<PathBuf>,
+}
+
+impl ConfigCandidates {
+    /// The candidates of this process: `flag`, [`CONFIG_ENV_VAR`], the executable's
+    /// directory and the platform config directory.
+    pub fn from_environment(flag: Option<PathBuf>) -> Self {
+        Self {
+            flag,
+            env: std::env::var_os(CONFIG_ENV_VAR)
+                .filter(|v| !v.is_empty())
+                .map(PathBuf::from),
+            portable: portable_config_path(),
+            config_dir: ProjectDirs::from("dev", "nbdy", "autoclicker")
+                .map(|dirs| dirs.config_dir().to_path_buf()),
+        }
+    }
+
+    /// The first candidate in order of precedence.
+    pub fn resolve(self) -> Option<ConfigLocation> {
+        let location = |path, source| Some(ConfigLocation { path, source });
+        if let Some(path) = self.flag {
+            return location(path, ConfigSource::Flag);
+        }
+        if let Some(path) = self.env {
+            return location(path, ConfigSource::Env);
+        }
+        if let Some(path) = self.portable.filter(|p| p.is_file()) {
+            return location(path, ConfigSource::Portable);
+        }
+        location(
+            self.config_dir?.join(CONFIG_FILE_NAME),
+            ConfigSource::ProjectDirs,
+        )
+    }
+}
+
+fn portable_config_path() -> Option<PathBuf> {
+    let exe = std::env::current_exe().ok()?;
+    Some(exe.parent()?.join(CONFIG_FILE_NAME))
+}
+
+#[derive(Debug)]
+pub enum LoadError {
+    Io(std::io::Error),
+    Parse(toml::de::Error),
+    Invalid(Vec<Diagnostic>),
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadError::Io(e) => write!(f, "failed to read settings: {e}"),
+            LoadError::Parse(e) => write!(f, "failed to parse settings TOML: {e}"),
+            LoadError::Invalid(diags) => {
+                write!(f, "invalid settings")?;
+                for d in diags.iter().filter(|d| d.is_error()) {
+                    write!(f, "; {}: {}", d.path, d.message)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LoadError::Io(e) => Some(e),
+            LoadError::Parse(e) => Some(e),
+            LoadError::Invalid(_) => None,
+        }
+    }
+}
+
+pub fn read_settings(path: &Path) -> Result<Settings, LoadError> {
+    let s = std::fs::read_to_string(path).map_err(LoadError::Io)?;
+    parse_settings(&s)
+}
+
+/// Parses and validates settings. Warnings are logged; any error rejects the whole file.
+pub fn parse_settings(s: &str) -> Result<Settings, LoadError> {
+    let settings = toml::from_str::<Settings>(s).map_err(LoadError::Parse)?;
+    let diagnostics = settings.validate();
+    if has_errors(&diagnostics) {
+        return Err(LoadError::Invalid(diagnostics));
+    }
+    for d in &diagnostics {
+        warn!("{}", d);
+    }
+    Ok(settings)
+}
+
+/// Loads settings for startup, falling back to defaults when the file is missing or unusable.
+/// A missing file is not an error; anything else is returned alongside the defaults.
+pub fn load_settings(path: &Path) -> (Settings, Option<LoadError>) {
+    match read_settings(path) {
+        Ok(s) => (s, None),
+        Err(LoadError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+            (Settings::default(), None)
+        }
+        Err(e) => {
+            error!("{}; using defaults", e);
+            (Settings::default(), Some(e))
+        }
+    }
 }
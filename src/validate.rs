@@ -0,0 +1,601 @@
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+
+use std::time::Duration;
+
+use crate::interval::Interval;
+use crate::keymap::{MAX_RAW_KEY, is_modifier_code};
+use crate::settings::{
+    Action, Binding, Command, Hotkey, HotkeyMode, InputInjector, InputListener, Key, MouseButton,
+    Profile, Settings, Side, Trigger,
+};
+
+/// Shorter periods than this outrun what input backends can inject.
+const MIN_PRACTICAL_INTERVAL: Duration = Duration::from_micros(100);
+/// Longer periods are almost certainly a unit mix-up.
+const MAX_PRACTICAL_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Shortcuts desktops commonly keep for themselves, and what they do there.
+const DESKTOP_SHORTCUTS: &[(&str, &str)] = &[
+    ("Ctrl+Alt+T", "opens a terminal on GNOME and Ubuntu"),
+    ("Ctrl+Alt+L", "locks the screen on KDE, Cinnamon and Xfce"),
+    ("Ctrl+Alt+Delete", "logs out or opens the security screen"),
+    ("Ctrl+Alt+Backspace", "may kill the X server"),
+    (
+        "Ctrl+Shift+Escape",
+        "opens the task manager on Windows and KDE",
+    ),
+    ("Alt+Tab", "switches windows"),
+    ("Alt+F2", "opens the run dialog on GNOME and KDE"),
+    ("Alt+F4", "closes the focused window"),
+    ("Meta+L", "locks the screen"),
+    ("Meta+D", "shows the desktop"),
+    ("Meta+E", "opens the file manager on Windows and KDE"),
+    ("Meta+R", "opens the run dialog on Windows"),
+    ("Meta+Tab", "switches windows"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Dotted path of the offending field, e.g. `hotkey.key`.
+    pub path: String,
+    pub message: String,
+}
+
+impl Diagnostic {
+    fn error(path: &str, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            path: path.to_string(),
+            message: message.into(),
+        }
+    }
+
+    fn warning(path: &str, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            path: path.to_string(),
+            message: message.into(),
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
+    /// Whether it is about a hotkey or binding, conflicts between them included.
+    pub fn is_about_hotkeys(&self) -> bool {
+        ["hotkey", "bindings", "suppress_hotkeys"]
+            .iter()
+            .any(|prefix| self.path.starts_with(prefix))
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}: {}", self.severity, self.path, self.message)
+    }
+}
+
+pub fn has_errors(diagnostics: &[Diagnostic]) -> bool {
+    diagnostics.iter().any(Diagnostic::is_error)
+}
+
+impl Settings {
+    /// Checks the settings for values that are unusable or likely mistakes.
+    ///
+    /// Errors describe settings the clicker cannot honour; warnings describe settings
+    /// that work but probably don't do what was intended.
+    #[must_use]
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let mut out = Vec::new();
+        validate_hotkey("hotkey", &self.hotkey, &mut out);
+        validate_action("action", &self.action, &mut out);
+        validate_interval("interval", &self.interval, &mut out);
+        if self.click_limit == Some(0) {
+            out.push(Diagnostic::error(
+                "click_limit",
+                "a limit of 0 would never click; remove it for unlimited clicking",
+            ));
+        }
+        validate_profiles(self, &mut out);
+        for (i, binding) in self.bindings.iter().enumerate() {
+            validate_binding(&format!("bindings[{i}]"), binding, self, &mut out);
+        }
+        validate_conflicts(self, &mut out);
+        if self.listener == InputListener::Evdev && !cfg!(target_os = "linux") {
+            out.push(Diagnostic::warning(
+                "listener",
+                "evdev is only available on Linux; rdev will be used instead",
+            ));
+        }
+        if self.suppress_hotkeys
+            && (self.listener == InputListener::Rdev || !cfg!(target_os = "linux"))
+        {
+            out.push(Diagnostic::warning(
+                "suppress_hotkeys",
+                "only the evdev listener on Linux can suppress hotkeys; they will reach the focused window too",
+            ));
+        }
+        if matches!(
+            self.injector,
+            InputInjector::Uinput | InputInjector::Xtest | InputInjector::Ydotool
+        ) && !cfg!(target_os = "linux")
+        {
+            out.push(Diagnostic::warning(
+                "injector",
+                "uinput, xtest and ydotool are only available on Linux; the automatic choice will be used instead",
+            ));
+        }
+        if self.injector == InputInjector::Enigo && !cfg!(feature = "clicking_enigo") {
+            out.push(Diagnostic::warning(
+                "injector",
+                "this build has no enigo support; the automatic choice will be used instead",
+            ));
+        }
+        out
+    }
+}
+
+fn validate_hotkey(path: &str, hotkey: &Hotkey, out: &mut Vec<Diagnostic>) {
+    let at = |field: &str| format!("{path}.{field}");
+    validate_trigger(path, hotkey, out);
+    validate_sequence(path, hotkey, out);
+    let Some(key) = &hotkey.key else {
+        if !hotkey.has_modifiers() {
+            out.push(Diagnostic::error(
+                path,
+                "a hotkey needs a key or at least one modifier",
+            ));
+        }
+        return;
+    };
+    validate_raw_key(&at("key"), key, out);
+    if let Key::Raw { code, name } = key
+        && is_modifier_code(*code)
+    {
+        out.push(Diagnostic::error(
+            &at("key"),
+            format!("{name} is a modifier, not a key; set it like hotkey=rightctrl instead"),
+        ));
+    }
+    for (field, wanted, side) in [
+        ("ctrl_side", hotkey.ctrl, hotkey.ctrl_side),
+        ("shift_side", hotkey.shift, hotkey.shift_side),
+        ("meta_side", hotkey.meta, hotkey.meta_side),
+    ] {
+        if !wanted && !side.is_either() {
+            out.push(Diagnostic::warning(
+                &at(field),
+                format!("{side:?} has no effect without the modifier itself"),
+            ));
+        }
+    }
+    if let Key::Char(c) = *key {
+        if !c.is_ascii_alphanumeric() {
+            out.push(Diagnostic::error(
+                &at("key"),
+                format!("{c:?} cannot be detected as a hotkey; use A-Z, 0-9 or a named key"),
+            ));
+        } else if c.is_ascii_lowercase() {
+            out.push(Diagnostic::warning(
+                &at("key"),
+                format!(
+                    "{c:?} is matched as {:?}; letters are not case sensitive",
+                    c.to_ascii_uppercase()
+                ),
+            ));
+        }
+    }
+
+    match key {
+        Key::Mouse(
+            MouseButton::ScrollUp
+            | MouseButton::ScrollDown
+            | MouseButton::ScrollLeft
+            | MouseButton::ScrollRight,
+        ) => out.push(Diagnostic::error(
+            &at("key"),
+            "scrolling has no press and release, so it cannot be a hotkey",
+        )),
+        Key::Mouse(MouseButton::Left) if !hotkey.has_modifiers() => out.push(Diagnostic::error(
+            path,
+            "the left mouse button needs modifiers, or every click would toggle the clicker",
+        )),
+        _ => {}
+    }
+
+    if !hotkey.has_modifiers()
+        && hotkey.trigger == Trigger::Press
+        && hotkey.then.is_empty()
+        && matches!(key, Key::Char(_) | Key::Space | Key::Enter)
+    {
+        out.push(Diagnostic::warning(
+            path,
+            format!("{hotkey} has no modifiers and will fire while typing"),
+        ));
+    }
+}
+
+fn validate_trigger(path: &str, hotkey: &Hotkey, out: &mut Vec<Diagnostic>) {
+    let timing = &hotkey.timing;
+    for (field, ms) in [
+        ("tap_ms", timing.tap_ms),
+        ("double_tap_ms", timing.double_tap_ms),
+        ("long_press_ms", timing.long_press_ms),
+        ("sequence_ms", timing.sequence_ms),
+    ] {
+        if ms == 0 {
+            out.push(Diagnostic::error(
+                &format!("{path}.timing.{field}"),
+                "must be at least 1 ms",
+            ));
+        }
+    }
+    let trigger_path = format!("{path}.trigger");
+    if hotkey.trigger.is_tap() && hotkey.mode == HotkeyMode::Hold {
+        out.push(Diagnostic::error(
+            &trigger_path,
+            format!(
+                "{} fires when the hotkey is let go, so it cannot be held; use Toggle mode",
+                hotkey.trigger
+            ),
+        ));
+    }
+    if hotkey.key.is_none() && hotkey.trigger == Trigger::Press && hotkey.mode == HotkeyMode::Toggle
+    {
+        out.push(Diagnostic::warning(
+            &trigger_path,
+            format!("{hotkey} alone fires with every shortcut that uses it; consider Tap"),
+        ));
+    }
+}
+
+fn validate_sequence(path: &str, hotkey: &Hotkey, out: &mut Vec<Diagnostic>) {
+    if hotkey.then.is_empty() {
+        return;
+    }
+    let then_path = format!("{path}.then");
+    if hotkey.trigger != Trigger::Press {
+        out.push(Diagnostic::error(
+            &format!("{path}.trigger"),
+            format!(
+                "a sequence fires on the press of its last key, not on a {}",
+                hotkey.trigger
+            ),
+        ));
+    }
+    if hotkey.mode == HotkeyMode::Hold {
+        out.push(Diagnostic::error(
+            &format!("{path}.mode"),
+            "a sequence cannot be held; use Toggle mode",
+        ));
+    }
+    if hotkey.key.is_none() || hotkey.then.iter().any(|step| step.key.is_none()) {
+        out.push(Diagnostic::error(
+            &then_path,
+            "every step of a sequence needs a key",
+        ));
+    }
+    for key in hotkey.then.iter().filter_map(|step| step.key.as_ref()) {
+        validate_raw_key(&then_path, key, out);
+    }
+}
+
+fn validate_binding(path: &str, binding: &Binding, settings: &Settings, out: &mut Vec<Diagnostic>) {
+    validate_hotkey(&format!("{path}.hotkey"), &binding.hotkey, out);
+    match &binding.command {
+        Command::Toggle => {}
+        Command::Profile(name) => {
+            if !settings.profiles.iter().any(|p| p.name == *name) {
+                out.push(Diagnostic::error(
+                    &format!("{path}.command"),
+                    format!("there is no profile named {name:?}"),
+                ));
+            }
+        }
+        Command::NextProfile => {
+            if settings.profiles.is_empty() {
+                out.push(Diagnostic::warning(
+                    &format!("{path}.command"),
+                    "there are no profiles to cycle through",
+                ));
+            }
+        }
+        Command::MultiplyInterval(factor) | Command::DivideInterval(factor) => {
+            if !factor.is_finite() || *factor <= 0.0 {
+                out.push(Diagnostic::error(
+                    &format!("{path}.command"),
+                    "the factor must be a positive number",
+                ));
+            } else if *factor == 1.0 {
+                out.push(Diagnostic::warning(
+                    &format!("{path}.command"),
+                    "a factor of 1 leaves the interval as it is",
+                ));
+            }
+        }
+        Command::StepCps(step) => {
+            if !step.is_finite() || *step == 0.0 {
+                out.push(Diagnostic::error(
+                    &format!("{path}.command"),
+                    "the step must be a non-zero number",
+                ));
+            }
+        }
+        Command::CycleMouseButton => {}
+    }
+    if binding.command != Command::Toggle && binding.hotkey.mode == HotkeyMode::Hold {
+        out.push(Diagnostic::error(
+            &format!("{path}.hotkey.mode"),
+            "only Toggle can be held",
+        ));
+    }
+}
+
+fn validate_profiles(settings: &Settings, out: &mut Vec<Diagnostic>) {
+    for (i, profile) in settings.profiles.iter().enumerate() {
+        let path = format!("profiles[{i}]");
+        validate_profile(&path, profile, out);
+        if settings.profiles[..i]
+            .iter()
+            .any(|p| p.name == profile.name)
+        {
+            out.push(Diagnostic::error(
+                &format!("{path}.name"),
+                format!("there is another profile named {:?}", profile.name),
+            ));
+        }
+    }
+    if let Some(name) = &settings.profile
+        && !settings.profiles.iter().any(|p| p.name == *name)
+    {
+        out.push(Diagnostic::warning(
+            "profile",
+            format!("there is no profile named {name:?}"),
+        ));
+    }
+}
+
+fn validate_profile(path: &str, profile: &Profile, out: &mut Vec<Diagnostic>) {
+    if profile.name.trim().is_empty() {
+        out.push(Diagnostic::error(
+            &format!("{path}.name"),
+            "a profile needs a name",
+        ));
+    }
+    validate_action(&format!("{path}.action"), &profile.action, out);
+    validate_interval(&format!("{path}.interval"), &profile.interval, out);
+    if profile.click_limit == Some(0) {
+        out.push(Diagnostic::error(
+            &format!("{path}.click_limit"),
+            "a limit of 0 would never click; remove it for unlimited clicking",
+        ));
+    }
+}
+
+fn validate_action(path: &str, action: &Action, out: &mut Vec<Diagnostic>) {
+    if let Action::Keyboard(key) = action {
+        validate_raw_key(path, key, out);
+    }
+    if let Action::Keyboard(Key::Mouse(_)) = action {
+        out.push(Diagnostic::error(
+            path,
+            "mouse buttons are not keys; use a mouse action instead",
+        ));
+    }
+    if let Action::Keyboard(Key::Char(c)) = action {
+        if c.is_control() || c.is_whitespace() {
+            out.push(Diagnostic::error(
+                path,
+                format!("{c:?} is not a typeable key; use Space or Enter for whitespace"),
+            ));
+        } else if !c.is_ascii_alphanumeric() {
+            out.push(Diagnostic::warning(
+                path,
+                format!("{c:?} will be typed, but cannot be shown or recorded in the GUI"),
+            ));
+        }
+    }
+}
+
+fn validate_raw_key(field: &str, key: &Key, out: &mut Vec<Diagnostic>) {
+    if let Key::Raw { code, name } = key
+        && (*code == 0 || *code > MAX_RAW_KEY)
+    {
+        out.push(Diagnostic::error(
+            field,
+            format!("{name} has key code {code}, but Linux key codes go from 1 to {MAX_RAW_KEY}"),
+        ));
+    }
+}
+
+fn validate_interval(path: &str, interval: &Interval, out: &mut Vec<Diagnostic>) {
+    let period = interval.as_duration();
+    if !interval.value.is_finite() || period.is_zero() {
+        out.push(Diagnostic::error(
+            path,
+            format!("{interval} is not a usable interval; it must be at least 1 µs"),
+        ));
+    } else if period < MIN_PRACTICAL_INTERVAL {
+        out.push(Diagnostic::warning(
+            path,
+            format!("{interval} is faster than most systems can inject; expect dropped clicks"),
+        ));
+    } else if period > MAX_PRACTICAL_INTERVAL {
+        out.push(Diagnostic::warning(
+            path,
+            format!("{interval} is longer than a day"),
+        ));
+    }
+}
+
+/// Each hotkey with the path of its settings.
+fn hotkey_paths(settings: &Settings) -> Vec<(String, &Hotkey, &Command)> {
+    settings
+        .hotkeys()
+        .enumerate()
+        .map(|(i, (hotkey, command))| {
+            let path = match i {
+                0 => "hotkey".to_string(),
+                i => format!("bindings[{}].hotkey", i - 1),
+            };
+            (path, hotkey, command)
+        })
+        .collect()
+}
+
+/// Hotkeys that clash with each other, the action or desktop shortcuts.
+fn validate_conflicts(settings: &Settings, out: &mut Vec<Diagnostic>) {
+    let hotkeys = hotkey_paths(settings);
+    for (i, (path, hotkey, _)) in hotkeys.iter().enumerate() {
+        for (other_path, other, command) in &hotkeys[..i] {
+            if let Some(message) =
+                sequence_clash(hotkey, other, &format!("{other_path} ({command})"))
+            {
+                out.push(Diagnostic::error(path, message));
+            }
+        }
+    }
+
+    let actions = std::iter::once(("action".to_string(), &settings.action)).chain(
+        settings
+            .profiles
+            .iter()
+            .enumerate()
+            .map(|(i, p)| (format!("profiles[{i}].action"), &p.action)),
+    );
+    for (action_path, action) in actions {
+        for (_, hotkey, command) in &hotkeys {
+            validate_hotkey_vs_action(&action_path, action, hotkey, command, out);
+        }
+    }
+
+    let desktop: Vec<(Hotkey, &str)> = DESKTOP_SHORTCUTS
+        .iter()
+        .map(|(combo, what)| (combo.to_string(), *what))
+        .chain((1..=12).map(|n| (format!("Ctrl+Alt+F{n}"), "switches to a text console")))
+        .filter_map(|(combo, what)| Some((combo.parse().ok()?, what)))
+        .collect();
+    for (path, hotkey, _) in &hotkeys {
+        for step in std::iter::once(*hotkey).chain(&hotkey.then) {
+            if let Some((shortcut, what)) = desktop.iter().find(|(s, _)| combos_overlap(step, s)) {
+                out.push(Diagnostic::warning(
+                    path,
+                    format!("{shortcut} {what}, so the desktop may get it first"),
+                ));
+            }
+        }
+    }
+}
+
+/// How `a` and `b`, bound at `b_at`, get in each other's way, if they do.
+fn sequence_clash(a: &Hotkey, b: &Hotkey, b_at: &str) -> Option<String> {
+    let steps = |h: &Hotkey| h.then.len() + 1;
+    let shared = steps(a).min(steps(b));
+    let overlap = (0..shared).all(|i| match (a.step(i), b.step(i)) {
+        (Some(x), Some(y)) => combos_overlap(x, y),
+        _ => false,
+    });
+    if !overlap {
+        return None;
+    }
+    // Only the step that fires has a trigger.
+    let last = |h: &Hotkey, i: usize| {
+        if i + 1 == steps(h) {
+            h.trigger
+        } else {
+            Trigger::Press
+        }
+    };
+    if !triggers_clash(last(a, shared - 1), last(b, shared - 1)) {
+        return None;
+    }
+    Some(match steps(a).cmp(&steps(b)) {
+        Ordering::Equal => format!("{a} is also bound at {b_at}"),
+        Ordering::Less => format!("{a} also fires on the way to {b} at {b_at}"),
+        Ordering::Greater => format!("{b} at {b_at} also fires on the way to {a}"),
+    })
+}
+
+/// Whether some keys held at once match both `a` and `b`, ignoring further steps.
+fn combos_overlap(a: &Hotkey, b: &Hotkey) -> bool {
+    if a.key.as_ref().map(Key::normalized) != b.key.as_ref().map(Key::normalized) {
+        return false;
+    }
+    let flag = |x: bool, y: bool| x == y || (x && b.extra_modifiers) || (y && a.extra_modifiers);
+    let side = |both: bool, x: Side, y: Side| !both || x.is_either() || y.is_either() || x == y;
+    flag(a.ctrl, b.ctrl)
+        && flag(a.alt, b.alt)
+        && flag(a.altgr, b.altgr)
+        && flag(a.shift, b.shift)
+        && flag(a.meta, b.meta)
+        && side(a.ctrl && b.ctrl, a.ctrl_side, b.ctrl_side)
+        && side(a.shift && b.shift, a.shift_side, b.shift_side)
+        && side(a.meta && b.meta, a.meta_side, b.meta_side)
+}
+
+/// A long press can share its key with a tap; everything else fires on the same keys.
+fn triggers_clash(a: Trigger, b: Trigger) -> bool {
+    let long_vs_tap = |x: Trigger, y: Trigger| x == Trigger::LongPress && y.is_tap();
+    !(long_vs_tap(a, b) || long_vs_tap(b, a))
+}
+
+fn validate_hotkey_vs_action(
+    path: &str,
+    action: &Action,
+    hotkey: &Hotkey,
+    command: &Command,
+    out: &mut Vec<Diagnostic>,
+) {
+    let key = match action {
+        Action::Keyboard(key) => key.clone(),
+        Action::Mouse(button) => Key::Mouse(*button),
+    };
+    if !hotkey.then.is_empty() || hotkey.key.as_ref().map(Key::normalized) != Some(key.normalized())
+    {
+        return;
+    }
+    let effect = match command {
+        Command::Toggle => "toggle the clicker".to_string(),
+        Command::Profile(name) => format!("switch to profile {name}"),
+        Command::NextProfile => "switch to the next profile".to_string(),
+        Command::MultiplyInterval(_) | Command::DivideInterval(_) | Command::StepCps(_) => {
+            "change the interval".to_string()
+        }
+        Command::CycleMouseButton => "change the mouse button".to_string(),
+    };
+    if hotkey.has_modifiers() {
+        out.push(Diagnostic::warning(
+            path,
+            format!(
+                "action key {} is also the key of {hotkey}; holding its modifiers while it clicks will {effect}",
+                key.to_str()
+            ),
+        ));
+    } else {
+        out.push(Diagnostic::error(
+            path,
+            format!(
+                "action key {} is the hotkey {hotkey}; each click would {effect}",
+                key.to_str()
+            ),
+        ));
+    }
+}
@@ -0,0 +1,136 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock, mpsc};
+use std::thread;
+use std::time::Duration;
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use tracing::{info, warn};
+
+use crate::settings::{LoadError, Settings, parse_settings};
+
+/// Editors tend to emit several events per save (truncate, write, rename); wait for them to settle.
+const SETTLE: Duration = Duration::from_millis(150);
+
+/// Keeps the file watcher alive and exposes the outcome of the last reload attempt.
+#[derive(Debug)]
+pub struct ConfigWatcher {
+    _watcher: RecommendedWatcher,
+    last_error: Arc<RwLock<Option<Arc<LoadError>>>>,
+}
+
+impl ConfigWatcher {
+    pub fn last_error(&self) -> Option<Arc<LoadError>> {
+        self.last_error.read().unwrap().clone()
+    }
+}
+
+/// Applies valid edits of `path` to `settings`. Watches directories, not the file, so
+/// rename-over saves are seen.
+pub fn start_config_watcher(
+    path: PathBuf,
+    settings: Arc<RwLock<Settings>>,
+    is_own_write: impl Fn(&str) -> bool + Send + 'static,
+    on_reload: impl Fn(Result<(), &LoadError>) + Send + 'static,
+) -> notify::Result<ConfigWatcher> {
+    let mut watched = vec![path.clone()];
+    if std::fs::symlink_metadata(&path).is_ok_and(|m| m.file_type().is_symlink())
+        && let Ok(target) = std::fs::canonicalize(&path)
+    {
+        watched.push(target);
+    }
+    let file_names: Vec<_> = watched
+        .iter()
+        .filter_map(|p| p.file_name().map(|n| n.to_os_string()))
+        .collect();
+
+    let (tx, rx) = mpsc::channel::<()>();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<Event>| match res {
+        Ok(event) => {
+            let relevant = matches!(
+                event.kind,
+                EventKind::Create(_) | EventKind::Modify(_) | EventKind::Any
+            ) && event.paths.iter().any(|p| {
+                p.file_name()
+                    .is_some_and(|n| file_names.iter().any(|f| f == n))
+            });
+            if relevant {
+                let _ = tx.send(());
+            }
+        }
+        Err(e) => warn!("Config watcher error: {}", e),
+    })?;
+    let mut dirs: Vec<_> = watched
+        .iter()
+        .map(|p| {
+            p.parent()
+                .filter(|d| !d.as_os_str().is_empty())
+                .unwrap_or(Path::new("."))
+        })
+        .collect();
+    dirs.dedup();
+    for dir in dirs {
+        watcher.watch(dir, RecursiveMode::NonRecursive)?;
+    }
+
+    let last_error = Arc::new(RwLock::new(None));
+    let thread_error = Arc::clone(&last_error);
+    thread::spawn(move || {
+        while rx.recv().is_ok() {
+            while rx.recv_timeout(SETTLE).is_ok() {}
+            reload(&path, &settings, &is_own_write, &on_reload, &thread_error);
+        }
+    });
+
+    Ok(ConfigWatcher {
+        _watcher: watcher,
+        last_error,
+    })
+}
+
+fn reload(
+    path: &Path,
+    settings: &RwLock<Settings>,
+    is_own_write: &impl Fn(&str) -> bool,
+    on_reload: &impl Fn(Result<(), &LoadError>),
+    last_error: &RwLock<Option<Arc<LoadError>>>,
+) {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(c) => c,
+        // The file may be briefly missing in the middle of a rename; the next event will catch up.
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return,
+        Err(e) => {
+            warn!("Failed to read changed settings: {}", e);
+            let e = Arc::new(LoadError::Io(e));
+            *last_error.write().unwrap() = Some(Arc::clone(&e));
+            on_reload(Err(&e));
+            return;
+        }
+    };
+    if is_own_write(&contents) {
+        return;
+    }
+
+    match parse_settings(&contents) {
+        Ok(new) => {
+            let had_error = last_error.write().unwrap().take().is_some();
+            let changed = {
+                let mut s = settings.write().unwrap();
+                let changed = *s != new;
+                *s = new;
+                changed
+            };
+            if changed {
+                info!("Reloaded settings from {}", path.display());
+            }
+            if changed || had_error {
+                on_reload(Ok(()));
+            }
+        }
+        Err(e) => {
+            warn!("Ignoring invalid settings edit: {}", e);
+            let e = Arc::new(e);
+            *last_error.write().unwrap() = Some(Arc::clone(&e));
+            on_reload(Err(&e));
+        }
+    }
+}
@@ -2,88 +2,201 @@ use std::sync::{Arc, RwLock, mpsc};
 use std::thread;
 use std::time::{Duration, Instant};
 
-use crate::settings::{Action, Key, MouseButton, Settings};
+use crate::backend::Backend;
+use crate::health::{Backoff, Health};
+use crate::scheduler::{Scheduler, Wake, wait_until};
+use crate::settings::Settings;
 
+/// Messages that drive the click worker. The worker blocks on these while idle and
+/// is interrupted by them while waiting for the next click.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkerCommand {
+    /// Starts clicking with a fresh count.
+    Start,
+    /// Continues clicking after a stop, keeping the count towards the click limit.
+    Resume,
+    Stop,
+    /// The shared settings were modified; re-read them before the next click.
+    SettingsChanged,
+    Shutdown,
+}
+
+/// Consecutive failed actions after which the backend is reconnected.
+const MAX_CONSECUTIVE_FAILURES: u32 = 5;
+/// At intervals shorter than this, backends are flushed in batches at most this far apart.
+const FLUSH_EVERY: Duration = Duration::from_millis(1);
+
+/// What the worker tells its owner while running.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WorkerReport {
+    /// An action was performed; carries the count since the last `Start`.
+    Clicked(u64),
+    /// The configured click limit was reached and the worker stopped itself.
+    LimitReached,
+    /// Clicking stopped because the backend cannot perform actions.
+    Failed(String),
+    /// The backend's health changed.
+    Injector(Health),
+}
+
+#[derive(Debug, Clone)]
+pub struct WorkerHandle {
+    tx: mpsc::Sender<WorkerCommand>,
+}
+
+impl WorkerHandle {
+    pub fn send(&self, command: WorkerCommand) {
+        let _ = self.tx.send(command);
+    }
+}
+
+/// Runs the backend on the worker thread. A backend that fails to connect is retried
+/// with backoff while idle, and immediately when clicking is started.
 pub fn start_click_worker(
     settings: Arc<RwLock<Settings>>,
-    active: Arc<RwLock<bool>>,
-    rx_wake: mpsc::Receiver<()>,
-) {
+    mut backend: impl Backend + 'static,
+    report: mpsc::Sender<WorkerReport>,
+) -> WorkerHandle {
+    let (tx, rx) = mpsc::channel::<WorkerCommand>();
     thread::spawn(move || {
-        #[cfg(feature = "clicking_enigo")]
-        let mut enigo = enigo::Enigo::new(&enigo::Settings::default()).ok();
+        let mut backoff = Backoff::new();
+        let mut retry_at: Option<Instant> = None;
+        let mut ready = connect(&mut backend, &mut backoff, &mut retry_at, &report).is_ok();
 
-        let mut last_tick = Instant::now();
-        loop {
-            while rx_wake.try_recv().is_ok() {}
+        let read_settings = || {
+            let s = settings.read().unwrap();
+            let interval = s.interval.as_duration().max(Duration::from_micros(1));
+            (s.action.clone(), interval, s.timing, s.click_limit)
+        };
+        let (mut action, mut interval, timing, mut limit) = read_settings();
+        let mut scheduler = Scheduler::new(timing, interval, Instant::now());
+        let mut last_flush = Instant::now();
+        let mut running = false;
+        let mut clicks: u64 = 0;
+        let mut failures: u32 = 0;
 
-            let is_active = { *active.read().unwrap() };
-            if !is_active {
-                thread::sleep(Duration::from_millis(50));
-                last_tick = Instant::now();
-                continue;
-            }
-
-            let (action, interval_ms) = {
-                let s = settings.read().unwrap();
-                (s.action.clone(), s.interval_ms)
+        loop {
+            let wake = if running {
+                wait_until(scheduler.deadline(), &rx)
+            } else if let Some(at) = retry_at {
+                wait_until(at, &rx)
+            } else {
+                rx.recv().map_or(Wake::Disconnected, Wake::Message)
             };
 
-            let interval = Duration::from_millis(interval_ms.max(1));
-            let now = Instant::now();
-            let elapsed = now.saturating_duration_since(last_tick);
-            if elapsed < interval {
-                thread::sleep(interval - elapsed);
-            }
-            last_tick = Instant::now();
-
-            #[cfg(feature = "clicking_enigo")]
-            {
-                if let Some(enigo) = enigo.as_mut() {
-                    use enigo::{Button as EButton, Direction as EDir, Keyboard as _, Mouse as _};
-                    match action {
-                        Action::Mouse(MouseButton::Left) => {
-                            let _ = enigo.button(EButton::Left, EDir::Click);
-                        }
-                        Action::Mouse(MouseButton::Right) => {
-                            let _ = enigo.button(EButton::Right, EDir::Click);
+            let command = match wake {
+                Wake::Disconnected => return,
+                Wake::Message(command) => command,
+                Wake::Deadline if !running => {
+                    ready = connect(&mut backend, &mut backoff, &mut retry_at, &report).is_ok();
+                    continue;
+                }
+                Wake::Deadline => {
+                    let flush = interval >= FLUSH_EVERY || last_flush.elapsed() >= FLUSH_EVERY;
+                    let result = backend.perform(&action).and_then(|()| {
+                        if flush {
+                            last_flush = Instant::now();
+                            backend.flush()
+                        } else {
+                            Ok(())
                         }
-                        Action::Mouse(MouseButton::Middle) => {
-                            let _ = enigo.button(EButton::Middle, EDir::Click);
+                    });
+                    if let Err(e) = result {
+                        failures += 1;
+                        let reason = format!("{} backend: {e}", backend.name());
+                        if failures < MAX_CONSECUTIVE_FAILURES {
+                            let _ = report.send(WorkerReport::Injector(Health::Degraded(format!(
+                                "{reason} ({failures} failed in a row)"
+                            ))));
+                        } else {
+                            running = false;
+                            ready = false;
+                            failures = 0;
+                            backend.reset();
+                            retry_at = Some(Instant::now() + backoff.next_delay());
+                            let _ =
+                                report.send(WorkerReport::Injector(Health::Failed(reason.clone())));
+                            let _ = report.send(WorkerReport::Failed(reason));
+                            continue;
                         }
-                        Action::Keyboard(k) => match k {
-                            Key::Char(c) => {
-                                let s = c.to_string();
-                                let _ = enigo.text(&s);
-                            }
-                            Key::Space => {
-                                let _ = enigo.text(" ");
-                            }
-                            Key::Enter => {
-                                let _ = enigo.text("\n");
-                            }
-                            _ => {
-                                if let Some(ek) = map_key_to_enigo(&k) {
-                                    let _ = enigo.key(ek, enigo::Direction::Click);
-                                }
-                            }
-                        },
+                        // A failed action is no click, but is not retried either.
+                        scheduler.advance(Instant::now());
+                        continue;
+                    }
+                    if failures > 0 {
+                        failures = 0;
+                        let _ = report.send(WorkerReport::Injector(Health::Ok));
                     }
+
+                    clicks += 1;
+                    let _ = report.send(WorkerReport::Clicked(clicks));
+                    if limit.is_some_and(|limit| clicks >= limit) {
+                        running = false;
+                        let _ = backend.flush();
+                        let _ = report.send(WorkerReport::LimitReached);
+                        continue;
+                    }
+                    scheduler.advance(Instant::now());
+                    continue;
                 }
-            }
+            };
 
-            #[cfg(not(feature = "clicking_enigo"))]
-            {
-                let _ = action; // no-op when enigo disabled
+            match command {
+                WorkerCommand::Start | WorkerCommand::Resume | WorkerCommand::SettingsChanged => {
+                    let (new_action, new_interval, timing, new_limit) = read_settings();
+                    action = new_action;
+                    interval = new_interval;
+                    limit = new_limit;
+                    scheduler.configure(timing, interval);
+                    if command == WorkerCommand::Start {
+                        clicks = 0;
+                    }
+                    if command == WorkerCommand::SettingsChanged || running {
+                        continue;
+                    }
+                    if !ready {
+                        backoff.reset();
+                        match connect(&mut backend, &mut backoff, &mut retry_at, &report) {
+                            Ok(()) => ready = true,
+                            Err(reason) => {
+                                let _ = report.send(WorkerReport::Failed(reason));
+                                continue;
+                            }
+                        }
+                    }
+                    scheduler.start(Instant::now());
+                    running = true;
+                }
+                WorkerCommand::Stop => {
+                    running = false;
+                    let _ = backend.flush();
+                }
+                WorkerCommand::Shutdown => return,
             }
         }
     });
+    WorkerHandle { tx }
 }
 
-#[cfg(feature = "clicking_enigo")]
-fn map_key_to_enigo(key: &Key) -> Option<enigo::Key> {
-    match key {
-        Key::Char(c) => Some(enigo::Key::Unicode(*c)),
-        _ => None,
+/// Initializes the backend and reports its health; on failure schedules the next attempt.
+fn connect(
+    backend: &mut impl Backend,
+    backoff: &mut Backoff,
+    retry_at: &mut Option<Instant>,
+    report: &mpsc::Sender<WorkerReport>,
+) -> Result<(), String> {
+    match backend.init() {
+        Ok(()) => {
+            backoff.reset();
+            *retry_at = None;
+            let _ = report.send(WorkerReport::Injector(Health::Ok));
+            Ok(())
+        }
+        Err(e) => {
+            let reason = format!("{} backend: {e}", backend.name());
+            *retry_at = Some(Instant::now() + backoff.next_delay());
+            let _ = report.send(WorkerReport::Injector(Health::Failed(reason.clone())));
+            Err(reason)
+        }
     }
 }
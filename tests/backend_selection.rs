@@ -0,0 +1,39 @@
+//! The automatic injector order per session. Probing itself needs a display or
+//! `/dev/uinput`, so only the candidate lists are checked here.
+#![cfg(all(target_os = "linux", feature = "clicking_enigo"))]
+
+use autoclicker::backend::candidates;
+use autoclicker::session::Session;
+use autoclicker::settings::InputInjector::{self, Auto, Enigo, Uinput, Xtest, Ydotool};
+
+#[test]
+fn x11_prefers_xtest() {
+    assert_eq!(candidates(Auto, Session::X11), [Xtest, Enigo, Uinput]);
+}
+
+#[test]
+fn wayland_prefers_ydotoold_then_uinput_then_xwayland() {
+    assert_eq!(
+        candidates(Auto, Session::Wayland),
+        [Ydotool, Uinput, Xtest, Enigo]
+    );
+}
+
+#[test]
+fn console_can_only_use_uinput_devices() {
+    assert_eq!(candidates(Auto, Session::Headless), [Ydotool, Uinput]);
+}
+
+#[test]
+fn explicit_choice_is_not_second_guessed() {
+    for session in [Session::X11, Session::Wayland, Session::Headless] {
+        assert_eq!(candidates(Enigo, session), [Enigo]);
+        assert_eq!(candidates(Uinput, session), [Uinput]);
+        assert_eq!(candidates(Xtest, session), [Xtest]);
+        assert_eq!(candidates(Ydotool, session), [Ydotool]);
+        assert_eq!(
+            candidates(InputInjector::None, session),
+            [InputInjector::None]
+        );
+    }
+}
@@ -0,0 +1,232 @@
+use std::time::Instant;
+
+use autoclicker::hotkey::{BindingsMatcher, Decision, KeyEvent, KeyInput, Modifier};
+use autoclicker::settings::{Action, Binding, Command, Key, MouseButton, Profile, Settings};
+use autoclicker::validate::has_errors;
+
+fn with_bindings() -> Settings {
+    Settings {
+        hotkey: "Ctrl+K, C".parse().unwrap(),
+        profiles: vec![Profile {
+            name: "1".to_string(),
+            action: Action::Mouse(MouseButton::Right),
+            interval: "20cps".parse().unwrap(),
+            timing: Default::default(),
+            click_limit: Some(100),
+        }],
+        bindings: vec![Binding {
+            hotkey: "Ctrl+K, 1".parse().unwrap(),
+            command: Command::Profile("1".to_string()),
+        }],
+        ..Settings::default()
+    }
+}
+
+fn leader_then(key: char) -> Vec<KeyEvent> {
+    vec![
+        KeyEvent::Press(KeyInput::Modifier(Modifier::ControlLeft)),
+        KeyEvent::Press(KeyInput::Key(Key::Char('K'))),
+        KeyEvent::Release(KeyInput::Key(Key::Char('K'))),
+        KeyEvent::Release(KeyInput::Modifier(Modifier::ControlLeft)),
+        KeyEvent::Press(KeyInput::Key(Key::Char(key))),
+        KeyEvent::Release(KeyInput::Key(Key::Char(key))),
+    ]
+}
+
+fn run(settings: &Settings, events: &[KeyEvent]) -> Vec<(Command, Decision)> {
+    let mut matcher = BindingsMatcher::new();
+    let now = Instant::now();
+    events
+        .iter()
+        .flat_map(|e| matcher.handle_at(settings, e, now))
+        .collect()
+}
+
+#[test]
+fn each_binding_runs_its_own_command() {
+    let settings = with_bindings();
+    assert_eq!(
+        run(&settings, &leader_then('C')),
+        [(Command::Toggle, Decision::Toggle)]
+    );
+    assert_eq!(
+        run(&settings, &leader_then('1')),
+        [(Command::Profile("1".to_string()), Decision::Toggle)]
+    );
+}
+
+#[test]
+fn pending_leader_is_reported() {
+    let settings = with_bindings();
+    let mut matcher = BindingsMatcher::new();
+    let events = leader_then('1');
+    for event in &events[..2] {
+        matcher.handle(&settings, event);
+    }
+    assert_eq!(matcher.pending(&settings).as_deref(), Some("Ctrl+K"));
+    for event in &events[2..5] {
+        matcher.handle(&settings, event);
+    }
+    assert_eq!(matcher.pending(&settings), None);
+}
+
+#[test]
+fn switching_profiles_takes_over_action_and_interval() {
+    let mut settings = with_bindings();
+    assert!(settings.apply_profile("1"));
+    assert_eq!(settings.action, Action::Mouse(MouseButton::Right));
+    assert_eq!(settings.interval, "20cps".parse().unwrap());
+    assert_eq!(settings.click_limit, Some(100));
+    assert_eq!(settings.profile.as_deref(), Some("1"));
+    assert!(!settings.apply_profile("2"));
+}
+
+#[test]
+fn toml_writes_plain_binding_hotkeys_as_text() {
+    let settings = with_bindings();
+    let text = toml::to_string_pretty(&settings).unwrap();
+    assert!(text.contains("hotkey = \"Ctrl+K, 1\""), "{text}");
+    assert!(text.contains("then = [\"C\"]"), "{text}");
+
+    let back: Settings = toml::from_str(&text).unwrap();
+    assert_eq!(back, settings);
+}
+
+#[test]
+fn binding_hotkeys_can_be_tables() {
+    let text = r#"
+        action = { Mouse = "Left" }
+        interval = "100ms"
+
+        [hotkey]
+        ctrl = true
+        alt = false
+        shift = false
+        meta = false
+        key = "F8"
+
+        [[bindings]]
+        command = "Toggle"
+        hotkey = { ctrl = false, alt = false, shift = true, meta = false, key = "F9", mode = "Hold" }
+    "#;
+    let settings: Settings = toml::from_str(text).unwrap();
+    assert_eq!(settings.bindings[0].hotkey.to_string(), "Shift+F9");
+    assert!(!has_errors(&settings.validate()));
+}
+
+#[test]
+fn rejects_bindings_to_missing_profiles() {
+    let mut settings = with_bindings();
+    settings.bindings[0].command = Command::Profile("2".to_string());
+    assert!(has_errors(&settings.validate()));
+}
+
+#[test]
+fn rejects_sequences_that_cannot_fire() {
+    for hotkey in ["Ctrl+K, Ctrl", "Ctrl, C"] {
+        let settings = Settings {
+            hotkey: hotkey.parse().unwrap(),
+            ..Settings::default()
+        };
+        assert!(has_errors(&settings.validate()), "{hotkey}");
+    }
+}
+
+#[test]
+fn live_adjustments_change_the_interval_in_its_own_unit() {
+    let mut settings = Settings {
+        interval: "100ms".parse().unwrap(),
+        ..Settings::default()
+    };
+    let describe = settings.adjust(&Command::DivideInterval(4.0));
+    assert_eq!(describe.as_deref(), Some("Interval 25ms"));
+    settings.adjust(&Command::MultiplyInterval(3.0));
+    assert_eq!(settings.interval, "75ms".parse().unwrap());
+
+    settings.interval = "10cps".parse().unwrap();
+    settings.adjust(&Command::MultiplyInterval(2.0));
+    assert_eq!(settings.interval, "5cps".parse().unwrap());
+    settings.adjust(&Command::StepCps(2.5));
+    assert_eq!(settings.interval, "7.5cps".parse().unwrap());
+
+    settings.interval = "100ms".parse().unwrap();
+    settings.adjust(&Command::StepCps(10.0));
+    assert_eq!(settings.interval, "50ms".parse().unwrap());
+}
+
+#[test]
+fn stepping_below_zero_fails_validation() {
+    let mut settings = Settings {
+        interval: "5cps".parse().unwrap(),
+        ..Settings::default()
+    };
+    settings.adjust(&Command::StepCps(-5.0));
+    assert!(has_errors(&settings.validate()));
+}
+
+#[test]
+fn cycling_goes_through_mouse_buttons_and_profiles() {
+    let mut settings = with_bindings();
+    settings.action = Action::Keyboard(Key::Char('A'));
+    let buttons: Vec<_> = (0..6)
+        .map(|_| settings.adjust(&Command::CycleMouseButton).unwrap())
+        .collect();
+    assert_eq!(
+        buttons,
+        [
+            "Action mouse:left",
+            "Action mouse:right",
+            "Action mouse:middle",
+            "Action mouse:back",
+            "Action mouse:forward",
+            "Action mouse:left",
+        ]
+    );
+
+    settings.profiles.push(Profile {
+        name: "2".to_string(),
+        ..settings.profiles[0].clone()
+    });
+    for expected in ["1", "2", "1"] {
+        assert_eq!(
+            settings.adjust(&Command::NextProfile),
+            Some(format!("Profile {expected}"))
+        );
+    }
+    settings.profiles.clear();
+    assert_eq!(settings.adjust(&Command::NextProfile), None);
+}
+
+#[test]
+fn adjustment_commands_round_trip_and_are_checked() {
+    let mut settings = with_bindings();
+    for command in [
+        Command::MultiplyInterval(2.0),
+        Command::DivideInterval(1.5),
+        Command::StepCps(-5.0),
+        Command::CycleMouseButton,
+        Command::NextProfile,
+    ] {
+        settings.bindings[0].command = command;
+        assert!(
+            !has_errors(&settings.validate()),
+            "{}",
+            settings.bindings[0].command
+        );
+        let text = toml::to_string_pretty(&settings).unwrap();
+        let back: Settings = toml::from_str(&text).unwrap();
+        assert_eq!(back, settings);
+    }
+    for command in [
+        Command::MultiplyInterval(0.0),
+        Command::DivideInterval(-2.0),
+        Command::StepCps(0.0),
+    ] {
+        settings.bindings[0].command = command;
+        assert!(
+            has_errors(&settings.validate()),
+            "{}",
+            settings.bindings[0].command
+        );
+    }
+}
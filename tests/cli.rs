@@ -0,0 +1,260 @@
+//! The `autoclicker config` subcommands, run as the real binary against a temp file.
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+
+use autoclicker::interval::Interval;
+use autoclicker::persist::backup_path;
+use autoclicker::settings::{Settings, read_settings};
+use common::TempDir;
+
+fn autoclicker(config: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_autoclicker"))
+        .arg("--config")
+        .arg(config)
+        .args(args)
+        .env_remove("AUTOCLICKER_CONFIG")
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+#[test]
+fn legacy_interval_ms_is_read_and_migrated() {
+    let dir = TempDir::new("cli");
+    let path = dir.join("settings.toml");
+    let legacy: String = toml::to_string(&Settings::default())
+        .unwrap()
+        .lines()
+        .map(|line| {
+            if line.starts_with("interval = ") {
+                "interval_ms = 60\n".to_string()
+            } else {
+                format!("{line}\n")
+            }
+        })
+        .collect();
+    assert!(legacy.contains("interval_ms = 60"), "{legacy}");
+    fs::write(&path, legacy).unwrap();
+
+    assert_eq!(
+        read_settings(&path).unwrap().interval,
+        Interval::millis(60.0)
+    );
+    assert_eq!(
+        stdout(&autoclicker(&path, &["config", "get", "interval"])),
+        "60ms\n"
+    );
+    assert_eq!(
+        stdout(&autoclicker(&path, &["config", "get", "interval_ms"])),
+        "60\n"
+    );
+
+    stdout(&autoclicker(&path, &["config", "set", "interval_ms=25"]));
+    let migrated = fs::read_to_string(&path).unwrap();
+    assert!(migrated.contains("interval = \"25ms\""), "{migrated}");
+    assert!(!migrated.contains("interval_ms"), "{migrated}");
+}
+
+#[test]
+fn interval_ms_takes_any_interval() {
+    let dir = TempDir::new("cli");
+    let path = dir.join("settings.toml");
+    stdout(&autoclicker(&path, &["config", "set", "interval_ms=20cps"]));
+    assert_eq!(
+        stdout(&autoclicker(&path, &["config", "get", "interval_ms"])),
+        "50\n"
+    );
+    assert_eq!(
+        stdout(&autoclicker(&path, &["config", "get", "interval"])),
+        "20cps\n"
+    );
+}
+
+#[test]
+fn unset_optional_settings_can_be_set() {
+    let dir = TempDir::new("cli");
+    let path = dir.join("settings.toml");
+    stdout(&autoclicker(&path, &["config", "reset"]));
+    assert_eq!(
+        stdout(&autoclicker(&path, &["config", "get", "click_limit"])),
+        ""
+    );
+
+    stdout(&autoclicker(&path, &["config", "set", "click_limit=100"]));
+    assert_eq!(read_settings(&path).unwrap().click_limit, Some(100));
+    assert_eq!(
+        stdout(&autoclicker(&path, &["config", "get", "click_limit"])),
+        "100\n"
+    );
+
+    stdout(&autoclicker(
+        &path,
+        &["config", "set", "ydotool_socket=/run/ydotool.sock"],
+    ));
+    assert_eq!(
+        read_settings(&path).unwrap().ydotool_socket,
+        Some("/run/ydotool.sock".into())
+    );
+
+    stdout(&autoclicker(
+        &path,
+        &[
+            "config",
+            "set",
+            r#"profiles=[{ name = "fast", action = { Mouse = "Right" }, interval = "20cps" }]"#,
+            "profile=fast",
+        ],
+    ));
+    let settings = read_settings(&path).unwrap();
+    assert_eq!(settings.profiles[0].name, "fast");
+    assert_eq!(settings.profile.as_deref(), Some("fast"));
+}
+
+#[test]
+fn unknown_settings_are_still_rejected() {
+    let dir = TempDir::new("cli");
+    let path = dir.join("settings.toml");
+    let output = autoclicker(&path, &["config", "set", "click_limt=100"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("unknown setting"));
+    assert!(!path.exists());
+}
+
+#[test]
+fn set_repairs_an_invalid_file() {
+    let dir = TempDir::new("cli");
+    let path = dir.join("settings.toml");
+    stdout(&autoclicker(&path, &["config", "set", "click_limit=5"]));
+    let invalid = fs::read_to_string(&path)
+        .unwrap()
+        .replace("click_limit = 5", "click_limit = 0");
+    fs::write(&path, &invalid).unwrap();
+    assert!(read_settings(&path).is_err());
+
+    let output = autoclicker(&path, &["config", "set", "interval=50ms"]);
+    assert!(!output.status.success());
+    assert_eq!(fs::read_to_string(&path).unwrap(), invalid);
+
+    stdout(&autoclicker(&path, &["config", "set", "click_limit=10"]));
+    assert_eq!(read_settings(&path).unwrap().click_limit, Some(10));
+}
+
+#[test]
+fn get_reads_back_what_set_wrote() {
+    let dir = TempDir::new("cli");
+    let path = dir.join("settings.toml");
+    stdout(&autoclicker(
+        &path,
+        &[
+            "config",
+            "set",
+            "hotkey=ctrl+f9",
+            "action=mouse:middle",
+            "interval=25ms",
+        ],
+    ));
+
+    for (key, value) in [
+        ("hotkey", "Ctrl+F9\n"),
+        ("hotkey.ctrl", "true\n"),
+        ("action", "mouse:middle\n"),
+        ("interval", "25ms\n"),
+    ] {
+        assert_eq!(
+            stdout(&autoclicker(&path, &["config", "get", key])),
+            value,
+            "{key}"
+        );
+    }
+}
+
+#[test]
+fn print_shows_the_effective_settings_in_either_format() {
+    let dir = TempDir::new("cli");
+    let path = dir.join("settings.toml");
+    stdout(&autoclicker(&path, &["config", "set", "click_limit=7"]));
+    let expected = read_settings(&path).unwrap();
+
+    let toml = stdout(&autoclicker(&path, &["config", "print"]));
+    assert_eq!(toml::from_str::<Settings>(&toml).unwrap(), expected);
+    let json = stdout(&autoclicker(
+        &path,
+        &["config", "print", "--format", "json"],
+    ));
+    assert_eq!(serde_json::from_str::<Settings>(&json).unwrap(), expected);
+
+    // Without a file, the defaults are in effect.
+    let missing = dir.join("missing.toml");
+    let toml = stdout(&autoclicker(&missing, &["config", "print"]));
+    assert_eq!(
+        toml::from_str::<Settings>(&toml).unwrap(),
+        Settings::default()
+    );
+    assert!(!missing.exists());
+}
+
+#[test]
+fn reset_restores_the_defaults_and_keeps_a_backup() {
+    let dir = TempDir::new("cli");
+    let path = dir.join("settings.toml");
+    stdout(&autoclicker(&path, &["config", "set", "click_limit=5"]));
+    let before = fs::read_to_string(&path).unwrap();
+
+    stdout(&autoclicker(&path, &["config", "reset"]));
+    assert_eq!(read_settings(&path).unwrap(), Settings::default());
+    assert_eq!(fs::read_to_string(backup_path(&path, 1)).unwrap(), before);
+}
+
+#[test]
+fn validate_reports_problems_and_fails() {
+    let dir = TempDir::new("cli");
+    let path = dir.join("settings.toml");
+    stdout(&autoclicker(&path, &["config", "set", "click_limit=5"]));
+    assert!(stdout(&autoclicker(&path, &["config", "validate"])).ends_with(": ok\n"));
+
+    let invalid = dir.join("invalid.toml");
+    let contents = fs::read_to_string(&path)
+        .unwrap()
+        .replace("click_limit = 5", "click_limit = 0");
+    fs::write(&invalid, contents).unwrap();
+    let path_arg = invalid.to_str().unwrap();
+    let output = autoclicker(&path, &["config", "validate", path_arg]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("click_limit"));
+}
+
+#[test]
+fn headless_run_clicks_up_to_the_limit_and_exits() {
+    let dir = TempDir::new("cli");
+    let path = dir.join("settings.toml");
+    stdout(&autoclicker(
+        &path,
+        &[
+            "config",
+            "set",
+            "injector=None",
+            "interval=1ms",
+            "click_limit=3",
+        ],
+    ));
+
+    let output = autoclicker(&path, &["run", "--start"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stderr}");
+    assert!(stderr.contains("Clicking with none"), "{stderr}");
+    assert!(
+        stderr.contains("Stopped (limit reached) (3 clicks)"),
+        "{stderr}"
+    );
+}
@@ -0,0 +1,83 @@
+mod common;
+
+use std::path::PathBuf;
+
+use autoclicker::settings::{ConfigCandidates, ConfigLocation, ConfigSource};
+use common::TempDir;
+
+fn all_candidates(dir: &TempDir) -> ConfigCandidates {
+    let portable = dir.join("settings.toml");
+    std::fs::write(&portable, "").unwrap();
+    ConfigCandidates {
+        flag: Some(PathBuf::from("/flag/settings.toml")),
+        env: Some(PathBuf::from("/env/settings.toml")),
+        portable: Some(portable),
+        config_dir: Some(PathBuf::from("/home/user/.config/autoclicker")),
+    }
+}
+
+fn location(path: impl Into<PathBuf>, source: ConfigSource) -> Option<ConfigLocation> {
+    Some(ConfigLocation {
+        path: path.into(),
+        source,
+    })
+}
+
+#[test]
+fn flag_beats_env_beats_portable_beats_config_dir() {
+    let dir = TempDir::new("location");
+    let mut candidates = all_candidates(&dir);
+    assert_eq!(
+        candidates.clone().resolve(),
+        location("/flag/settings.toml", ConfigSource::Flag)
+    );
+
+    candidates.flag = None;
+    assert_eq!(
+        candidates.clone().resolve(),
+        location("/env/settings.toml", ConfigSource::Env)
+    );
+
+    candidates.env = None;
+    assert_eq!(
+        candidates.clone().resolve(),
+        location(dir.join("settings.toml"), ConfigSource::Portable)
+    );
+
+    candidates.portable = None;
+    assert_eq!(
+        candidates.resolve(),
+        location(
+            "/home/user/.config/autoclicker/settings.toml",
+            ConfigSource::ProjectDirs
+        )
+    );
+}
+
+#[test]
+fn portable_file_is_only_used_if_it_exists() {
+    let dir = TempDir::new("location");
+    let candidates = ConfigCandidates {
+        portable: Some(dir.join("settings.toml")),
+        config_dir: Some(PathBuf::from("/config")),
+        ..ConfigCandidates::default()
+    };
+    assert_eq!(
+        candidates.resolve(),
+        location("/config/settings.toml", ConfigSource::ProjectDirs)
+    );
+}
+
+#[test]
+fn nothing_resolves_to_none() {
+    let dir = TempDir::new("location");
+    assert_eq!(ConfigCandidates::default().resolve(), None);
+    assert_eq!(
+        ConfigCandidates {
+            portable: Some(dir.join("settings.toml")),
+            ..ConfigCandidates::default()
+        }
+        .resolve(),
+        None
+    );
+}
@@ -0,0 +1,127 @@
+use autoclicker::settings::{
+    Action, Binding, Command, Hotkey, Key, MouseButton, Profile, Settings, Trigger,
+};
+use autoclicker::validate::{Diagnostic, Severity};
+
+fn binding(hotkey: &str, command: Command) -> Binding {
+    Binding {
+        hotkey: hotkey.parse().unwrap(),
+        command,
+    }
+}
+
+fn profile(name: &str, action: Action) -> Profile {
+    Profile {
+        name: name.to_string(),
+        action,
+        interval: "10cps".parse().unwrap(),
+        timing: Default::default(),
+        click_limit: None,
+    }
+}
+
+fn with_hotkeys(hotkey: &str, bindings: &[&str]) -> Settings {
+    Settings {
+        hotkey: hotkey.parse().unwrap(),
+        bindings: bindings
+            .iter()
+            .map(|b| binding(b, Command::Toggle))
+            .collect(),
+        ..Settings::default()
+    }
+}
+
+fn at<'a>(diagnostics: &'a [Diagnostic], path: &str) -> Vec<&'a Diagnostic> {
+    diagnostics.iter().filter(|d| d.path == path).collect()
+}
+
+#[test]
+fn defaults_have_no_conflicts() {
+    assert_eq!(Settings::default().validate(), []);
+}
+
+#[test]
+fn duplicate_bindings_are_errors() {
+    let diagnostics = with_hotkeys("Ctrl+F8", &["Ctrl+F9", "ctrl+f8"]).validate();
+    let found = at(&diagnostics, "bindings[1].hotkey");
+    assert_eq!(found.len(), 1, "{diagnostics:?}");
+    assert_eq!(found[0].severity, Severity::Error);
+    assert!(found[0].message.contains("hotkey (Toggle)"), "{}", found[0]);
+    assert!(at(&diagnostics, "bindings[0].hotkey").is_empty());
+}
+
+#[test]
+fn a_hotkey_that_starts_a_sequence_is_an_error() {
+    let diagnostics = with_hotkeys("Ctrl+K, C", &["Ctrl+K"]).validate();
+    let found = at(&diagnostics, "bindings[0].hotkey");
+    assert_eq!(found.len(), 1, "{diagnostics:?}");
+    assert!(found[0].is_error());
+
+    let apart = with_hotkeys("Ctrl+K, C", &["Ctrl+K, 1"]).validate();
+    assert!(at(&apart, "bindings[0].hotkey").is_empty(), "{apart:?}");
+}
+
+#[test]
+fn overlap_follows_sides_extra_modifiers_and_triggers() {
+    let sides = with_hotkeys("LeftCtrl+F8", &["RightCtrl+F8"]).validate();
+    assert!(at(&sides, "bindings[0].hotkey").is_empty(), "{sides:?}");
+    let either = with_hotkeys("Ctrl+F8", &["RightCtrl+F8"]).validate();
+    assert_eq!(at(&either, "bindings[0].hotkey").len(), 1);
+
+    let mut extra = with_hotkeys("Ctrl+F8", &["Ctrl+Shift+F8"]);
+    assert!(at(&extra.validate(), "bindings[0].hotkey").is_empty());
+    extra.hotkey.extra_modifiers = true;
+    assert_eq!(at(&extra.validate(), "bindings[0].hotkey").len(), 1);
+
+    let mut taps = with_hotkeys("F8", &[]);
+    taps.hotkey.trigger = Trigger::Tap;
+    taps.bindings.push(Binding {
+        hotkey: Hotkey {
+            trigger: Trigger::LongPress,
+            ..taps.hotkey.clone()
+        },
+        command: Command::Toggle,
+    });
+    assert!(at(&taps.validate(), "bindings[0].hotkey").is_empty());
+}
+
+#[test]
+fn actions_that_press_a_hotkey_are_reported() {
+    let mut settings = Settings {
+        action: Action::Keyboard(Key::F9),
+        profiles: vec![profile("fast", Action::Mouse(MouseButton::Middle))],
+        bindings: vec![
+            binding("F9", Command::Profile("fast".to_string())),
+            binding("MouseMiddle", Command::Toggle),
+        ],
+        ..Settings::default()
+    };
+    let diagnostics = settings.validate();
+    let action = at(&diagnostics, "action");
+    assert_eq!(action.len(), 1, "{diagnostics:?}");
+    assert!(action[0].message.contains("switch to profile fast"));
+    assert!(action[0].is_error());
+    assert_eq!(at(&diagnostics, "profiles[0].action").len(), 1);
+
+    settings.bindings[0].hotkey = "Shift+F9".parse().unwrap();
+    let with_modifier = settings.validate();
+    assert_eq!(at(&with_modifier, "action")[0].severity, Severity::Warning);
+}
+
+#[test]
+fn desktop_shortcuts_are_warnings() {
+    for combo in [
+        "Ctrl+Alt+T",
+        "Super+L",
+        "Ctrl+Alt+F2",
+        "Ctrl+Alt+Delete",
+        "Alt+F4",
+    ] {
+        let diagnostics = with_hotkeys(combo, &[]).validate();
+        let found = at(&diagnostics, "hotkey");
+        assert_eq!(found.len(), 1, "{combo}: {diagnostics:?}");
+        assert_eq!(found[0].severity, Severity::Warning);
+    }
+    let leader = with_hotkeys("Ctrl+F8", &["Ctrl+K, Ctrl+Alt+T"]).validate();
+    assert_eq!(at(&leader, "bindings[0].hotkey").len(), 1);
+}
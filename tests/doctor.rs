@@ -0,0 +1,56 @@
+use autoclicker::doctor::{Check, Status, check_hotkeys, has_failures};
+use autoclicker::settings::{Binding, Command, Settings};
+
+fn check(status: Status) -> Check {
+    Check {
+        name: "Test",
+        status,
+        detail: String::new(),
+        fix: None,
+    }
+}
+
+#[test]
+fn only_failed_checks_are_failures() {
+    assert!(!has_failures(&[]));
+    assert!(!has_failures(&[
+        check(Status::Ok),
+        check(Status::Skip),
+        check(Status::Warn)
+    ]));
+    assert!(has_failures(&[check(Status::Ok), check(Status::Fail)]));
+}
+
+#[test]
+fn default_hotkeys_pass() {
+    let check = check_hotkeys(&Settings::default());
+    assert_eq!(check.status, Status::Ok);
+    assert_eq!(check.detail, "1 bound, no conflicts");
+    assert_eq!(check.fix, None);
+}
+
+#[test]
+fn desktop_shortcuts_warn() {
+    let settings = Settings {
+        hotkey: "Alt+Tab".parse().unwrap(),
+        ..Settings::default()
+    };
+    let check = check_hotkeys(&settings);
+    assert_eq!(check.status, Status::Warn);
+    assert!(check.detail.starts_with("hotkey: "), "{}", check.detail);
+    assert!(check.fix.is_some());
+}
+
+#[test]
+fn clashing_bindings_fail() {
+    let settings = Settings {
+        bindings: vec![Binding {
+            hotkey: Settings::default().hotkey,
+            command: Command::NextProfile,
+        }],
+        ..Settings::default()
+    };
+    let check = check_hotkeys(&settings);
+    assert_eq!(check.status, Status::Fail, "{}", check.detail);
+    assert!(check.detail.contains("bindings[0]"), "{}", check.detail);
+}
@@ -0,0 +1,233 @@
+use std::sync::{Arc, RwLock, mpsc};
+use std::time::Duration;
+
+use autoclicker::hotkey::{BindingsMatcher, KeyEvent, KeyInput, Modifier, handle_event};
+use autoclicker::settings::{Command, Key, MouseButton};
+use autoclicker::{ClickerState, Engine, EngineEvent, Hotkey, NullBackend, Settings};
+
+const WAIT: Duration = Duration::from_secs(5);
+
+fn start(limit: Option<u64>) -> (Engine, mpsc::Receiver<EngineEvent>) {
+    let settings = Settings {
+        interval: "1ms".parse().unwrap(),
+        click_limit: limit,
+        ..Settings::default()
+    };
+    let (tx, rx) = mpsc::channel();
+    let engine = Engine::builder(Arc::new(RwLock::new(settings)), NullBackend)
+        .subscribe(move |event| {
+            let _ = tx.send(event.clone());
+        })
+        .start();
+    (engine, rx)
+}
+
+/// The state changes until the clicker reaches `to`.
+fn transitions_until(rx: &mpsc::Receiver<EngineEvent>, to: ClickerState) -> Vec<ClickerState> {
+    let mut seen = Vec::new();
+    while seen.last() != Some(&to) {
+        if let EngineEvent::StateChanged { to, .. } = rx.recv_timeout(WAIT).unwrap() {
+            seen.push(to);
+        }
+    }
+    seen
+}
+
+#[test]
+fn start_arms_then_runs_and_stop_goes_idle() {
+    let (engine, rx) = start(None);
+    assert_eq!(engine.state(), ClickerState::Idle);
+
+    engine.start_clicking();
+    assert_eq!(
+        transitions_until(&rx, ClickerState::Running),
+        [ClickerState::Arming, ClickerState::Running]
+    );
+    engine.stop();
+    assert_eq!(
+        transitions_until(&rx, ClickerState::Idle),
+        [ClickerState::Idle]
+    );
+    assert!(engine.clicks() > 0);
+}
+
+#[test]
+fn pause_and_resume_keep_the_count() {
+    let (engine, rx) = start(None);
+    engine.start_clicking();
+    transitions_until(&rx, ClickerState::Running);
+
+    engine.pause();
+    assert_eq!(engine.state(), ClickerState::Paused);
+    std::thread::sleep(Duration::from_millis(50));
+    let paused_at = engine.clicks();
+    assert!(paused_at > 0);
+
+    engine.resume();
+    assert_eq!(
+        transitions_until(&rx, ClickerState::Running),
+        [
+            ClickerState::Paused,
+            ClickerState::Arming,
+            ClickerState::Running
+        ]
+    );
+    assert!(engine.clicks() > paused_at);
+    engine.stop();
+}
+
+#[test]
+fn limit_stops_the_clicker_and_restart_counts_afresh() {
+    let (engine, rx) = start(Some(3));
+    engine.start_clicking();
+    assert_eq!(
+        transitions_until(&rx, ClickerState::StoppedByLimit),
+        [
+            ClickerState::Arming,
+            ClickerState::Running,
+            ClickerState::StoppedByLimit
+        ]
+    );
+    assert_eq!(engine.clicks(), 3);
+
+    engine.toggle();
+    transitions_until(&rx, ClickerState::StoppedByLimit);
+    assert_eq!(engine.clicks(), 3);
+}
+
+#[test]
+fn settings_changed_while_clicking_keeps_running() {
+    let (engine, rx) = start(None);
+    engine.start_clicking();
+    transitions_until(&rx, ClickerState::Running);
+
+    engine.settings().write().unwrap().interval = "2ms".parse().unwrap();
+    engine.settings_changed();
+    let event = rx
+        .iter()
+        .find(|e| {
+            !matches!(
+                e,
+                EngineEvent::HealthChanged { .. } | EngineEvent::ClicksChanged(_)
+            )
+        })
+        .unwrap();
+    assert_eq!(event, EngineEvent::SettingsChanged);
+    assert_eq!(engine.state(), ClickerState::Running);
+
+    // A lower limit still applies to the clicks already made.
+    engine.settings().write().unwrap().click_limit = Some(1);
+    engine.settings_changed();
+    transitions_until(&rx, ClickerState::StoppedByLimit);
+}
+
+#[test]
+fn subscribers_can_drive_the_engine_and_subscribe() {
+    let (engine, rx) = start(None);
+    let inner = engine.clone();
+    engine.subscribe(move |event| {
+        if let EngineEvent::StateChanged {
+            to: ClickerState::Running,
+            ..
+        } = event
+        {
+            inner.subscribe(|_| {});
+            inner.stop();
+        }
+    });
+
+    engine.start_clicking();
+    assert_eq!(
+        transitions_until(&rx, ClickerState::Idle),
+        [
+            ClickerState::Arming,
+            ClickerState::Running,
+            ClickerState::Idle
+        ]
+    );
+}
+
+#[test]
+fn adjustments_apply_to_the_current_settings() {
+    let (engine, rx) = start(None);
+    // An edit made elsewhere, e.g. in the GUI, just before the hotkey fires.
+    engine.settings().write().unwrap().click_limit = Some(50);
+    engine.adjust(&Command::DivideInterval(2.0));
+
+    let settings = engine.settings().read().unwrap().clone();
+    assert_eq!(settings.interval, "0.5ms".parse().unwrap());
+    assert_eq!(settings.click_limit, Some(50));
+    assert!(
+        rx.try_iter()
+            .any(|e| e == EngineEvent::SettingsAdjusted("Interval 0.5ms".to_string()))
+    );
+}
+
+#[test]
+fn invalid_adjustments_are_rejected() {
+    let (engine, rx) = start(None);
+    engine.adjust(&Command::StepCps(-5000.0));
+
+    assert_eq!(
+        engine.settings().read().unwrap().interval,
+        "1ms".parse().unwrap()
+    );
+    assert!(
+        rx.try_iter()
+            .any(|e| matches!(e, EngineEvent::SettingsRejected(_)))
+    );
+}
+
+/// Records the hotkey pressed by `events` and returns what the recording ended with.
+fn record(events: &[KeyEvent]) -> Option<Hotkey> {
+    let (engine, rx) = start(None);
+    engine.record_hotkey(WAIT);
+    let mut matcher = BindingsMatcher::new();
+    for event in events {
+        handle_event(&engine, &mut matcher, event);
+    }
+    rx.iter()
+        .find_map(|e| match e {
+            EngineEvent::HotkeyRecorded(hotkey) => Some(hotkey),
+            _ => None,
+        })
+        .unwrap()
+}
+
+#[test]
+fn recording_skips_plain_left_clicks() {
+    let left = Key::Mouse(MouseButton::Left);
+    let hotkey = record(&[
+        KeyEvent::Press(KeyInput::Key(left.clone())),
+        KeyEvent::Release(KeyInput::Key(left.clone())),
+        KeyEvent::Press(KeyInput::Key(Key::F9)),
+    ])
+    .unwrap();
+    assert_eq!(hotkey.to_string(), "F9");
+
+    let hotkey = record(&[
+        KeyEvent::Press(KeyInput::Modifier(Modifier::ControlLeft)),
+        KeyEvent::Press(KeyInput::Key(left)),
+    ])
+    .unwrap();
+    assert_eq!(hotkey.to_string(), "Ctrl+MouseLeft");
+}
+
+#[test]
+fn click_counts_are_reported_a_few_times_a_second() {
+    let (engine, rx) = start(None);
+    engine.start_clicking();
+    std::thread::sleep(Duration::from_secs(1));
+    let counts: Vec<u64> = rx
+        .try_iter()
+        .filter_map(|e| match e {
+            EngineEvent::ClicksChanged(count) => Some(count),
+            _ => None,
+        })
+        .collect();
+    engine.stop();
+
+    assert!((2..=5).contains(&counts.len()), "{counts:?}");
+    assert!(counts.is_sorted(), "{counts:?}");
+    assert!(counts.last() < Some(&engine.clicks()));
+}
@@ -0,0 +1,146 @@
+//! Drives the evdev listener with a virtual keyboard. Needs write access to `/dev/uinput`
+//! and read access to `/dev/input/event*`, so it only runs on request:
+//! `cargo test --test evdev_listener -- --ignored`.
+#![cfg(target_os = "linux")]
+
+use std::sync::{Arc, RwLock, mpsc};
+use std::thread;
+use std::time::Duration;
+
+use autoclicker::NullBackend;
+use autoclicker::engine::{ClickerState, Engine, EngineEvent};
+use autoclicker::evdev_listener::start_evdev_listener;
+use autoclicker::health::{Health, Subsystem};
+use autoclicker::settings::{HotkeyMode, InputListener, Settings};
+use evdev::uinput::VirtualDevice;
+use evdev::{AttributeSet, EventType, InputEvent, KeyCode};
+
+const TIMEOUT: Duration = Duration::from_secs(3);
+
+fn virtual_keyboard() -> VirtualDevice {
+    named_keyboard("autoclicker test keyboard")
+}
+
+fn named_keyboard(name: &str) -> VirtualDevice {
+    let mut keys = AttributeSet::<KeyCode>::new();
+    for key in [KeyCode::KEY_LEFTCTRL, KeyCode::KEY_F8, KeyCode::KEY_A] {
+        keys.insert(key);
+    }
+    VirtualDevice::builder()
+        .expect("open /dev/uinput")
+        .name(name)
+        .with_keys(&keys)
+        .unwrap()
+        .build()
+        .unwrap()
+}
+
+fn key(device: &mut VirtualDevice, code: KeyCode, value: i32) {
+    device
+        .emit(&[InputEvent::new(EventType::KEY.0, code.0, value)])
+        .unwrap();
+}
+
+fn start(mode: HotkeyMode) -> (Engine, mpsc::Receiver<ClickerState>) {
+    let mut settings = Settings {
+        listener: InputListener::Evdev,
+        ..Settings::default()
+    };
+    settings.hotkey.mode = mode;
+    let (tx, rx) = mpsc::channel();
+    let engine = Engine::builder(Arc::new(RwLock::new(settings)), NullBackend)
+        .subscribe(move |event| {
+            if let EngineEvent::StateChanged { to, .. } = event {
+                let _ = tx.send(to.clone());
+            }
+        })
+        .start();
+    start_evdev_listener(engine.clone()).expect("read /dev/input");
+    (engine, rx)
+}
+
+/// Waits for the state to become `want`, skipping intermediate states.
+fn wait_for(rx: &mpsc::Receiver<ClickerState>, want: &ClickerState) {
+    loop {
+        match rx.recv_timeout(TIMEOUT) {
+            Ok(state) if state == *want => return,
+            Ok(_) => {}
+            Err(_) => panic!("clicker never became {want}"),
+        }
+    }
+}
+
+/// Gives the hotplug watcher time to open a freshly created device.
+fn settle() {
+    thread::sleep(Duration::from_millis(500));
+}
+
+#[test]
+#[ignore = "needs /dev/uinput and /dev/input access"]
+fn hotplugged_keyboard_toggles_the_clicker() {
+    let (engine, rx) = start(HotkeyMode::Toggle);
+    let mut keyboard = virtual_keyboard();
+    settle();
+
+    key(&mut keyboard, KeyCode::KEY_LEFTCTRL, 1);
+    key(&mut keyboard, KeyCode::KEY_F8, 1);
+    key(&mut keyboard, KeyCode::KEY_F8, 2);
+    key(&mut keyboard, KeyCode::KEY_F8, 0);
+    wait_for(&rx, &ClickerState::Arming);
+
+    key(&mut keyboard, KeyCode::KEY_F8, 1);
+    key(&mut keyboard, KeyCode::KEY_F8, 0);
+    key(&mut keyboard, KeyCode::KEY_LEFTCTRL, 0);
+    wait_for(&rx, &ClickerState::Idle);
+
+    key(&mut keyboard, KeyCode::KEY_F8, 1);
+    key(&mut keyboard, KeyCode::KEY_F8, 0);
+    thread::sleep(Duration::from_millis(200));
+    assert!(
+        !engine.state().is_active(),
+        "F8 without Ctrl must not toggle"
+    );
+}
+
+#[test]
+#[ignore = "needs /dev/uinput and /dev/input access"]
+fn unplugging_a_keyboard_releases_a_held_hotkey() {
+    let (engine, rx) = start(HotkeyMode::Hold);
+    let mut keyboard = virtual_keyboard();
+    settle();
+
+    key(&mut keyboard, KeyCode::KEY_LEFTCTRL, 1);
+    key(&mut keyboard, KeyCode::KEY_F8, 1);
+    wait_for(&rx, &ClickerState::Arming);
+    assert!(engine.state().is_active());
+
+    drop(keyboard);
+    wait_for(&rx, &ClickerState::Idle);
+}
+
+#[test]
+#[ignore = "needs /dev/uinput and /dev/input access"]
+fn the_injector_device_is_not_listened_to() {
+    let (engine, _rx) = start(HotkeyMode::Toggle);
+    let mut injector = named_keyboard("autoclicker");
+    settle();
+
+    key(&mut injector, KeyCode::KEY_LEFTCTRL, 1);
+    key(&mut injector, KeyCode::KEY_F8, 1);
+    key(&mut injector, KeyCode::KEY_F8, 0);
+    key(&mut injector, KeyCode::KEY_LEFTCTRL, 0);
+    thread::sleep(Duration::from_millis(200));
+    assert!(
+        !engine.state().is_active(),
+        "keys typed by the injector must not toggle"
+    );
+}
+
+#[test]
+#[ignore = "needs /dev/uinput and /dev/input access"]
+fn listener_is_healthy_while_reading_a_keyboard() {
+    let (engine, _rx) = start(HotkeyMode::Toggle);
+    let _keyboard = virtual_keyboard();
+    settle();
+    assert_eq!(engine.health(Subsystem::Listener), Health::Ok);
+}
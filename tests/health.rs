@@ -0,0 +1,117 @@
+use std::sync::{Arc, RwLock, mpsc};
+use std::time::Duration;
+
+use autoclicker::backend::{Backend, BackendError};
+use autoclicker::health::{Backoff, Health, Subsystem};
+use autoclicker::{ClickerState, Engine, EngineEvent, Settings};
+
+const WAIT: Duration = Duration::from_secs(5);
+
+#[test]
+fn backoff_doubles_up_to_the_cap_until_reset() {
+    let mut backoff = Backoff::new();
+    let delays: Vec<_> = (0..7).map(|_| backoff.next_delay().as_secs()).collect();
+    assert_eq!(delays, [1, 2, 4, 8, 16, 30, 30]);
+
+    backoff.reset();
+    assert_eq!(backoff.next_delay(), Duration::from_secs(1));
+}
+
+/// Fails to connect `connect_failures` times, then fails every action if `broken`.
+struct Unreliable {
+    connect_failures: u32,
+    broken: bool,
+}
+
+impl Backend for Unreliable {
+    fn name(&self) -> &'static str {
+        "unreliable"
+    }
+
+    fn init(&mut self) -> Result<(), BackendError> {
+        if self.connect_failures == 0 {
+            return Ok(());
+        }
+        self.connect_failures -= 1;
+        Err(BackendError("not yet".to_string()))
+    }
+
+    fn perform(&mut self, _: &autoclicker::Action) -> Result<(), BackendError> {
+        if self.broken {
+            return Err(BackendError("gone".to_string()));
+        }
+        Ok(())
+    }
+}
+
+fn start(backend: Unreliable) -> (Engine, mpsc::Receiver<EngineEvent>) {
+    let settings = Settings {
+        interval: "1ms".parse().unwrap(),
+        ..Settings::default()
+    };
+    let (tx, rx) = mpsc::channel();
+    let engine = Engine::builder(Arc::new(RwLock::new(settings)), backend)
+        .subscribe(move |event| {
+            let _ = tx.send(event.clone());
+        })
+        .start();
+    (engine, rx)
+}
+
+/// The injector health reported until it becomes `last`.
+fn injector_health_until(rx: &mpsc::Receiver<EngineEvent>, last: &Health) -> Vec<Health> {
+    let mut seen = Vec::new();
+    while seen.last() != Some(last) {
+        if let EngineEvent::HealthChanged {
+            subsystem: Subsystem::Injector,
+            health,
+        } = rx.recv_timeout(WAIT).unwrap()
+        {
+            seen.push(health);
+        }
+    }
+    seen
+}
+
+#[test]
+fn a_backend_that_cannot_connect_is_retried() {
+    let (engine, rx) = start(Unreliable {
+        connect_failures: 1,
+        broken: false,
+    });
+    assert_eq!(
+        injector_health_until(&rx, &Health::Ok),
+        [
+            Health::Failed("unreliable backend: not yet".to_string()),
+            Health::Ok
+        ]
+    );
+    assert_eq!(engine.health(Subsystem::Injector), Health::Ok);
+}
+
+#[test]
+fn failing_actions_degrade_then_fail_the_injector() {
+    let (engine, rx) = start(Unreliable {
+        connect_failures: 0,
+        broken: true,
+    });
+    injector_health_until(&rx, &Health::Ok);
+
+    engine.start_clicking();
+    let failed = Health::Failed("unreliable backend: gone".to_string());
+    let seen = injector_health_until(&rx, &failed);
+    assert!(
+        seen[..seen.len() - 1]
+            .iter()
+            .all(|h| matches!(h, Health::Degraded(_))),
+        "{seen:?}"
+    );
+    let stopped = rx.iter().find_map(|e| match e {
+        EngineEvent::StateChanged { to, .. } if !to.is_active() => Some(to),
+        _ => None,
+    });
+    assert_eq!(
+        stopped,
+        Some(ClickerState::Error("unreliable backend: gone".to_string()))
+    );
+}
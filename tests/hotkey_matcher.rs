@@ -0,0 +1,833 @@
+use autoclicker::hotkey::{Decision, HotkeyMatcher, KeyEvent, KeyInput, Modifier};
+use std::time::{Duration, Instant};
+
+use autoclicker::settings::{Hotkey, HotkeyMode, Key, MouseButton, Side, Trigger};
+
+fn ctrl_f8(mode: HotkeyMode) -> Hotkey {
+    Hotkey {
+        mode,
+        ..Hotkey::default_toggle()
+    }
+}
+
+fn ctrl_shift_f8() -> Hotkey {
+    Hotkey {
+        shift: true,
+        ..Hotkey::default_toggle()
+    }
+}
+
+fn press_mod(m: Modifier) -> KeyEvent {
+    KeyEvent::Press(KeyInput::Modifier(m))
+}
+
+fn release_mod(m: Modifier) -> KeyEvent {
+    KeyEvent::Release(KeyInput::Modifier(m))
+}
+
+fn press(key: Key) -> KeyEvent {
+    KeyEvent::Press(KeyInput::Key(key))
+}
+
+fn release(key: Key) -> KeyEvent {
+    KeyEvent::Release(KeyInput::Key(key))
+}
+
+/// Feeds `events` in order and returns every decision made.
+fn run(hotkey: &Hotkey, matcher: &mut HotkeyMatcher, events: &[KeyEvent]) -> Vec<Decision> {
+    events
+        .iter()
+        .filter_map(|e| matcher.handle(hotkey, e))
+        .collect()
+}
+
+fn decisions(hotkey: &Hotkey, events: &[KeyEvent]) -> Vec<Decision> {
+    run(hotkey, &mut HotkeyMatcher::new(), events)
+}
+
+#[test]
+fn toggles_on_combo() {
+    let events = [
+        press_mod(Modifier::ControlLeft),
+        press(Key::F8),
+        release(Key::F8),
+        release_mod(Modifier::ControlLeft),
+    ];
+    assert_eq!(
+        decisions(&ctrl_f8(HotkeyMode::Toggle), &events),
+        [Decision::Toggle]
+    );
+}
+
+#[test]
+fn either_side_of_a_modifier_counts() {
+    for side in [Modifier::ControlLeft, Modifier::ControlRight] {
+        let events = [press_mod(side), press(Key::F8)];
+        assert_eq!(
+            decisions(&ctrl_f8(HotkeyMode::Toggle), &events),
+            [Decision::Toggle],
+            "{side:?}"
+        );
+    }
+}
+
+#[test]
+fn key_without_modifiers_does_not_fire() {
+    let events = [press(Key::F8), release(Key::F8)];
+    assert!(decisions(&ctrl_f8(HotkeyMode::Toggle), &events).is_empty());
+}
+
+#[test]
+fn extra_modifiers_do_not_fire() {
+    let events = [
+        press_mod(Modifier::ControlLeft),
+        press_mod(Modifier::AltLeft),
+        press(Key::F8),
+    ];
+    assert!(decisions(&ctrl_f8(HotkeyMode::Toggle), &events).is_empty());
+}
+
+#[test]
+fn other_keys_do_not_fire() {
+    let events = [press_mod(Modifier::ControlLeft), press(Key::F9)];
+    assert!(decisions(&ctrl_f8(HotkeyMode::Toggle), &events).is_empty());
+}
+
+#[test]
+fn letters_match_case_insensitively() {
+    let hotkey = Hotkey {
+        key: Some(Key::Char('k')),
+        ..Hotkey::default_toggle()
+    };
+    let events = [
+        press_mod(Modifier::ControlLeft),
+        press(Key::Char('K')),
+        release(Key::Char('k')),
+        press(Key::Char('k')),
+    ];
+    assert_eq!(
+        decisions(&hotkey, &events),
+        [Decision::Toggle, Decision::Toggle]
+    );
+}
+
+#[test]
+fn autorepeat_fires_once() {
+    let mut events = vec![press_mod(Modifier::ControlLeft)];
+    events.extend(std::iter::repeat_n(press(Key::F8), 20));
+    events.push(release(Key::F8));
+    assert_eq!(
+        decisions(&ctrl_f8(HotkeyMode::Toggle), &events),
+        [Decision::Toggle]
+    );
+}
+
+#[test]
+fn autorepeat_of_modifiers_is_harmless() {
+    let mut events = vec![press_mod(Modifier::ControlLeft); 10];
+    events.push(press(Key::F8));
+    events.push(release(Key::F8));
+    events.extend(vec![press_mod(Modifier::ControlLeft); 10]);
+    events.push(press(Key::F8));
+    assert_eq!(
+        decisions(&ctrl_f8(HotkeyMode::Toggle), &events),
+        [Decision::Toggle, Decision::Toggle]
+    );
+}
+
+#[test]
+fn repeated_presses_toggle_each_time() {
+    let mut events = vec![press_mod(Modifier::ControlLeft)];
+    for _ in 0..3 {
+        events.push(press(Key::F8));
+        events.push(release(Key::F8));
+    }
+    assert_eq!(
+        decisions(&ctrl_f8(HotkeyMode::Toggle), &events),
+        [Decision::Toggle; 3]
+    );
+}
+
+#[test]
+fn modifier_order_does_not_matter() {
+    let orders = [
+        [Modifier::ControlLeft, Modifier::ShiftLeft],
+        [Modifier::ShiftLeft, Modifier::ControlLeft],
+        [Modifier::ShiftRight, Modifier::ControlRight],
+    ];
+    for [first, second] in orders {
+        let events = [press_mod(first), press_mod(second), press(Key::F8)];
+        assert_eq!(
+            decisions(&ctrl_shift_f8(), &events),
+            [Decision::Toggle],
+            "{first:?} then {second:?}"
+        );
+    }
+}
+
+#[test]
+fn modifiers_pressed_after_the_key_do_not_fire() {
+    let events = [
+        press(Key::F8),
+        press_mod(Modifier::ControlLeft),
+        press(Key::F8), // autorepeat of the held key
+    ];
+    assert!(decisions(&ctrl_f8(HotkeyMode::Toggle), &events).is_empty());
+}
+
+#[test]
+fn releasing_the_modifier_before_the_key_toggles_once() {
+    let events = [
+        press_mod(Modifier::ControlLeft),
+        press(Key::F8),
+        release_mod(Modifier::ControlLeft),
+        press(Key::F8), // autorepeat while the key is still down
+        release(Key::F8),
+    ];
+    assert_eq!(
+        decisions(&ctrl_f8(HotkeyMode::Toggle), &events),
+        [Decision::Toggle]
+    );
+}
+
+#[test]
+fn re_pressing_the_modifier_while_the_key_is_held_does_not_fire() {
+    let events = [
+        press_mod(Modifier::ControlLeft),
+        press(Key::F8),
+        release_mod(Modifier::ControlLeft),
+        press_mod(Modifier::ControlLeft),
+        press(Key::F8),
+    ];
+    assert_eq!(
+        decisions(&ctrl_f8(HotkeyMode::Toggle), &events),
+        [Decision::Toggle]
+    );
+}
+
+#[test]
+fn releasing_one_side_keeps_the_other_side_held() {
+    let events = [
+        press_mod(Modifier::ControlLeft),
+        press_mod(Modifier::ControlRight),
+        release_mod(Modifier::ControlLeft),
+        press(Key::F8),
+    ];
+    assert_eq!(
+        decisions(&ctrl_f8(HotkeyMode::Toggle), &events),
+        [Decision::Toggle]
+    );
+}
+
+#[test]
+fn release_of_a_modifier_pressed_before_listening_is_ignored() {
+    let events = [
+        release_mod(Modifier::ShiftLeft),
+        release(Key::F8),
+        press_mod(Modifier::ControlLeft),
+        press(Key::F8),
+    ];
+    assert_eq!(
+        decisions(&ctrl_f8(HotkeyMode::Toggle), &events),
+        [Decision::Toggle]
+    );
+}
+
+#[test]
+fn stuck_modifier_blocks_until_pressed_and_released_again() {
+    let hotkey = ctrl_f8(HotkeyMode::Toggle);
+    let mut matcher = HotkeyMatcher::new();
+    // The release of Shift was lost, e.g. while the screen was locked.
+    run(&hotkey, &mut matcher, &[press_mod(Modifier::ShiftLeft)]);
+    assert!(matcher.shift());
+
+    let blocked = [
+        press_mod(Modifier::ControlLeft),
+        press(Key::F8),
+        release(Key::F8),
+    ];
+    assert!(run(&hotkey, &mut matcher, &blocked).is_empty());
+
+    let recovered = [
+        press_mod(Modifier::ShiftLeft),
+        release_mod(Modifier::ShiftLeft),
+        press(Key::F8),
+    ];
+    assert_eq!(run(&hotkey, &mut matcher, &recovered), [Decision::Toggle]);
+}
+
+#[test]
+fn stuck_key_blocks_until_released() {
+    let hotkey = ctrl_f8(HotkeyMode::Toggle);
+    let mut matcher = HotkeyMatcher::new();
+    // F8 was pressed but its release was lost, so the next press looks like autorepeat.
+    run(&hotkey, &mut matcher, &[press(Key::F8)]);
+    let events = [press_mod(Modifier::ControlLeft), press(Key::F8)];
+    assert!(run(&hotkey, &mut matcher, &events).is_empty());
+
+    let events = [release(Key::F8), press(Key::F8)];
+    assert_eq!(run(&hotkey, &mut matcher, &events), [Decision::Toggle]);
+}
+
+#[test]
+fn reset_forgets_stuck_keys() {
+    let hotkey = ctrl_f8(HotkeyMode::Toggle);
+    let mut matcher = HotkeyMatcher::new();
+    run(
+        &hotkey,
+        &mut matcher,
+        &[press_mod(Modifier::ShiftLeft), press(Key::F8)],
+    );
+    assert_eq!(matcher.reset(), None);
+    assert!(!matcher.shift());
+
+    let events = [press_mod(Modifier::ControlLeft), press(Key::F8)];
+    assert_eq!(run(&hotkey, &mut matcher, &events), [Decision::Toggle]);
+}
+
+#[test]
+fn hold_presses_and_releases_on_key() {
+    let events = [
+        press_mod(Modifier::ControlLeft),
+        press(Key::F8),
+        press(Key::F8),
+        press(Key::F8),
+        release(Key::F8),
+        release_mod(Modifier::ControlLeft),
+    ];
+    assert_eq!(
+        decisions(&ctrl_f8(HotkeyMode::Hold), &events),
+        [Decision::Press, Decision::Release]
+    );
+}
+
+#[test]
+fn hold_releases_when_the_modifier_goes_first() {
+    let events = [
+        press_mod(Modifier::ControlLeft),
+        press(Key::F8),
+        release_mod(Modifier::ControlLeft),
+        press_mod(Modifier::ControlLeft),
+        release(Key::F8),
+    ];
+    assert_eq!(
+        decisions(&ctrl_f8(HotkeyMode::Hold), &events),
+        [Decision::Press, Decision::Release]
+    );
+}
+
+#[test]
+fn hold_survives_releasing_one_side_of_a_modifier() {
+    let events = [
+        press_mod(Modifier::ControlLeft),
+        press_mod(Modifier::ControlRight),
+        press(Key::F8),
+        release_mod(Modifier::ControlLeft),
+        release(Key::F8),
+    ];
+    assert_eq!(
+        decisions(&ctrl_f8(HotkeyMode::Hold), &events),
+        [Decision::Press, Decision::Release]
+    );
+}
+
+#[test]
+fn hold_ignores_extra_modifiers_once_held() {
+    let events = [
+        press_mod(Modifier::ControlLeft),
+        press(Key::F8),
+        press_mod(Modifier::ShiftLeft),
+        release_mod(Modifier::ShiftLeft),
+    ];
+    // Only letting go of the combo itself ends the hold.
+    assert_eq!(
+        decisions(&ctrl_f8(HotkeyMode::Hold), &events),
+        [Decision::Press]
+    );
+}
+
+#[test]
+fn reset_releases_a_held_hotkey() {
+    let hotkey = ctrl_f8(HotkeyMode::Hold);
+    let mut matcher = HotkeyMatcher::new();
+    let events = [press_mod(Modifier::ControlLeft), press(Key::F8)];
+    assert_eq!(run(&hotkey, &mut matcher, &events), [Decision::Press]);
+    assert_eq!(matcher.reset(), Some(Decision::Release));
+    assert_eq!(matcher.reset(), None);
+}
+
+#[test]
+fn every_single_modifier_combination_matches_only_itself() {
+    let sides = [
+        (Modifier::ControlLeft, 0),
+        (Modifier::AltLeft, 1),
+        (Modifier::ShiftLeft, 2),
+        (Modifier::MetaLeft, 3),
+    ];
+    for wanted in 0..16u8 {
+        let hotkey = Hotkey {
+            ctrl: wanted & 1 != 0,
+            alt: wanted & 2 != 0,
+            shift: wanted & 4 != 0,
+            meta: wanted & 8 != 0,
+            key: Some(Key::F9),
+            ..Hotkey::default_toggle()
+        };
+        for held in 0..16u8 {
+            let mut events: Vec<KeyEvent> = sides
+                .iter()
+                .filter(|(_, bit)| held & (1 << bit) != 0)
+                .map(|(m, _)| press_mod(*m))
+                .collect();
+            events.push(press(Key::F9));
+            let fired = !decisions(&hotkey, &events).is_empty();
+            assert_eq!(fired, held == wanted, "hotkey {hotkey}, held {held:04b}");
+        }
+    }
+}
+
+#[test]
+fn side_specific_modifier_ignores_the_other_side() {
+    let hotkey: Hotkey = "RightCtrl+F8".parse().unwrap();
+    assert_eq!(hotkey.ctrl_side, Side::Right);
+    assert_eq!(hotkey.to_string(), "RightCtrl+F8");
+
+    let left = [press_mod(Modifier::ControlLeft), press(Key::F8)];
+    assert!(decisions(&hotkey, &left).is_empty());
+    let right = [press_mod(Modifier::ControlRight), press(Key::F8)];
+    assert_eq!(decisions(&hotkey, &right), [Decision::Toggle]);
+    let both = [
+        press_mod(Modifier::ControlLeft),
+        press_mod(Modifier::ControlRight),
+        press(Key::F8),
+    ];
+    assert!(decisions(&hotkey, &both).is_empty());
+}
+
+#[test]
+fn altgr_is_not_alt() {
+    let alt: Hotkey = "Alt+F8".parse().unwrap();
+    let altgr: Hotkey = "AltGr+F8".parse().unwrap();
+    assert!(altgr.altgr && !altgr.alt);
+
+    let with_altgr = [press_mod(Modifier::AltRight), press(Key::F8)];
+    assert!(decisions(&alt, &with_altgr).is_empty());
+    assert_eq!(decisions(&altgr, &with_altgr), [Decision::Toggle]);
+
+    let with_alt = [press_mod(Modifier::AltLeft), press(Key::F8)];
+    assert_eq!(decisions(&alt, &with_alt), [Decision::Toggle]);
+    assert!(decisions(&altgr, &with_alt).is_empty());
+}
+
+#[test]
+fn extra_modifiers_are_allowed_only_when_asked_for() {
+    let events = [
+        press_mod(Modifier::ControlLeft),
+        press_mod(Modifier::ShiftRight),
+        press(Key::F8),
+    ];
+    assert!(decisions(&ctrl_f8(HotkeyMode::Toggle), &events).is_empty());
+
+    let relaxed = Hotkey {
+        extra_modifiers: true,
+        ..ctrl_f8(HotkeyMode::Toggle)
+    };
+    assert_eq!(decisions(&relaxed, &events), [Decision::Toggle]);
+    // The hotkey's own modifiers are still required.
+    let without_ctrl = [press_mod(Modifier::ShiftRight), press(Key::F8)];
+    assert!(decisions(&relaxed, &without_ctrl).is_empty());
+}
+
+#[test]
+fn hold_with_extra_modifiers_survives_releasing_an_extra_one() {
+    let hotkey = Hotkey {
+        extra_modifiers: true,
+        ..ctrl_f8(HotkeyMode::Hold)
+    };
+    let events = [
+        press_mod(Modifier::ControlLeft),
+        press_mod(Modifier::ShiftLeft),
+        press(Key::F8),
+        release_mod(Modifier::ShiftLeft),
+        release_mod(Modifier::ControlLeft),
+    ];
+    assert_eq!(
+        decisions(&hotkey, &events),
+        [Decision::Press, Decision::Release]
+    );
+}
+
+/// Feeds `events`, each after the given number of milliseconds, ticking in between like
+/// a listener would.
+fn timed(hotkey: &Hotkey, events: &[(u64, KeyEvent)]) -> Vec<Decision> {
+    let mut matcher = HotkeyMatcher::new();
+    let mut now = Instant::now();
+    let mut out = Vec::new();
+    for (after, event) in events {
+        let next = now + Duration::from_millis(*after);
+        if let Some(deadline) = matcher.next_deadline().filter(|d| *d <= next) {
+            out.extend(matcher.tick(hotkey, deadline));
+        }
+        now = next;
+        out.extend(matcher.handle_at(hotkey, event, now));
+    }
+    out
+}
+
+fn with_trigger(hotkey: &str, trigger: Trigger) -> Hotkey {
+    Hotkey {
+        trigger,
+        ..hotkey.parse().unwrap()
+    }
+}
+
+#[test]
+fn modifiers_alone_parse_and_display() {
+    let hotkey: Hotkey = "RightCtrl".parse().unwrap();
+    assert_eq!(hotkey.key, None);
+    assert!(hotkey.ctrl);
+    assert_eq!(hotkey.ctrl_side, Side::Right);
+    assert_eq!(hotkey.to_string(), "RightCtrl");
+}
+
+#[test]
+fn tap_of_a_modifier_alone_toggles() {
+    let hotkey = with_trigger("RightCtrl", Trigger::Tap);
+    let tap = [
+        (0, press_mod(Modifier::ControlRight)),
+        (100, release_mod(Modifier::ControlRight)),
+    ];
+    assert_eq!(timed(&hotkey, &tap), [Decision::Toggle]);
+
+    let too_slow = [
+        (0, press_mod(Modifier::ControlRight)),
+        (400, release_mod(Modifier::ControlRight)),
+    ];
+    assert!(timed(&hotkey, &too_slow).is_empty());
+
+    let wrong_side = [
+        (0, press_mod(Modifier::ControlLeft)),
+        (100, release_mod(Modifier::ControlLeft)),
+    ];
+    assert!(timed(&hotkey, &wrong_side).is_empty());
+}
+
+#[test]
+fn a_modifier_used_in_a_shortcut_is_no_tap() {
+    let hotkey = with_trigger("RightCtrl", Trigger::Tap);
+    let shortcut = [
+        (0, press_mod(Modifier::ControlRight)),
+        (20, press(Key::Char('C'))),
+        (20, release(Key::Char('C'))),
+        (20, release_mod(Modifier::ControlRight)),
+    ];
+    assert!(timed(&hotkey, &shortcut).is_empty());
+
+    let with_shift = [
+        (0, press_mod(Modifier::ControlRight)),
+        (20, press_mod(Modifier::ShiftLeft)),
+        (20, release_mod(Modifier::ShiftLeft)),
+        (20, release_mod(Modifier::ControlRight)),
+    ];
+    assert!(timed(&hotkey, &with_shift).is_empty());
+}
+
+#[test]
+fn double_tap_needs_two_quick_taps() {
+    let hotkey = with_trigger("Shift", Trigger::DoubleTap);
+    let tap = |gap| {
+        [
+            (gap, press_mod(Modifier::ShiftLeft)),
+            (50, release_mod(Modifier::ShiftLeft)),
+        ]
+    };
+    let quick: Vec<_> = tap(0).into_iter().chain(tap(100)).collect();
+    assert_eq!(timed(&hotkey, &quick), [Decision::Toggle]);
+
+    let slow: Vec<_> = tap(0).into_iter().chain(tap(500)).collect();
+    assert!(timed(&hotkey, &slow).is_empty());
+
+    // The third tap starts a new pair.
+    let triple: Vec<_> = tap(0).into_iter().chain(tap(100)).chain(tap(100)).collect();
+    assert_eq!(timed(&hotkey, &triple), [Decision::Toggle]);
+
+    let typed_between: Vec<_> = tap(0)
+        .into_iter()
+        .chain([(20, press(Key::Char('A'))), (20, release(Key::Char('A')))])
+        .chain(tap(20))
+        .collect();
+    assert!(timed(&hotkey, &typed_between).is_empty());
+}
+
+#[test]
+fn clicks_do_not_interrupt_taps() {
+    let click = || {
+        [
+            (10, press(Key::Mouse(MouseButton::Left))),
+            (10, release(Key::Mouse(MouseButton::Left))),
+        ]
+    };
+    let double_tap = with_trigger("Shift", Trigger::DoubleTap);
+    let events: Vec<_> = [
+        (0, press_mod(Modifier::ShiftLeft)),
+        (50, release_mod(Modifier::ShiftLeft)),
+    ]
+    .into_iter()
+    .chain(click())
+    .chain([(20, press_mod(Modifier::ShiftLeft))])
+    .chain(click())
+    .chain([(20, release_mod(Modifier::ShiftLeft))])
+    .collect();
+    assert_eq!(timed(&double_tap, &events), [Decision::Toggle]);
+
+    let long_press = with_trigger("RightCtrl", Trigger::LongPress);
+    let events: Vec<_> = [(0, press_mod(Modifier::ControlRight))]
+        .into_iter()
+        .chain(click())
+        .chain([(600, release_mod(Modifier::ControlRight))])
+        .collect();
+    assert_eq!(timed(&long_press, &events), [Decision::Toggle]);
+}
+
+#[test]
+fn a_mouse_button_in_the_hotkey_still_counts() {
+    let hotkey: Hotkey = "Ctrl+MouseBack".parse().unwrap();
+    let events = [
+        press_mod(Modifier::ControlLeft),
+        press(Key::Mouse(MouseButton::Left)),
+        release(Key::Mouse(MouseButton::Left)),
+        press(Key::Mouse(MouseButton::Back)),
+    ];
+    assert_eq!(decisions(&hotkey, &events), [Decision::Toggle]);
+}
+
+#[test]
+fn long_press_fires_while_still_held() {
+    let hotkey = Hotkey {
+        ctrl: false,
+        key: Some(Key::raw(58)), // Caps Lock
+        trigger: Trigger::LongPress,
+        ..Hotkey::default_toggle()
+    };
+    let mut matcher = HotkeyMatcher::new();
+    let start = Instant::now();
+    let caps = press(Key::raw(58));
+    assert_eq!(matcher.handle_at(&hotkey, &caps, start), None);
+
+    let deadline = matcher.next_deadline().unwrap();
+    assert_eq!(deadline, start + Duration::from_millis(500));
+    assert_eq!(
+        matcher.tick(&hotkey, deadline - Duration::from_millis(1)),
+        None
+    );
+    assert_eq!(matcher.tick(&hotkey, deadline), Some(Decision::Toggle));
+    assert_eq!(matcher.next_deadline(), None);
+    let later = deadline + Duration::from_millis(100);
+    assert_eq!(
+        matcher.handle_at(&hotkey, &release(Key::raw(58)), later),
+        None
+    );
+}
+
+#[test]
+fn long_press_is_cancelled_by_an_early_release_or_another_key() {
+    let hotkey = with_trigger("Ctrl+F8", Trigger::LongPress);
+    let short = [
+        (0, press_mod(Modifier::ControlLeft)),
+        (0, press(Key::F8)),
+        (200, release(Key::F8)),
+    ];
+    assert!(timed(&hotkey, &short).is_empty());
+
+    let interrupted = [
+        (0, press_mod(Modifier::ControlLeft)),
+        (0, press(Key::F8)),
+        (100, press(Key::F9)),
+        (600, release(Key::F8)),
+    ];
+    assert!(timed(&hotkey, &interrupted).is_empty());
+}
+
+#[test]
+fn long_press_released_before_a_tick_still_toggles() {
+    let hotkey = with_trigger("Ctrl+F8", Trigger::LongPress);
+    let mut matcher = HotkeyMatcher::new();
+    let start = Instant::now();
+    for event in [press_mod(Modifier::ControlLeft), press(Key::F8)] {
+        matcher.handle_at(&hotkey, &event, start);
+    }
+    let late = start + Duration::from_millis(700);
+    assert_eq!(
+        matcher.handle_at(&hotkey, &release(Key::F8), late),
+        Some(Decision::Toggle)
+    );
+}
+
+#[test]
+fn long_press_in_hold_mode_clicks_until_released() {
+    let hotkey = Hotkey {
+        mode: HotkeyMode::Hold,
+        ..with_trigger("Ctrl+F8", Trigger::LongPress)
+    };
+    let events = [
+        (0, press_mod(Modifier::ControlLeft)),
+        (0, press(Key::F8)),
+        (800, release(Key::F8)),
+    ];
+    assert_eq!(
+        timed(&hotkey, &events),
+        [Decision::Press, Decision::Release]
+    );
+}
+
+#[test]
+fn modifiers_alone_can_be_held() {
+    let hotkey = Hotkey {
+        mode: HotkeyMode::Hold,
+        ..with_trigger("Ctrl+Alt", Trigger::Press)
+    };
+    let events = [
+        (0, press_mod(Modifier::ControlLeft)),
+        (10, press_mod(Modifier::AltLeft)),
+        (500, release_mod(Modifier::ControlLeft)),
+        (10, release_mod(Modifier::AltLeft)),
+    ];
+    assert_eq!(
+        timed(&hotkey, &events),
+        [Decision::Press, Decision::Release]
+    );
+}
+
+/// Ctrl+K, then `key`, with `gap` ms between the two steps.
+fn leader_then(key: char, gap: u64) -> [(u64, KeyEvent); 6] {
+    [
+        (0, press_mod(Modifier::ControlLeft)),
+        (10, press(Key::Char('K'))),
+        (10, release(Key::Char('K'))),
+        (10, release_mod(Modifier::ControlLeft)),
+        (gap, press(Key::Char(key))),
+        (10, release(Key::Char(key))),
+    ]
+}
+
+#[test]
+fn sequences_parse_and_display() {
+    let hotkey: Hotkey = "ctrl+k,c".parse().unwrap();
+    assert_eq!(hotkey.key, Some(Key::Char('K')));
+    assert_eq!(hotkey.then.len(), 1);
+    assert_eq!(hotkey.then[0].key, Some(Key::Char('C')));
+    assert!(!hotkey.then[0].ctrl);
+    assert_eq!(hotkey.to_string(), "Ctrl+K, C");
+    assert_eq!(hotkey.to_string().parse::<Hotkey>().unwrap(), hotkey);
+}
+
+#[test]
+fn commas_and_pluses_can_be_keys() {
+    let ctrl_comma: Hotkey = "ctrl+,".parse().unwrap();
+    assert_eq!(ctrl_comma.key, Some(Key::Char(',')));
+    assert!(ctrl_comma.ctrl && ctrl_comma.then.is_empty());
+
+    let sequence: Hotkey = "Ctrl+K, ,".parse().unwrap();
+    assert_eq!(sequence.then[0].key, Some(Key::Char(',')));
+
+    for text in [
+        ",",
+        "Ctrl+,",
+        "Ctrl+K, ,",
+        ",, C",
+        "+",
+        "Ctrl++",
+        "Ctrl++, C",
+    ] {
+        let hotkey: Hotkey = text.parse().unwrap();
+        assert_eq!(hotkey.to_string(), text);
+        assert_eq!(
+            hotkey.to_string().parse::<Hotkey>().unwrap(),
+            hotkey,
+            "{text}"
+        );
+    }
+    assert!("Ctrl+".parse::<Hotkey>().is_err());
+}
+
+#[test]
+fn sequence_fires_on_its_last_step() {
+    let hotkey: Hotkey = "Ctrl+K, C".parse().unwrap();
+    assert_eq!(timed(&hotkey, &leader_then('C', 100)), [Decision::Toggle]);
+    assert!(timed(&hotkey, &leader_then('D', 100)).is_empty());
+    // The leader alone does nothing.
+    assert!(timed(&hotkey, &leader_then('C', 100)[..4]).is_empty());
+}
+
+#[test]
+fn sequence_times_out_between_steps() {
+    let hotkey: Hotkey = "Ctrl+K, C".parse().unwrap();
+    assert!(timed(&hotkey, &leader_then('C', 1500)).is_empty());
+
+    let mut matcher = HotkeyMatcher::new();
+    let start = Instant::now();
+    matcher.handle_at(&hotkey, &press_mod(Modifier::ControlLeft), start);
+    matcher.handle_at(&hotkey, &press(Key::Char('K')), start);
+    assert_eq!(matcher.pending_steps(), 1);
+    let deadline = matcher.next_deadline().unwrap();
+    assert_eq!(deadline, start + hotkey.timing.sequence());
+    matcher.tick(&hotkey, deadline);
+    assert_eq!(matcher.pending_steps(), 0);
+    assert_eq!(matcher.next_deadline(), None);
+}
+
+#[test]
+fn sequence_steps_match_modifiers_exactly() {
+    let hotkey: Hotkey = "Ctrl+K, C".parse().unwrap();
+    let ctrl_held = [
+        (0, press_mod(Modifier::ControlLeft)),
+        (10, press(Key::Char('K'))),
+        (10, release(Key::Char('K'))),
+        (10, press(Key::Char('C'))),
+    ];
+    assert!(timed(&hotkey, &ctrl_held).is_empty());
+
+    let both_ctrl: Hotkey = "Ctrl+K, Ctrl+C".parse().unwrap();
+    assert_eq!(timed(&both_ctrl, &ctrl_held), [Decision::Toggle]);
+}
+
+#[test]
+fn repeating_the_leader_starts_the_sequence_over() {
+    let hotkey: Hotkey = "Ctrl+K, C".parse().unwrap();
+    let mut events = leader_then('C', 100)[..4].to_vec();
+    events.extend(leader_then('C', 100));
+    assert_eq!(timed(&hotkey, &events), [Decision::Toggle]);
+}
+
+#[test]
+fn only_the_hotkey_key_is_consumed() {
+    let hotkey = ctrl_f8(HotkeyMode::Toggle);
+    let mut matcher = HotkeyMatcher::new();
+    let mut feed = |event: KeyEvent| {
+        let before = matcher.consumes(&event);
+        matcher.handle(&hotkey, &event);
+        before || matcher.consumes(&event)
+    };
+    assert!(!feed(press_mod(Modifier::ControlLeft)));
+    assert!(feed(press(Key::F8)));
+    assert!(feed(press(Key::F8)), "autorepeat");
+    assert!(feed(release(Key::F8)));
+    assert!(!feed(press(Key::F9)));
+    assert!(!feed(release(Key::F9)));
+    assert!(!feed(release_mod(Modifier::ControlLeft)));
+    assert!(!feed(press(Key::F8)), "F8 without Ctrl");
+    assert!(!feed(release(Key::F8)));
+}
+
+#[test]
+fn sequence_steps_are_consumed_but_wrong_keys_are_not() {
+    let hotkey: Hotkey = "Ctrl+K, C".parse().unwrap();
+    let mut matcher = HotkeyMatcher::new();
+    matcher.handle(&hotkey, &press_mod(Modifier::ControlLeft));
+    matcher.handle(&hotkey, &press(Key::Char('K')));
+    assert!(matcher.consumes(&release(Key::Char('K'))));
+    matcher.handle(&hotkey, &release(Key::Char('K')));
+    matcher.handle(&hotkey, &release_mod(Modifier::ControlLeft));
+    matcher.handle(&hotkey, &press(Key::Char('D')));
+    assert!(!matcher.consumes(&release(Key::Char('D'))));
+}
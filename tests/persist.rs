@@ -0,0 +1,148 @@
+mod common;
+
+use std::fs;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use autoclicker::persist::{Persister, backup_path, save_settings_atomic, write_atomic};
+use autoclicker::settings::{Settings, read_settings};
+use common::TempDir;
+
+fn with_limit(limit: u64) -> Settings {
+    Settings {
+        click_limit: Some(limit),
+        ..Settings::default()
+    }
+}
+
+#[test]
+fn atomic_save_replaces_the_file_and_leaves_no_temp_file() {
+    let dir = TempDir::new("persist");
+    let path = dir.join("settings.toml");
+    save_settings_atomic(&path, &with_limit(1), 3).unwrap();
+    save_settings_atomic(&path, &with_limit(2), 3).unwrap();
+
+    assert_eq!(read_settings(&path).unwrap(), with_limit(2));
+    assert!(!dir.join("settings.toml.tmp").exists());
+}
+
+#[test]
+fn failed_write_leaves_the_original_intact() {
+    let dir = TempDir::new("persist");
+    let path = dir.join("settings.toml");
+    save_settings_atomic(&path, &with_limit(1), 3).unwrap();
+    let before = fs::read_to_string(&path).unwrap();
+
+    // A directory where the temp file should go makes creating it fail.
+    fs::create_dir(dir.join("settings.toml.tmp")).unwrap();
+    assert!(save_settings_atomic(&path, &with_limit(2), 3).is_err());
+
+    assert_eq!(fs::read_to_string(&path).unwrap(), before);
+    assert!(!backup_path(&path, 1).exists());
+}
+
+#[test]
+fn backups_rotate_and_the_oldest_is_dropped() {
+    let dir = TempDir::new("persist");
+    let path = dir.join("settings.toml");
+    for version in 1..=5 {
+        write_atomic(&path, &format!("version {version}"), 3).unwrap();
+    }
+
+    assert_eq!(fs::read_to_string(&path).unwrap(), "version 5");
+    for (index, version) in [(1, 4), (2, 3), (3, 2)] {
+        assert_eq!(
+            fs::read_to_string(backup_path(&path, index)).unwrap(),
+            format!("version {version}")
+        );
+    }
+    assert!(!backup_path(&path, 4).exists());
+}
+
+#[test]
+fn burst_of_saves_is_written_once() {
+    let dir = TempDir::new("persist");
+    let path = dir.join("settings.toml");
+    let writes = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&writes);
+    let persister = Persister::spawn(path.clone(), move || {
+        counter.fetch_add(1, Ordering::SeqCst);
+    });
+
+    for limit in 1..=10 {
+        persister.save(with_limit(limit));
+    }
+    // Well past the quiet period, well before anything else could be written.
+    thread::sleep(Duration::from_millis(1200));
+
+    assert_eq!(writes.load(Ordering::SeqCst), 1);
+    assert_eq!(read_settings(&path).unwrap(), with_limit(10));
+    assert!(persister.last_error().is_none());
+}
+
+#[test]
+fn flush_writes_pending_changes_right_away() {
+    let dir = TempDir::new("persist");
+    let path = dir.join("settings.toml");
+    let persister = Persister::spawn(path.clone(), || {});
+
+    persister.save(with_limit(7));
+    persister.flush();
+
+    assert_eq!(read_settings(&path).unwrap(), with_limit(7));
+}
+
+#[test]
+fn own_write_probe_recognizes_only_our_writes() {
+    let dir = TempDir::new("persist");
+    let path = dir.join("settings.toml");
+    let persister = Persister::spawn(path.clone(), || {});
+    let probe = persister.own_write_probe();
+    assert!(!probe(""));
+
+    persister.save(with_limit(3));
+    persister.flush();
+    let written = fs::read_to_string(&path).unwrap();
+    assert!(probe(&written));
+
+    let edited = written.replace("click_limit = 3", "click_limit = 4");
+    assert_ne!(edited, written);
+    assert!(!probe(&edited));
+
+    // A save that never reached the disk is not ours to ignore.
+    fs::create_dir(dir.join("settings.toml.tmp")).unwrap();
+    persister.save(with_limit(4));
+    persister.flush();
+    assert!(persister.last_error().is_some());
+    assert!(probe(&written));
+    assert!(!probe(&edited));
+}
+
+#[cfg(unix)]
+#[test]
+fn saving_through_a_symlink_keeps_the_link() {
+    let dir = TempDir::new("persist");
+    fs::create_dir(dir.join("dotfiles")).unwrap();
+    let real = dir.join("dotfiles/settings.toml");
+    let link = dir.join("settings.toml");
+    fs::write(&real, "version 1").unwrap();
+    std::os::unix::fs::symlink(&real, &link).unwrap();
+
+    write_atomic(&link, "version 2", 3).unwrap();
+
+    assert!(
+        fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink()
+    );
+    assert_eq!(fs::read_to_string(&real).unwrap(), "version 2");
+    assert_eq!(
+        fs::read_to_string(backup_path(&real, 1)).unwrap(),
+        "version 1"
+    );
+    assert!(!backup_path(&link, 1).exists());
+    assert!(!dir.join("settings.toml.tmp").exists());
+}
@@ -0,0 +1,121 @@
+use autoclicker::hotkey::{Decision, HotkeyMatcher, KeyEvent, KeyInput, Modifier};
+use autoclicker::settings::{Action, Hotkey, Key, Settings};
+use autoclicker::validate::has_errors;
+
+fn volume_up() -> Key {
+    Key::raw(115)
+}
+
+#[test]
+fn known_codes_get_readable_names() {
+    assert_eq!(volume_up().to_str(), "VolumeUp");
+    assert_eq!(Key::raw(194).to_str(), "F24");
+}
+
+#[test]
+fn toml_round_trip_keeps_code_and_name() {
+    let settings = Settings {
+        hotkey: Hotkey {
+            key: Some(volume_up()),
+            ..Hotkey::default_toggle()
+        },
+        action: Action::Keyboard(Key::raw(183)),
+        ..Settings::default()
+    };
+    let text = toml::to_string_pretty(&settings).unwrap();
+    assert!(text.contains("code = 115"), "{text}");
+    assert!(text.contains("name = \"VolumeUp\""), "{text}");
+
+    let back: Settings = toml::from_str(&text).unwrap();
+    assert_eq!(back, settings);
+    assert_eq!(toml::to_string_pretty(&back).unwrap(), text);
+}
+
+#[test]
+fn parses_names_and_codes() {
+    assert_eq!("volumeup".parse::<Key>().unwrap(), volume_up());
+    assert_eq!("raw:115".parse::<Key>().unwrap(), volume_up());
+    assert_eq!("Raw0x73".parse::<Key>().unwrap(), volume_up());
+    assert!("raw:banana".parse::<Key>().is_err());
+
+    let hotkey: Hotkey = "Ctrl+F13".parse().unwrap();
+    assert_eq!(hotkey.key, Some(Key::raw(183)));
+    assert_eq!(hotkey.to_string().parse::<Hotkey>().unwrap(), hotkey);
+}
+
+#[test]
+fn unnamed_codes_round_trip_through_display() {
+    let key = Key::raw(0x2f0);
+    assert_eq!(key.to_str().parse::<Key>().unwrap(), key);
+}
+
+#[test]
+fn matches_by_code_whatever_the_name() {
+    let hotkey = Hotkey {
+        ctrl: false,
+        key: Some(Key::Raw {
+            code: 115,
+            name: "Louder".to_string(),
+        }),
+        ..Hotkey::default_toggle()
+    };
+    let mut matcher = HotkeyMatcher::new();
+    let press = KeyEvent::Press(KeyInput::Key(volume_up()));
+    assert_eq!(matcher.handle(&hotkey, &press), Some(Decision::Toggle));
+}
+
+#[test]
+fn rejects_codes_outside_the_kernel_range() {
+    let settings = Settings {
+        action: Action::Keyboard(Key::raw(0x300)),
+        ..Settings::default()
+    };
+    assert!(has_errors(&settings.validate()));
+    assert!(!has_errors(
+        &Settings {
+            action: Action::Keyboard(volume_up()),
+            ..Settings::default()
+        }
+        .validate()
+    ));
+}
+
+#[test]
+fn codes_of_named_keys_match_like_the_named_key() {
+    for hotkey in ["ctrl+raw:30", "ctrl+KEY_A"] {
+        let hotkey: Hotkey = hotkey.parse().unwrap();
+        assert_eq!(hotkey.key, Some(Key::Char('A')));
+    }
+
+    // A raw key written into the file still matches the letter the listeners report.
+    let written = Hotkey {
+        key: Some(Key::raw(30)),
+        ..Hotkey::default_toggle()
+    };
+    assert_eq!(Key::raw(30).normalized(), Key::Char('A'));
+    let mut matcher = HotkeyMatcher::new();
+    for (event, decision) in [
+        (
+            KeyEvent::Press(KeyInput::Modifier(Modifier::ControlLeft)),
+            None,
+        ),
+        (
+            KeyEvent::Press(KeyInput::Key(Key::Char('A'))),
+            Some(Decision::Toggle),
+        ),
+    ] {
+        assert_eq!(matcher.handle(&written, &event), decision);
+    }
+}
+
+#[test]
+fn codes_of_modifiers_are_not_keys() {
+    let settings = Settings {
+        hotkey: Hotkey {
+            key: Some(Key::raw(97)),
+            ..Hotkey::default_toggle()
+        },
+        ..Settings::default()
+    };
+    assert!(has_errors(&settings.validate()));
+}
@@ -0,0 +1,76 @@
+use std::time::{Duration, Instant};
+
+use autoclicker::scheduler::{Scheduler, measure};
+use autoclicker::settings::TimingMode;
+
+const MS: Duration = Duration::from_millis(1);
+
+#[test]
+fn fixed_rate_keeps_its_schedule_without_drift() {
+    let start = Instant::now();
+    let mut scheduler = Scheduler::new(TimingMode::FixedRate, 10 * MS, start);
+    for tick in 1..=1000u32 {
+        let deadline = scheduler.deadline();
+        assert_eq!(deadline, start + 10 * MS * tick);
+        // Each action finishes a little late; the schedule must not shift with it.
+        scheduler.advance(deadline + 3 * MS);
+    }
+    assert_eq!(scheduler.deadline(), start + 10 * MS * 1001);
+}
+
+#[test]
+fn fixed_rate_catches_up_short_hiccups() {
+    let start = Instant::now();
+    let mut scheduler = Scheduler::new(TimingMode::FixedRate, 10 * MS, start);
+    // The first action took two and a half intervals.
+    scheduler.advance(start + 35 * MS);
+    assert_eq!(scheduler.deadline(), start + 20 * MS);
+    scheduler.advance(start + 36 * MS);
+    assert_eq!(scheduler.deadline(), start + 30 * MS);
+}
+
+#[test]
+fn fixed_rate_resyncs_after_falling_far_behind() {
+    let start = Instant::now();
+    let mut scheduler = Scheduler::new(TimingMode::FixedRate, 10 * MS, start);
+    let stalled_until = start + 200 * MS;
+    scheduler.advance(stalled_until);
+    // Missed ticks are dropped instead of replayed in a burst.
+    assert_eq!(scheduler.deadline(), stalled_until);
+    scheduler.advance(stalled_until);
+    assert_eq!(scheduler.deadline(), stalled_until + 10 * MS);
+}
+
+#[test]
+fn fixed_delay_waits_from_completion() {
+    let start = Instant::now();
+    let mut scheduler = Scheduler::new(TimingMode::FixedDelay, 10 * MS, start);
+    assert_eq!(scheduler.deadline(), start + 10 * MS);
+    let finished = start + 14 * MS;
+    scheduler.advance(finished);
+    assert_eq!(scheduler.deadline(), finished + 10 * MS);
+}
+
+#[test]
+fn changed_interval_applies_to_the_pending_deadline() {
+    let start = Instant::now();
+    let mut scheduler = Scheduler::new(TimingMode::FixedRate, 10 * MS, start);
+    scheduler.advance(start + 10 * MS);
+    scheduler.configure(TimingMode::FixedRate, 50 * MS);
+    assert_eq!(scheduler.deadline(), start + 60 * MS);
+}
+
+#[test]
+fn measured_rate_is_close_to_the_target() {
+    // Loose bounds: test machines are busy, but a 200/s schedule should not be far off.
+    for (mode, work) in [
+        (TimingMode::FixedRate, Duration::ZERO),
+        (TimingMode::FixedDelay, MS),
+    ] {
+        let result = measure(mode, 5 * MS, Duration::from_millis(500), work);
+        assert!(result.ticks > 10, "{result}");
+        let ratio = result.achieved_rate / result.target_rate;
+        assert!((0.7..1.1).contains(&ratio), "{result}");
+        assert!(result.p99_lateness <= result.max_lateness, "{result}");
+    }
+}
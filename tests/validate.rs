@@ -0,0 +1,227 @@
+use autoclicker::interval::{Interval, IntervalUnit};
+use autoclicker::settings::{Action, Hotkey, InputListener, Key, MouseButton, Settings, Side};
+use autoclicker::validate::{Diagnostic, Severity, has_errors};
+
+fn with_hotkey(hotkey: &str) -> Settings {
+    Settings {
+        hotkey: hotkey.parse().unwrap(),
+        ..Settings::default()
+    }
+}
+
+fn with_key(ctrl: bool, key: Key) -> Settings {
+    Settings {
+        hotkey: Hotkey {
+            ctrl,
+            key: Some(key),
+            ..Hotkey::default_toggle()
+        },
+        ..Settings::default()
+    }
+}
+
+fn with_action(action: Action) -> Settings {
+    Settings {
+        action,
+        ..Settings::default()
+    }
+}
+
+fn with_interval(value: f64, unit: IntervalUnit) -> Settings {
+    Settings {
+        interval: Interval::new(value, unit),
+        ..Settings::default()
+    }
+}
+
+/// Asserts that `settings` report a diagnostic at `path` with `severity` whose message
+/// mentions `text`.
+fn assert_reports(settings: &Settings, severity: Severity, path: &str, text: &str) {
+    let diagnostics = settings.validate();
+    assert!(
+        diagnostics
+            .iter()
+            .any(|d| d.severity == severity && d.path == path && d.message.contains(text)),
+        "no {severity} at {path} mentioning {text:?} in {diagnostics:#?}"
+    );
+}
+
+#[test]
+fn defaults_are_clean() {
+    assert_eq!(Settings::default().validate(), []);
+}
+
+#[test]
+fn hotkey_needs_a_key_or_a_modifier() {
+    let settings = Settings {
+        hotkey: Hotkey {
+            ctrl: false,
+            key: None,
+            ..Hotkey::default_toggle()
+        },
+        ..Settings::default()
+    };
+    assert_reports(
+        &settings,
+        Severity::Error,
+        "hotkey",
+        "needs a key or at least one modifier",
+    );
+}
+
+#[test]
+fn hotkey_characters_must_be_detectable() {
+    assert_reports(
+        &with_key(true, Key::Char('#')),
+        Severity::Error,
+        "hotkey.key",
+        "cannot be detected",
+    );
+    assert_reports(
+        &with_key(true, Key::Char('a')),
+        Severity::Warning,
+        "hotkey.key",
+        "not case sensitive",
+    );
+}
+
+#[test]
+fn hotkey_mouse_buttons_must_be_usable() {
+    assert_reports(
+        &with_key(true, Key::Mouse(MouseButton::ScrollUp)),
+        Severity::Error,
+        "hotkey.key",
+        "scrolling",
+    );
+    assert_reports(
+        &with_key(false, Key::Mouse(MouseButton::Left)),
+        Severity::Error,
+        "hotkey",
+        "needs modifiers",
+    );
+    assert!(!has_errors(
+        &with_key(true, Key::Mouse(MouseButton::Left)).validate()
+    ));
+}
+
+#[test]
+fn hotkey_without_modifiers_warns_about_typing() {
+    assert_reports(
+        &with_hotkey("Space"),
+        Severity::Warning,
+        "hotkey",
+        "will fire while typing",
+    );
+    assert_eq!(with_hotkey("F9").validate(), []);
+}
+
+#[test]
+fn modifier_side_without_the_modifier_has_no_effect() {
+    let mut settings = with_hotkey("Alt+F8");
+    settings.hotkey.shift_side = Side::Right;
+    assert_reports(
+        &settings,
+        Severity::Warning,
+        "hotkey.shift_side",
+        "no effect",
+    );
+}
+
+#[test]
+fn action_keys_must_be_typeable() {
+    assert_reports(
+        &with_action(Action::Keyboard(Key::Mouse(MouseButton::Right))),
+        Severity::Error,
+        "action",
+        "mouse buttons are not keys",
+    );
+    assert_reports(
+        &with_action(Action::Keyboard(Key::Char('\t'))),
+        Severity::Error,
+        "action",
+        "not a typeable key",
+    );
+    assert_reports(
+        &with_action(Action::Keyboard(Key::Char('é'))),
+        Severity::Warning,
+        "action",
+        "cannot be shown",
+    );
+    assert_eq!(with_action(Action::Keyboard(Key::Char('Z'))).validate(), []);
+}
+
+#[test]
+fn interval_must_be_usable_and_practical() {
+    assert_reports(
+        &with_interval(0.0, IntervalUnit::Millis),
+        Severity::Error,
+        "interval",
+        "not a usable interval",
+    );
+    assert_reports(
+        &with_interval(f64::NAN, IntervalUnit::Millis),
+        Severity::Error,
+        "interval",
+        "not a usable interval",
+    );
+    assert_reports(
+        &with_interval(10.0, IntervalUnit::Micros),
+        Severity::Warning,
+        "interval",
+        "faster than most systems",
+    );
+    assert_reports(
+        &with_interval(25.0, IntervalUnit::Hours),
+        Severity::Warning,
+        "interval",
+        "longer than a day",
+    );
+    assert_eq!(with_interval(20.0, IntervalUnit::Cps).validate(), []);
+}
+
+#[test]
+fn click_limit_of_zero_is_rejected() {
+    let settings = Settings {
+        click_limit: Some(0),
+        ..Settings::default()
+    };
+    assert_reports(&settings, Severity::Error, "click_limit", "never click");
+}
+
+#[test]
+fn suppressing_needs_evdev() {
+    let settings = Settings {
+        suppress_hotkeys: true,
+        listener: InputListener::Rdev,
+        ..Settings::default()
+    };
+    assert_reports(
+        &settings,
+        Severity::Warning,
+        "suppress_hotkeys",
+        "only the evdev listener",
+    );
+    assert!(!has_errors(&settings.validate()));
+}
+
+#[test]
+fn diagnostics_print_severity_path_and_message() {
+    let settings = Settings {
+        click_limit: Some(0),
+        ..Settings::default()
+    };
+    let diagnostics = settings.validate();
+    assert_eq!(
+        diagnostics,
+        [Diagnostic {
+            severity: Severity::Error,
+            path: "click_limit".to_string(),
+            message: "a limit of 0 would never click; remove it for unlimited clicking".to_string(),
+        }]
+    );
+    assert_eq!(
+        diagnostics[0].to_string(),
+        "error: click_limit: a limit of 0 would never click; remove it for unlimited clicking"
+    );
+    assert!(Severity::Error > Severity::Warning);
+}
@@ -0,0 +1,107 @@
+mod common;
+
+use std::fs;
+use std::sync::{Arc, RwLock, mpsc};
+use std::time::Duration;
+
+use autoclicker::persist::save_settings_atomic;
+use autoclicker::settings::Settings;
+use autoclicker::watch::start_config_watcher;
+use common::TempDir;
+
+const WAIT: Duration = Duration::from_secs(5);
+
+fn with_limit(limit: u64) -> Settings {
+    Settings {
+        click_limit: Some(limit),
+        ..Settings::default()
+    }
+}
+
+#[test]
+fn external_edit_is_applied_and_invalid_one_is_ignored() {
+    let dir = TempDir::new("watch");
+    let path = dir.join("settings.toml");
+    save_settings_atomic(&path, &with_limit(1), 0).unwrap();
+    let settings = Arc::new(RwLock::new(with_limit(1)));
+
+    let (tx, rx) = mpsc::channel();
+    let watcher = start_config_watcher(
+        path.clone(),
+        Arc::clone(&settings),
+        |_| false,
+        move |result| {
+            let _ = tx.send(result.map_err(|e| e.to_string()));
+        },
+    )
+    .unwrap();
+
+    save_settings_atomic(&path, &with_limit(2), 0).unwrap();
+    assert_eq!(rx.recv_timeout(WAIT).unwrap(), Ok(()));
+    assert_eq!(*settings.read().unwrap(), with_limit(2));
+    assert!(watcher.last_error().is_none());
+
+    let broken = fs::read_to_string(&path)
+        .unwrap()
+        .replace("click_limit = 2", "click_limit = 0");
+    fs::write(&path, broken).unwrap();
+    assert!(rx.recv_timeout(WAIT).unwrap().is_err());
+    assert_eq!(*settings.read().unwrap(), with_limit(2));
+    assert!(watcher.last_error().is_some());
+
+    // Fixing the file clears the error again.
+    save_settings_atomic(&path, &with_limit(3), 0).unwrap();
+    assert_eq!(rx.recv_timeout(WAIT).unwrap(), Ok(()));
+    assert_eq!(*settings.read().unwrap(), with_limit(3));
+    assert!(watcher.last_error().is_none());
+}
+
+#[test]
+fn own_writes_are_not_reloaded() {
+    let dir = TempDir::new("watch");
+    let path = dir.join("settings.toml");
+    save_settings_atomic(&path, &with_limit(1), 0).unwrap();
+    let settings = Arc::new(RwLock::new(with_limit(1)));
+
+    let (tx, rx) = mpsc::channel();
+    let _watcher = start_config_watcher(
+        path.clone(),
+        Arc::clone(&settings),
+        |_| true,
+        move |result| {
+            let _ = tx.send(result.is_ok());
+        },
+    )
+    .unwrap();
+
+    save_settings_atomic(&path, &with_limit(2), 0).unwrap();
+    assert!(rx.recv_timeout(Duration::from_millis(800)).is_err());
+    assert_eq!(*settings.read().unwrap(), with_limit(1));
+}
+
+#[cfg(unix)]
+#[test]
+fn edits_to_the_target_of_a_symlink_are_applied() {
+    let dir = TempDir::new("watch");
+    fs::create_dir(dir.join("dotfiles")).unwrap();
+    let real = dir.join("dotfiles/settings.toml");
+    let link = dir.join("settings.toml");
+    save_settings_atomic(&real, &with_limit(1), 0).unwrap();
+    std::os::unix::fs::symlink(&real, &link).unwrap();
+    let settings = Arc::new(RwLock::new(with_limit(1)));
+
+    let (tx, rx) = mpsc::channel();
+    let _watcher = start_config_watcher(
+        link,
+        Arc::clone(&settings),
+        |_| false,
+        move |result| {
+            let _ = tx.send(result.is_ok());
+        },
+    )
+    .unwrap();
+
+    save_settings_atomic(&real, &with_limit(2), 0).unwrap();
+    assert!(rx.recv_timeout(WAIT).unwrap());
+    assert_eq!(*settings.read().unwrap(), with_limit(2));
+}
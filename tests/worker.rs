@@ -0,0 +1,187 @@
+use std::sync::{Arc, Mutex, RwLock, mpsc};
+use std::time::Duration;
+
+use autoclicker::backend::{Backend, BackendError, NullBackend};
+use autoclicker::health::Health;
+use autoclicker::settings::{Action, MouseButton, Settings};
+use autoclicker::worker::{WorkerCommand, WorkerHandle, WorkerReport, start_click_worker};
+
+const WAIT: Duration = Duration::from_secs(5);
+
+fn fast(limit: Option<u64>) -> Arc<RwLock<Settings>> {
+    Arc::new(RwLock::new(Settings {
+        interval: "1ms".parse().unwrap(),
+        click_limit: limit,
+        ..Settings::default()
+    }))
+}
+
+/// Skips health reports and returns the next other report.
+fn next(rx: &mpsc::Receiver<WorkerReport>) -> WorkerReport {
+    loop {
+        match rx.recv_timeout(WAIT).unwrap() {
+            WorkerReport::Injector(Health::Ok) => {}
+            report => return report,
+        }
+    }
+}
+
+/// Waits for the click with number `n`.
+fn wait_for_click(rx: &mpsc::Receiver<WorkerReport>, n: u64) {
+    loop {
+        match next(rx) {
+            WorkerReport::Clicked(count) if count >= n => return,
+            WorkerReport::Clicked(_) => {}
+            report => panic!("expected clicks, got {report:?}"),
+        }
+    }
+}
+
+/// Stops clicking and returns the last click count reported since the last look.
+fn stop(worker: &WorkerHandle, rx: &mpsc::Receiver<WorkerReport>) -> Option<u64> {
+    worker.send(WorkerCommand::Stop);
+    // Clicks sent before the stop was handled may still be queued.
+    std::thread::sleep(Duration::from_millis(50));
+    let last = rx
+        .try_iter()
+        .filter_map(|r| match r {
+            WorkerReport::Clicked(count) => Some(count),
+            _ => None,
+        })
+        .last();
+    std::thread::sleep(Duration::from_millis(50));
+    assert_eq!(rx.try_iter().count(), 0, "clicked after stopping");
+    last
+}
+
+#[test]
+fn start_stop_and_resume_keep_counting() {
+    let (tx, rx) = mpsc::channel();
+    let worker = start_click_worker(fast(None), NullBackend, tx);
+
+    worker.send(WorkerCommand::Start);
+    wait_for_click(&rx, 3);
+    let stopped_at = stop(&worker, &rx).unwrap_or(3);
+
+    worker.send(WorkerCommand::Resume);
+    assert_eq!(next(&rx), WorkerReport::Clicked(stopped_at + 1));
+    stop(&worker, &rx);
+
+    worker.send(WorkerCommand::Start);
+    assert_eq!(next(&rx), WorkerReport::Clicked(1));
+    worker.send(WorkerCommand::Shutdown);
+}
+
+#[test]
+fn stops_itself_at_the_limit() {
+    let (tx, rx) = mpsc::channel();
+    let worker = start_click_worker(fast(Some(3)), NullBackend, tx);
+
+    worker.send(WorkerCommand::Start);
+    for n in 1..=3 {
+        assert_eq!(next(&rx), WorkerReport::Clicked(n));
+    }
+    assert_eq!(next(&rx), WorkerReport::LimitReached);
+    std::thread::sleep(Duration::from_millis(50));
+    assert_eq!(rx.try_iter().count(), 0);
+    worker.send(WorkerCommand::Shutdown);
+}
+
+/// Remembers every action performed.
+struct Recorder(Arc<Mutex<Vec<Action>>>);
+
+impl Backend for Recorder {
+    fn name(&self) -> &'static str {
+        "recorder"
+    }
+
+    fn perform(&mut self, action: &Action) -> Result<(), BackendError> {
+        self.0.lock().unwrap().push(action.clone());
+        Ok(())
+    }
+}
+
+#[test]
+fn settings_changed_while_clicking_applies_to_the_next_click() {
+    let settings = fast(None);
+    let performed = Arc::new(Mutex::new(Vec::new()));
+    let (tx, rx) = mpsc::channel();
+    let worker = start_click_worker(Arc::clone(&settings), Recorder(Arc::clone(&performed)), tx);
+
+    worker.send(WorkerCommand::Start);
+    wait_for_click(&rx, 2);
+    settings.write().unwrap().action = Action::Mouse(MouseButton::Right);
+    worker.send(WorkerCommand::SettingsChanged);
+    let changed_at = performed.lock().unwrap().len();
+    // Clicks that were due already may still go out with the old action.
+    let mut n = changed_at as u64;
+    while performed.lock().unwrap().last() != Some(&Action::Mouse(MouseButton::Right)) {
+        n += 1;
+        wait_for_click(&rx, n);
+    }
+    stop(&worker, &rx);
+
+    let performed = performed.lock().unwrap();
+    assert_eq!(performed[0], Action::Mouse(MouseButton::Left));
+    assert_eq!(performed.last(), Some(&Action::Mouse(MouseButton::Right)));
+    // The change did not restart or reset the count.
+    assert!(performed.len() > changed_at);
+    worker.send(WorkerCommand::Shutdown);
+}
+
+#[test]
+fn lowering_the_limit_while_clicking_stops_the_worker() {
+    let settings = fast(None);
+    let (tx, rx) = mpsc::channel();
+    let worker = start_click_worker(Arc::clone(&settings), NullBackend, tx);
+
+    worker.send(WorkerCommand::Start);
+    wait_for_click(&rx, 5);
+    settings.write().unwrap().click_limit = Some(2);
+    worker.send(WorkerCommand::SettingsChanged);
+    loop {
+        match next(&rx) {
+            WorkerReport::Clicked(_) => {}
+            WorkerReport::LimitReached => break,
+            report => panic!("unexpected {report:?}"),
+        }
+    }
+    worker.send(WorkerCommand::Shutdown);
+}
+
+/// Fails its first `n` actions.
+struct FailsFirst(u32);
+
+impl Backend for FailsFirst {
+    fn name(&self) -> &'static str {
+        "flaky"
+    }
+
+    fn perform(&mut self, _: &Action) -> Result<(), BackendError> {
+        if self.0 == 0 {
+            return Ok(());
+        }
+        self.0 -= 1;
+        Err(BackendError("busy".to_string()))
+    }
+}
+
+#[test]
+fn failed_actions_are_not_counted() {
+    let (tx, rx) = mpsc::channel();
+    let worker = start_click_worker(fast(Some(2)), FailsFirst(2), tx);
+
+    worker.send(WorkerCommand::Start);
+    for failed in 1..=2 {
+        assert_eq!(
+            next(&rx),
+            WorkerReport::Injector(Health::Degraded(format!(
+                "flaky backend: busy ({failed} failed in a row)"
+            )))
+        );
+    }
+    assert_eq!(next(&rx), WorkerReport::Clicked(1));
+    assert_eq!(next(&rx), WorkerReport::Clicked(2));
+    assert_eq!(next(&rx), WorkerReport::LimitReached);
+    worker.send(WorkerCommand::Shutdown);
+}
@@ -0,0 +1,167 @@
+//! Injects into a window on a real X server. Needs `DISPLAY` with the XTEST extension,
+//! so it only runs on request: `xvfb-run -a cargo test --test xtest_backend -- --ignored`.
+#![cfg(target_os = "linux")]
+
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use autoclicker::backend::{Backend, XTestBackend};
+use autoclicker::settings::{Action, Key, MouseButton};
+use x11rb::connection::Connection;
+use x11rb::protocol::Event;
+use x11rb::protocol::xproto::{
+    ConnectionExt as _, CreateWindowAux, EventMask, InputFocus, KeyButMask, Keycode, Keysym,
+    Window, WindowClass,
+};
+use x11rb::rust_connection::RustConnection;
+use x11rb::wrapper::ConnectionExt as _;
+
+const TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Injected input goes to whichever test window is on top, so tests take turns.
+static DISPLAY: Mutex<()> = Mutex::new(());
+
+/// A mapped, focused window covering the screen, with the pointer inside it.
+struct Target {
+    conn: RustConnection,
+    window: Window,
+    _turn: MutexGuard<'static, ()>,
+}
+
+impl Target {
+    fn new() -> Self {
+        let turn = DISPLAY.lock().unwrap_or_else(|e| e.into_inner());
+        let (conn, screen) = x11rb::connect(None).expect("connect to DISPLAY");
+        let root = &conn.setup().roots[screen];
+        let window = conn.generate_id().unwrap();
+        let events = EventMask::BUTTON_PRESS | EventMask::KEY_PRESS | EventMask::EXPOSURE;
+        conn.create_window(
+            0,
+            window,
+            root.root,
+            0,
+            0,
+            root.width_in_pixels,
+            root.height_in_pixels,
+            0,
+            WindowClass::INPUT_OUTPUT,
+            0,
+            &CreateWindowAux::new().event_mask(events),
+        )
+        .unwrap();
+        conn.map_window(window).unwrap();
+        conn.flush().unwrap();
+        let target = Self {
+            conn,
+            window,
+            _turn: turn,
+        };
+        target.wait_for(|e| matches!(e, Event::Expose(_)));
+
+        target
+            .conn
+            .set_input_focus(InputFocus::POINTER_ROOT, window, x11rb::CURRENT_TIME)
+            .unwrap();
+        target
+            .conn
+            .warp_pointer(x11rb::NONE, window, 0, 0, 0, 0, 10, 10)
+            .unwrap();
+        target.conn.sync().unwrap();
+        target
+    }
+
+    fn wait_for(&self, mut want: impl FnMut(&Event) -> bool) -> Event {
+        let deadline = Instant::now() + TIMEOUT;
+        while Instant::now() < deadline {
+            match self.conn.poll_for_event().unwrap() {
+                Some(event) if want(&event) => return event,
+                Some(_) => {}
+                None => std::thread::sleep(Duration::from_millis(5)),
+            }
+        }
+        panic!("expected event never arrived");
+    }
+
+    fn keysym(&self, keycode: Keycode) -> Keysym {
+        let reply = self
+            .conn
+            .get_keyboard_mapping(keycode, 1)
+            .unwrap()
+            .reply()
+            .unwrap();
+        reply.keysyms[0]
+    }
+}
+
+fn inject(backend: &mut XTestBackend, action: Action) {
+    backend.perform(&action).unwrap();
+    backend.flush().unwrap();
+}
+
+#[test]
+#[ignore = "needs an X server with XTEST, e.g. under xvfb-run"]
+fn clicks_buttons_one_to_nine() {
+    let target = Target::new();
+    let mut backend = XTestBackend::default();
+
+    for button in MouseButton::ALL {
+        inject(&mut backend, Action::Mouse(button));
+        let Event::ButtonPress(event) = target.wait_for(|e| matches!(e, Event::ButtonPress(_)))
+        else {
+            unreachable!()
+        };
+        assert_eq!(event.detail, button.x11_button(), "{button:?}");
+        assert_eq!(event.event, target.window);
+    }
+}
+
+#[test]
+#[ignore = "needs an X server with XTEST, e.g. under xvfb-run"]
+fn enter_is_the_return_key_not_a_newline() {
+    let target = Target::new();
+    let mut backend = XTestBackend::default();
+
+    inject(&mut backend, Action::Keyboard(Key::Enter));
+    let Event::KeyPress(event) = target.wait_for(|e| matches!(e, Event::KeyPress(_))) else {
+        unreachable!()
+    };
+    assert_eq!(target.keysym(event.detail), 0xff0d);
+}
+
+#[test]
+#[ignore = "needs an X server with XTEST, e.g. under xvfb-run"]
+fn letters_keep_their_case_through_shift() {
+    let target = Target::new();
+    let mut backend = XTestBackend::default();
+
+    inject(&mut backend, Action::Keyboard(Key::Char('a')));
+    let Event::KeyPress(lower) = target.wait_for(|e| matches!(e, Event::KeyPress(_))) else {
+        unreachable!()
+    };
+    assert_eq!(target.keysym(lower.detail), u32::from('a'));
+    assert!(!lower.state.contains(KeyButMask::SHIFT));
+
+    inject(&mut backend, Action::Keyboard(Key::Char('A')));
+    // The first press is Shift itself.
+    let Event::KeyPress(upper) =
+        target.wait_for(|e| matches!(e, Event::KeyPress(k) if k.state.contains(KeyButMask::SHIFT)))
+    else {
+        unreachable!()
+    };
+    assert_eq!(upper.detail, lower.detail);
+}
+
+#[test]
+#[ignore = "needs an X server with XTEST, e.g. under xvfb-run"]
+fn many_actions_between_flushes_all_arrive() {
+    let target = Target::new();
+    let mut backend = XTestBackend::default();
+
+    for _ in 0..200 {
+        backend.perform(&Action::Mouse(MouseButton::Left)).unwrap();
+    }
+    backend.flush().unwrap();
+    for _ in 0..200 {
+        target.wait_for(|e| matches!(e, Event::ButtonPress(_)));
+    }
+}
@@ -0,0 +1,155 @@
+//! Runs the ydotool backend against a fake daemon socket that records what it receives.
+#![cfg(target_os = "linux")]
+
+use std::os::unix::net::UnixDatagram;
+use std::path::PathBuf;
+
+use autoclicker::backend::ydotool_backend::EVENT_SIZE;
+use autoclicker::backend::{Backend, YdotoolBackend};
+use autoclicker::settings::{Action, Key, MouseButton};
+
+const EV_SYN: u16 = 0;
+const EV_KEY: u16 = 1;
+const EV_REL: u16 = 2;
+const BTN_LEFT: u16 = 0x110;
+const KEY_A: u16 = 30;
+const KEY_LEFTSHIFT: u16 = 42;
+const KEY_VOLUMEUP: u16 = 115;
+const REL_WHEEL: u16 = 8;
+
+/// A bound datagram socket standing in for `ydotoold`.
+struct FakeDaemon {
+    socket: UnixDatagram,
+    path: PathBuf,
+}
+
+impl FakeDaemon {
+    fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!(
+            "autoclicker-ydotool-{}-{name}.sock",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        let socket = UnixDatagram::bind(&path).unwrap();
+        socket.set_nonblocking(true).unwrap();
+        Self { socket, path }
+    }
+
+    /// `(type, code, value)` of every event received so far, sync reports included.
+    fn received(&self) -> Vec<(u16, u16, i32)> {
+        let mut events = Vec::new();
+        let mut buf = [0u8; 64];
+        while let Ok(len) = self.socket.recv(&mut buf) {
+            assert_eq!(len, EVENT_SIZE, "one input_event per datagram");
+            let fields = &buf[EVENT_SIZE - 8..EVENT_SIZE];
+            assert!(buf[..EVENT_SIZE - 8].iter().all(|&b| b == 0));
+            events.push((
+                u16::from_ne_bytes([fields[0], fields[1]]),
+                u16::from_ne_bytes([fields[2], fields[3]]),
+                i32::from_ne_bytes([fields[4], fields[5], fields[6], fields[7]]),
+            ));
+        }
+        events
+    }
+}
+
+impl Drop for FakeDaemon {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+const SYN: (u16, u16, i32) = (EV_SYN, 0, 0);
+
+#[test]
+fn click_is_a_press_and_release_each_followed_by_a_sync() {
+    let daemon = FakeDaemon::new("click");
+    let mut backend = YdotoolBackend::new(&daemon.path);
+
+    backend.perform(&Action::Mouse(MouseButton::Left)).unwrap();
+    assert_eq!(
+        daemon.received(),
+        [(EV_KEY, BTN_LEFT, 1), SYN, (EV_KEY, BTN_LEFT, 0), SYN]
+    );
+}
+
+#[test]
+fn uppercase_letters_are_typed_with_shift() {
+    let daemon = FakeDaemon::new("shift");
+    let mut backend = YdotoolBackend::new(&daemon.path);
+
+    backend.perform(&Action::Keyboard(Key::Char('a'))).unwrap();
+    assert_eq!(
+        daemon.received(),
+        [(EV_KEY, KEY_A, 1), SYN, (EV_KEY, KEY_A, 0), SYN]
+    );
+
+    backend.perform(&Action::Keyboard(Key::Char('A'))).unwrap();
+    assert_eq!(
+        daemon.received(),
+        [
+            (EV_KEY, KEY_LEFTSHIFT, 1),
+            SYN,
+            (EV_KEY, KEY_A, 1),
+            SYN,
+            (EV_KEY, KEY_A, 0),
+            SYN,
+            (EV_KEY, KEY_LEFTSHIFT, 0),
+            SYN,
+        ]
+    );
+}
+
+#[test]
+fn raw_keys_send_their_code_as_is() {
+    let daemon = FakeDaemon::new("raw");
+    let mut backend = YdotoolBackend::new(&daemon.path);
+
+    backend
+        .perform(&Action::Keyboard(Key::raw(KEY_VOLUMEUP)))
+        .unwrap();
+    assert_eq!(
+        daemon.received(),
+        [
+            (EV_KEY, KEY_VOLUMEUP, 1),
+            SYN,
+            (EV_KEY, KEY_VOLUMEUP, 0),
+            SYN
+        ]
+    );
+}
+
+#[test]
+fn scrolling_moves_the_wheel_axis() {
+    let daemon = FakeDaemon::new("scroll");
+    let mut backend = YdotoolBackend::new(&daemon.path);
+
+    backend
+        .perform(&Action::Mouse(MouseButton::ScrollDown))
+        .unwrap();
+    assert_eq!(daemon.received(), [(EV_REL, REL_WHEEL, -1), SYN]);
+}
+
+#[test]
+fn missing_daemon_fails_to_init_and_names_the_socket() {
+    let path = std::env::temp_dir().join("autoclicker-ydotool-does-not-exist.sock");
+    let mut backend = YdotoolBackend::new(&path);
+
+    let err = backend.init().unwrap_err();
+    assert!(err.0.contains(&path.display().to_string()), "{err}");
+}
+
+#[test]
+fn reconnects_after_the_daemon_restarts() {
+    let daemon = FakeDaemon::new("restart");
+    let mut backend = YdotoolBackend::new(&daemon.path);
+    backend.init().unwrap();
+    drop(daemon);
+
+    assert!(backend.perform(&Action::Mouse(MouseButton::Left)).is_err());
+    backend.reset();
+
+    let daemon = FakeDaemon::new("restart");
+    backend.perform(&Action::Mouse(MouseButton::Left)).unwrap();
+    assert_eq!(daemon.received().len(), 4);
+}